@@ -155,7 +155,7 @@ fn execute_cryptocurrency(db: Box<Database>, c: &mut Criterion) {
         }
 
         fn execute(&self, view: &mut Fork) -> ExecutionResult {
-            let mut index = ProofMapIndex::new("balances_txs", view);
+            let mut index = ProofMapIndex::new("cryptocurrency.balances_txs", view);
             let from_balance = index.get(self.from()).unwrap_or(0_u64);
             let to_balance = index.get(self.to()).unwrap_or(0_u64);
             index.put(self.from(), from_balance - 1);
@@ -99,6 +99,28 @@ fn merkle_patricia_table_merge_insert<T: Database>(b: &mut Bencher, db: &T) {
     });
 }
 
+// Inserts a batch of entries into a map already containing entries, either with
+// single `put` calls or with `put_all`.
+fn merkle_patricia_table_batch_insert<T: Database>(b: &mut Bencher, db: &T, batched: bool) {
+    let data = generate_random_kv(4000);
+    let (existing, batch) = data.split_at(2000);
+    let mut fork = db.fork();
+    ProofMapIndex::new(NAME, &mut fork).put_all(existing.iter().cloned());
+    db.merge(fork.into_patch()).unwrap();
+
+    b.iter(|| {
+        let mut fork = db.fork();
+        let mut table = ProofMapIndex::new(NAME, &mut fork);
+        if batched {
+            table.put_all(batch.iter().cloned());
+        } else {
+            for item in batch {
+                table.put(&item.0, item.1.clone());
+            }
+        }
+    });
+}
+
 fn create_rocksdb(tempdir: &TempDir) -> RocksDB {
     let options = DbOptions::default();
     RocksDB::open(tempdir.path(), &options).unwrap()
@@ -122,6 +144,18 @@ fn bench_merkle_patricia_table_insertion_fork_rocksdb(b: &mut Bencher) {
     merkle_patricia_table_fork_insert(b, &db);
 }
 
+fn bench_merkle_patricia_table_single_insertion_rocksdb(b: &mut Bencher) {
+    let tempdir = TempDir::new("exonum").unwrap();
+    let db = create_rocksdb(&tempdir);
+    merkle_patricia_table_batch_insert(b, &db, false);
+}
+
+fn bench_merkle_patricia_table_batch_insertion_rocksdb(b: &mut Bencher) {
+    let tempdir = TempDir::new("exonum").unwrap();
+    let db = create_rocksdb(&tempdir);
+    merkle_patricia_table_batch_insert(b, &db, true);
+}
+
 pub fn bench_storage(c: &mut Criterion) {
     ::exonum::crypto::init();
 
@@ -134,4 +168,12 @@ pub fn bench_storage(c: &mut Criterion) {
         "insert with merge merkle table",
         bench_merkle_patricia_table_insertion_merge_rocksdb,
     );
+    c.bench_function(
+        "insert 2000 entries one by one into merkle table",
+        bench_merkle_patricia_table_single_insertion_rocksdb,
+    );
+    c.bench_function(
+        "insert 2000 entries as a batch into merkle table",
+        bench_merkle_patricia_table_batch_insertion_rocksdb,
+    );
 }
@@ -335,6 +335,7 @@ impl ApiAggregator {
     fn explorer_api(shared_node_state: SharedNodeState) -> ServiceApiBuilder {
         let mut builder = ServiceApiBuilder::new();
         self::node::public::ExplorerApi::wire(builder.public_scope(), shared_node_state);
+        self::node::private::MultisigApi::wire(builder.private_scope());
         builder
     }
 
@@ -17,7 +17,9 @@
 //! Private API includes requests that are available only to the blockchain
 //! administrators, e.g. view the list of services on the current node.
 
-use std::{collections::HashMap, net::SocketAddr};
+pub use self::multisig::MultisigApi;
+
+use std::{collections::HashMap, net::SocketAddr, path::{Component, Path, PathBuf}};
 
 use api::{Error as ApiError, ServiceApiScope, ServiceApiState};
 use blockchain::{Service, SharedNodeState};
@@ -25,6 +27,8 @@ use crypto::PublicKey;
 use messages::PROTOCOL_MAJOR_VERSION;
 use node::{ConnectInfo, ExternalMessage};
 
+pub mod multisig;
+
 /// Short information about the service.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ServiceInfo {
@@ -101,6 +105,30 @@ struct ConsensusEnabledQuery {
     enabled: bool,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct BackupQuery {
+    path: PathBuf,
+}
+
+// Resolves the path of a backup requested via the API. The path must be relative
+// and must not leave the backup directory set in the node configuration.
+fn resolve_backup_path(backup_dir: Option<&PathBuf>, path: &Path) -> Result<PathBuf, ApiError> {
+    let backup_dir = backup_dir.ok_or_else(|| {
+        ApiError::BadRequest("Backup directory is not set in the node configuration".to_owned())
+    })?;
+    let is_nested = path.components().all(|component| match component {
+        Component::Normal(_) => true,
+        _ => false,
+    });
+    if !is_nested || path.as_os_str().is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "Backup path {} must be relative to the backup directory",
+            path.display()
+        )));
+    }
+    Ok(backup_dir.join(path))
+}
+
 /// Private system API.
 #[derive(Clone, Debug)]
 pub struct SystemApi {
@@ -125,7 +153,10 @@ impl SystemApi {
             .handle_is_consensus_enabled("v1/consensus_enabled", api_scope)
             .handle_set_consensus_enabled("v1/consensus_enabled", api_scope)
             .handle_shutdown("v1/shutdown", api_scope)
-            .handle_rebroadcast("v1/rebroadcast", api_scope);
+            .handle_rebroadcast("v1/rebroadcast", api_scope)
+            .handle_backup("v1/backup", api_scope)
+            .handle_storage_stats("v1/storage/stats", api_scope)
+            .handle_cache_stats("v1/storage/cache", api_scope);
         api_scope
     }
 
@@ -230,4 +261,51 @@ impl SystemApi {
         });
         self
     }
+
+    fn handle_backup(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        let backup_dir = self.shared_api_state.backup_dir.clone();
+        api_scope.endpoint_mut(name, move |state: &ServiceApiState, query: BackupQuery| {
+            let path = resolve_backup_path(backup_dir.as_ref(), &query.path)?;
+            state
+                .blockchain()
+                .create_backup(&path)
+                .map_err(|e| ApiError::InternalError(e.to_string().into()))
+        });
+        self
+    }
+
+    fn handle_storage_stats(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(name, move |state: &ServiceApiState, _query: ()| {
+            Ok(state.blockchain().storage_stats())
+        });
+        self
+    }
+
+    fn handle_cache_stats(self, name: &'static str, api_scope: &mut ServiceApiScope) -> Self {
+        api_scope.endpoint(name, move |state: &ServiceApiState, _query: ()| {
+            Ok(state.blockchain().cache_stats())
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::resolve_backup_path;
+
+    #[test]
+    fn test_backup_path_within_backup_dir() {
+        let backup_dir = PathBuf::from("/var/lib/exonum/backups");
+        assert_eq!(
+            resolve_backup_path(Some(&backup_dir), Path::new("daily/1")).unwrap(),
+            backup_dir.join("daily/1")
+        );
+
+        for path in &["", "/tmp/backup", "../backup", "daily/../../backup", "./backup"] {
+            assert!(resolve_backup_path(Some(&backup_dir), Path::new(path)).is_err());
+        }
+        assert!(resolve_backup_path(None, Path::new("backup")).is_err());
+    }
 }
@@ -0,0 +1,201 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Private API for collecting partial signatures of multisignature transactions.
+
+use std::{
+    collections::{HashMap, VecDeque}, sync::{Arc, Mutex},
+};
+
+use api::{Error as ApiError, ServiceApiScope, ServiceApiState};
+use blockchain::Schema;
+use crypto::{CryptoHash, Hash};
+use messages::{MultisigStatement, PartialSignature, SignatureSet, ThresholdPolicy};
+use storage::Snapshot;
+
+/// The maximum number of statements for which partial signatures are collected by the node.
+/// If the limit is exceeded, the signatures of the oldest statement are dropped.
+pub const MAX_MULTISIG_STATEMENTS: usize = 1000;
+
+/// Partial signatures of the statement of a multisignature transaction submitted to the node.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MultisigSubmission {
+    /// Signed statement.
+    pub statement: MultisigStatement,
+    /// Threshold policy of the statement.
+    pub policy: ThresholdPolicy,
+    /// Partial signatures of the statement.
+    pub signatures: Vec<PartialSignature>,
+}
+
+/// Query parameters for the partial signatures of a statement.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MultisigQuery {
+    /// Hash of the signed statement.
+    pub statement_hash: Hash,
+}
+
+impl MultisigQuery {
+    /// Creates a new query for the signatures of the statement with the given hash.
+    pub fn new(statement_hash: Hash) -> Self {
+        Self { statement_hash }
+    }
+}
+
+/// Partial signatures of multisignature transactions collected off-chain by the node.
+///
+/// The signatures are kept in memory, so they are not shared with the other nodes
+/// and are lost when the node is restarted. The signatures of a statement are dropped
+/// once the sequence number of the statement is used by its policy.
+#[derive(Debug, Clone, Default)]
+pub struct MultisigCollector {
+    inner: Arc<Mutex<MultisigStatements>>,
+}
+
+#[derive(Debug, Default)]
+struct MultisigStatements {
+    sets: HashMap<Hash, SignatureSet>,
+    // Statement hashes in the order of the first submission.
+    order: VecDeque<Hash>,
+}
+
+impl MultisigStatements {
+    // Drops the signatures of the statements which have already been executed.
+    fn remove_executed(&mut self, snapshot: &dyn Snapshot) {
+        let schema = Schema::new(snapshot);
+        let sequences = schema.multisig_sequences();
+        let executed: Vec<Hash> = self.sets
+            .iter()
+            .filter(|&(_, set)| {
+                let statement = set.statement();
+                is_executed(statement, sequences.get(statement.policy_hash()))
+            })
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in &executed {
+            self.sets.remove(hash);
+        }
+        let sets = &self.sets;
+        self.order.retain(|hash| sets.contains_key(hash));
+    }
+}
+
+// Checks whether the sequence number of the statement has been used by its policy.
+fn is_executed(statement: &MultisigStatement, last_sequence: Option<u64>) -> bool {
+    last_sequence.map_or(false, |last| statement.sequence() <= last)
+}
+
+impl MultisigCollector {
+    /// Adds the partial signatures to the ones collected for the statement and returns
+    /// the collected signatures.
+    ///
+    /// Returns an error if the statement has already been executed, if the policy differs
+    /// from the one of the statement or if any of the signatures is not valid. In this case,
+    /// none of the signatures are added.
+    pub fn submit(
+        &self,
+        snapshot: &dyn Snapshot,
+        submission: MultisigSubmission,
+    ) -> Result<SignatureSet, ApiError> {
+        let mut statements = self.inner.lock().expect("Expected mutex lock");
+        statements.remove_executed(snapshot);
+        let MultisigSubmission {
+            statement,
+            policy,
+            signatures,
+        } = submission;
+
+        let last_sequence = Schema::new(snapshot)
+            .multisig_sequences()
+            .get(statement.policy_hash());
+        if is_executed(&statement, last_sequence) {
+            return Err(ApiError::BadRequest(format!(
+                "Sequence number {} of the policy has already been used",
+                statement.sequence()
+            )));
+        }
+
+        let statement_hash = statement.hash();
+        let mut set = match statements.sets.get(&statement_hash) {
+            Some(set) => set.clone(),
+            None => SignatureSet::new(statement, policy.clone())
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?,
+        };
+        if set.policy() != &policy {
+            return Err(ApiError::BadRequest(format!(
+                "Signatures of statement {:?} are collected for another policy",
+                statement_hash
+            )));
+        }
+        for signature in signatures {
+            set.add(signature)
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        }
+
+        if !statements.sets.contains_key(&statement_hash) {
+            if statements.order.len() >= MAX_MULTISIG_STATEMENTS {
+                let oldest = statements.order.pop_front().unwrap();
+                statements.sets.remove(&oldest);
+            }
+            statements.order.push_back(statement_hash);
+        }
+        statements.sets.insert(statement_hash, set.clone());
+        Ok(set)
+    }
+
+    /// Returns the partial signatures collected for the statement with the given hash.
+    pub fn signatures(&self, statement_hash: &Hash) -> Option<SignatureSet> {
+        let statements = self.inner.lock().expect("Expected mutex lock");
+        statements.sets.get(statement_hash).cloned()
+    }
+}
+
+/// Private API for collecting partial signatures of multisignature transactions.
+#[derive(Debug, Clone, Copy)]
+pub struct MultisigApi;
+
+impl MultisigApi {
+    /// Returns the partial signatures collected for a statement of a multisignature transaction.
+    pub fn multisig_signatures(
+        collector: &MultisigCollector,
+        query: MultisigQuery,
+    ) -> Result<SignatureSet, ApiError> {
+        collector.signatures(&query.statement_hash).ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Signatures of statement {:?} are not found",
+                query.statement_hash
+            ))
+        })
+    }
+
+    /// Adds multisignature API endpoints to the corresponding scope.
+    pub fn wire(api_scope: &mut ServiceApiScope) -> &mut ServiceApiScope {
+        let collector = MultisigCollector::default();
+        let submit_collector = collector.clone();
+        api_scope
+            .endpoint(
+                "v1/multisig",
+                move |_state: &ServiceApiState, query: MultisigQuery| {
+                    Self::multisig_signatures(&collector, query)
+                },
+            )
+            .endpoint_mut(
+                "v1/multisig",
+                move |state: &ServiceApiState, submission: MultisigSubmission| {
+                    let snapshot = state.snapshot();
+                    submit_collector.submit(&*snapshot, submission)
+                },
+            )
+    }
+}
@@ -27,11 +27,12 @@ use api::{
     websocket::{Server, Session}, Error as ApiError, ServiceApiBackend, ServiceApiScope,
     ServiceApiState,
 };
-use blockchain::{Block, SharedNodeState};
+use blockchain::{Block, BlockProof, Schema, SharedNodeState};
 use crypto::Hash;
 use explorer::{BlockchainExplorer, TransactionInfo};
 use helpers::Height;
 use messages::Precommit;
+use storage::MapProof;
 
 /// The maximum number of blocks to return per blocks request, in this way
 /// the parameter limits the maximum execution time for such requests.
@@ -99,6 +100,26 @@ impl TransactionQuery {
     }
 }
 
+/// Query parameters for the proof of a service table at a certain height.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TableProofQuery {
+    /// The height of the block which state is requested.
+    pub height: Height,
+    /// Identifier of the service owning the table.
+    pub service_id: u16,
+    /// Index of the table in the vector returned by the `state_hash` method of the service.
+    pub table_idx: usize,
+}
+
+/// Proof of the root hash of a service table at a certain height.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableProof {
+    /// The block at the requested height together with its precommits.
+    pub block_proof: BlockProof,
+    /// Proof of the table root hash tied to the `state_hash` of the block.
+    pub to_table: MapProof<Hash, Hash>,
+}
+
 /// Exonum blockchain explorer API.
 #[derive(Debug, Clone, Copy)]
 pub struct ExplorerApi;
@@ -144,27 +165,70 @@ impl ExplorerApi {
     }
 
     /// Returns the content for a block at a specific height.
+    ///
+    /// Returns an error with the `pruned` type if the precommits of the block have been
+    /// pruned from the storage.
     pub fn block(
         state: &ServiceApiState,
         query: BlockQuery,
     ) -> Result<Option<BlockInfo>, ApiError> {
-        Ok(BlockchainExplorer::new(state.blockchain())
-            .block(query.height)
-            .map(From::from))
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        if explorer.is_pruned(query.height) {
+            return Err(pruned_error(query.height));
+        }
+        Ok(explorer.block(query.height).map(From::from))
     }
 
     /// Searches for a transaction, either committed or uncommitted, by the hash.
+    ///
+    /// Returns an error with the `pruned` type if the transaction has been committed,
+    /// but its body has been pruned from the storage.
     pub fn transaction_info(
         state: &ServiceApiState,
         query: TransactionQuery,
     ) -> Result<TransactionInfo, ApiError> {
-        BlockchainExplorer::new(state.blockchain())
-            .transaction(&query.hash)
+        let explorer = BlockchainExplorer::new(state.blockchain());
+        explorer.transaction(&query.hash).ok_or_else(|| {
+            let description = if explorer.is_transaction_pruned(&query.hash) {
+                json!({ "type": "pruned" })
+            } else {
+                json!({ "type": "unknown" })
+            };
+            let description = serde_json::to_string(&description).unwrap();
+            debug!("{}", description);
+            ApiError::NotFound(description)
+        })
+    }
+
+    /// Returns the proof of a service table root hash at a specific height.
+    ///
+    /// The proof is built against the blockchain state right after the block at
+    /// the requested height has been committed, so it can be checked against
+    /// the `state_hash` of this block.
+    pub fn table_proof(
+        state: &ServiceApiState,
+        query: TableProofQuery,
+    ) -> Result<TableProof, ApiError> {
+        let snapshot = state
+            .blockchain()
+            .snapshot_at(query.height)
+            .ok_or_else(|| {
+                ApiError::NotFound(format!(
+                    "State at height {} is not available",
+                    query.height
+                ))
+            })?;
+        let schema = Schema::new(&snapshot);
+        let block_proof = schema
+            .block_and_precommits(query.height)
             .ok_or_else(|| {
-                let description = serde_json::to_string(&json!({ "type": "unknown" })).unwrap();
-                debug!("{}", description);
-                ApiError::NotFound(description)
-            })
+                ApiError::NotFound(format!("Block at height {} is not found", query.height))
+            })?;
+        let to_table = schema.get_proof_to_service_table(query.service_id, query.table_idx);
+        Ok(TableProof {
+            block_proof,
+            to_table,
+        })
     }
 
     /// Subscribes to block commits events.
@@ -210,9 +274,15 @@ impl ExplorerApi {
             .endpoint("v1/blocks", Self::blocks)
             .endpoint("v1/block", Self::block)
             .endpoint("v1/transactions", Self::transaction_info)
+            .endpoint("v1/table_proof", Self::table_proof)
     }
 }
 
+fn pruned_error(height: Height) -> ApiError {
+    let description = json!({ "type": "pruned", "height": height });
+    ApiError::NotFound(serde_json::to_string(&description).unwrap())
+}
+
 impl<'a> From<::explorer::BlockInfo<'a>> for BlockInfo {
     fn from(inner: ::explorer::BlockInfo<'a>) -> Self {
         Self {
@@ -0,0 +1,205 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Backups of the blockchain storage.
+//!
+//! A backup is a directory containing a consistent copy of the database (the `db`
+//! subdirectory) and the `backup.toml` file with information about the latest block
+//! in the copy. This information is used to verify the database after restoration.
+
+use failure;
+
+use std::{fs, path::Path};
+
+use super::{Blockchain, Schema};
+use crypto::{CryptoHash, Hash};
+use helpers::{config::ConfigFile, Height};
+use storage::{open_database, Database, DbOptions, Snapshot};
+
+const BACKUP_DB_DIR: &str = "db";
+const BACKUP_INFO_FILE: &str = "backup.toml";
+
+/// Information about the latest block in a backup of the blockchain storage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// Height of the latest block.
+    pub height: Height,
+    /// Hash of the latest block.
+    pub block_hash: Hash,
+}
+
+impl BackupInfo {
+    /// Reads information about the latest block from the given snapshot, checking that
+    /// the block is stored consistently.
+    pub(crate) fn from_snapshot(snapshot: &dyn Snapshot) -> Result<Self, failure::Error> {
+        let schema = Schema::new(snapshot);
+        let hashes = schema.block_hashes_by_height();
+        let block_hash = match hashes.last() {
+            Some(hash) => hash,
+            None => bail!("Database does not contain blocks"),
+        };
+        let block = match schema.blocks().get(&block_hash) {
+            Some(block) => block,
+            None => bail!("Block with hash {:?} is absent in the database", block_hash),
+        };
+        if block.hash() != block_hash || block.height() != Height(hashes.len() - 1) {
+            bail!(
+                "Latest block in the database is inconsistent with its hash {:?}",
+                block_hash
+            );
+        }
+
+        Ok(Self {
+            height: block.height(),
+            block_hash,
+        })
+    }
+}
+
+impl Blockchain {
+    /// Creates a backup of the blockchain storage in the given directory. The node
+    /// does not need to be stopped while the backup is being created.
+    ///
+    /// See [`create_backup`] for details.
+    ///
+    /// [`create_backup`]: fn.create_backup.html
+    pub fn create_backup<P: AsRef<Path>>(&self, path: P) -> Result<BackupInfo, failure::Error> {
+        create_backup(&*self.db, path)
+    }
+}
+
+/// Creates a backup of the given database in the given directory.
+///
+/// The directory must not exist; it is created by this function and removed
+/// if the backup cannot be created.
+///
+/// # Errors
+///
+/// Returns an error if the database does not support checkpoints (see
+/// [`Database::create_checkpoint`]) or the backup cannot be written.
+///
+/// [`Database::create_checkpoint`]: ../storage/trait.Database.html#method.create_checkpoint
+pub fn create_backup<P: AsRef<Path>>(
+    db: &dyn Database,
+    path: P,
+) -> Result<BackupInfo, failure::Error> {
+    let path = path.as_ref();
+    if path.exists() {
+        bail!("Backup directory {} already exists", path.display());
+    }
+    fs::create_dir_all(path)?;
+
+    let info = write_backup(db, path).map_err(|e| {
+        if let Err(remove_error) = fs::remove_dir_all(path) {
+            warn!(
+                "Cannot remove incomplete backup directory {}: {}",
+                path.display(),
+                remove_error
+            );
+        }
+        e
+    })?;
+
+    info!(
+        "Created backup in {} with the latest block {:?} at height {}",
+        path.display(),
+        info.block_hash,
+        info.height
+    );
+    Ok(info)
+}
+
+// Writes the checkpoint of the database and the backup information into the existing
+// backup directory.
+fn write_backup(db: &dyn Database, path: &Path) -> Result<BackupInfo, failure::Error> {
+    let snapshot = db.create_checkpoint(&path.join(BACKUP_DB_DIR))?;
+    let info = BackupInfo::from_snapshot(&*snapshot)?;
+    ConfigFile::save(&info, path.join(BACKUP_INFO_FILE))?;
+    Ok(info)
+}
+
+/// Restores the backup created by [`create_backup`] into a new database at `db_path`.
+/// The database is opened with the backend selected in `options`, which must match
+/// the backend of the backed up database.
+///
+/// The latest block of the restored database is checked against the information
+/// saved in the backup.
+///
+/// # Errors
+///
+/// Returns an error if `db_path` already exists, if the backup cannot be read,
+/// or if the restored database does not match the backup.
+///
+/// [`create_backup`]: fn.create_backup.html
+pub fn restore_backup<P, Q>(
+    backup_path: P,
+    db_path: Q,
+    options: &DbOptions,
+) -> Result<BackupInfo, failure::Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (backup_path, db_path) = (backup_path.as_ref(), db_path.as_ref());
+    let expected: BackupInfo = ConfigFile::load(backup_path.join(BACKUP_INFO_FILE))?;
+    if db_path.exists() {
+        bail!("Database directory {} already exists", db_path.display());
+    }
+
+    fs::create_dir_all(db_path)?;
+    for entry in fs::read_dir(backup_path.join(BACKUP_DB_DIR))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), db_path.join(entry.file_name()))?;
+        }
+    }
+
+    let db = open_database(db_path, options)?;
+    let restored = BackupInfo::from_snapshot(&*db.snapshot())?;
+    if restored != expected {
+        bail!(
+            "Restored database does not match the backup: expected the latest block {:?} \
+             at height {}, found {:?} at height {}",
+            expected.block_hash,
+            expected.height,
+            restored.block_hash,
+            restored.height
+        );
+    }
+
+    info!(
+        "Restored backup from {} into {}",
+        backup_path.display(),
+        db_path.display()
+    );
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+    use storage::MemoryDB;
+
+    #[test]
+    fn test_failed_backup_is_removed() {
+        let dir = TempDir::new("exonum_failed_backup").unwrap();
+        let path = dir.path().join("backup");
+
+        // `MemoryDB` does not support checkpoints.
+        assert!(create_backup(&MemoryDB::new(), &path).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,470 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portable dumps of the blockchain storage.
+//!
+//! Unlike a backup, a dump does not depend on the database backend: it contains
+//! the raw entries of every index recorded in the indexes metadata together with
+//! the internal storage tables, so it can be imported into any [`Database`]
+//! implementation.
+//!
+//! A dump is a stream of records. It starts with the information about the latest
+//! block and the root hash of the state hash aggregator, which are checked after
+//! the import. Each table is followed by the hash of its records, and the dump ends
+//! with the hash of all the preceding data.
+//!
+//! [`Database`]: ../storage/trait.Database.html
+
+use byteorder::{ByteOrder, LittleEndian};
+use failure;
+
+use std::{io::{self, Read, Write}, mem};
+
+use super::{BackupInfo, Blockchain, Schema};
+use crypto::{Hash, HashStream, HASH_SIZE};
+use helpers::Height;
+use storage::{self, Database, Snapshot};
+
+// Dump header: magic bytes followed by the format version.
+const DUMP_MAGIC: &[u8] = b"EXONUM-DUMP";
+const DUMP_FORMAT_VERSION: u32 = 1;
+
+// Tags of the dump records.
+const END_TAG: u8 = 0;
+const TABLE_TAG: u8 = 1;
+const ENTRY_TAG: u8 = 2;
+const TABLE_END_TAG: u8 = 3;
+
+// Maximum number of entries merged into the database at once during the import.
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+/// Information about the blockchain state recorded in a dump.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpInfo {
+    /// Height of the latest block.
+    pub height: Height,
+    /// Hash of the latest block.
+    pub block_hash: Hash,
+    /// Root hash of the state hash aggregator.
+    pub state_hash: Hash,
+}
+
+impl DumpInfo {
+    fn from_snapshot(snapshot: &dyn Snapshot) -> Result<Self, failure::Error> {
+        let backup = BackupInfo::from_snapshot(snapshot)?;
+        let state_hash = Schema::new(snapshot).state_hash_aggregator().merkle_root();
+        Ok(Self {
+            height: backup.height,
+            block_hash: backup.block_hash,
+            state_hash,
+        })
+    }
+}
+
+impl Blockchain {
+    /// Writes a portable dump of the blockchain storage. The node does not need
+    /// to be stopped while the dump is being written.
+    ///
+    /// See [`export_dump`] for details.
+    ///
+    /// [`export_dump`]: fn.export_dump.html
+    pub fn export_dump<W: Write>(&self, writer: W) -> Result<DumpInfo, failure::Error> {
+        export_dump(&*self.db, writer)
+    }
+}
+
+/// Writes a portable dump of a snapshot of the given database.
+///
+/// # Errors
+///
+/// Returns an error if the database does not contain blocks or the dump cannot
+/// be written.
+pub fn export_dump<W: Write>(db: &dyn Database, writer: W) -> Result<DumpInfo, failure::Error> {
+    let snapshot = db.snapshot();
+    let info = DumpInfo::from_snapshot(&*snapshot)?;
+
+    let mut writer = DumpWriter::new(writer);
+    writer.write(DUMP_MAGIC)?;
+    writer.write_u32(DUMP_FORMAT_VERSION)?;
+    writer.write_u64(info.height.0)?;
+    writer.write(info.block_hash.as_ref())?;
+    writer.write(info.state_hash.as_ref())?;
+
+    let tables = storage::table_names(&*snapshot);
+    for name in &tables {
+        writer.start_table();
+        writer.write_u8(TABLE_TAG)?;
+        writer.write_bytes(name.as_bytes())?;
+
+        let mut entries = 0_u64;
+        let mut iter = snapshot.iter(name, &[]);
+        while let Some((key, value)) = iter.next() {
+            writer.write_u8(ENTRY_TAG)?;
+            writer.write_bytes(key)?;
+            writer.write_bytes(value)?;
+            entries += 1;
+        }
+
+        writer.write_u8(TABLE_END_TAG)?;
+        let table_hash = writer.table_hash();
+        writer.write_u64(entries)?;
+        writer.write(table_hash.as_ref())?;
+    }
+
+    writer.write_u8(END_TAG)?;
+    writer.write_u32(tables.len() as u32)?;
+    writer.finish()?;
+
+    info!(
+        "Exported {} tables with the latest block {:?} at height {}",
+        tables.len(),
+        info.block_hash,
+        info.height
+    );
+    Ok(info)
+}
+
+/// Imports a dump created by [`export_dump`] into the given database.
+///
+/// The database must be empty. The dump is read in a single pass, so it can be
+/// imported from a stream, e.g., the standard input. The integrity of each table and
+/// the whole dump is checked against the hashes recorded in the dump, the tables of
+/// the dump are checked against the imported indexes metadata, and the latest block
+/// and the root hash of the state hash aggregator of the imported database are
+/// checked against the source ones.
+///
+/// # Errors
+///
+/// Returns an error if the database is not empty, if the dump cannot be read or
+/// is corrupted, if the dump contains tables which are not recorded in its indexes
+/// metadata or misses the recorded ones, or if the imported data does not match
+/// the source. If the dump is corrupted or its tables do not match the metadata,
+/// the imported data is removed from the database.
+///
+/// [`export_dump`]: fn.export_dump.html
+pub fn import_dump<R: Read>(reader: R, db: &dyn Database) -> Result<DumpInfo, failure::Error> {
+    {
+        let snapshot = db.snapshot();
+        for name in storage::table_names(&*snapshot) {
+            if snapshot.iter(&name, &[]).next().is_some() {
+                bail!("Cannot import the dump into a non-empty database");
+            }
+        }
+    }
+
+    // Names of the tables to which entries have been written.
+    let mut written_tables = Vec::new();
+    let (expected, tables) = match import_tables(reader, db, &mut written_tables) {
+        Ok(imported) => imported,
+        Err(e) => {
+            let mut fork = db.fork();
+            for name in &written_tables {
+                fork.remove_by_prefix(name, None);
+            }
+            db.merge_sync(fork.into_patch())?;
+            return Err(e);
+        }
+    };
+
+    let imported = DumpInfo::from_snapshot(&*db.snapshot())?;
+    if imported != expected {
+        bail!(
+            "Imported database does not match the dump: expected the latest block {:?} \
+             at height {} with the state hash {:?}, found {:?} at height {} with \
+             the state hash {:?}",
+            expected.block_hash,
+            expected.height,
+            expected.state_hash,
+            imported.block_hash,
+            imported.height,
+            imported.state_hash
+        );
+    }
+
+    info!(
+        "Imported {} tables with the latest block {:?} at height {}",
+        tables, imported.block_hash, imported.height
+    );
+    Ok(imported)
+}
+
+// Writes the entries of the dump into the database in batches, recording the names of
+// the tables to which entries have been written, and checks that the tables of the dump
+// are the ones recorded in the imported indexes metadata. Returns the information
+// recorded in the dump and the number of the tables.
+fn import_tables<R: Read>(
+    reader: R,
+    db: &dyn Database,
+    written_tables: &mut Vec<String>,
+) -> Result<(DumpInfo, usize), failure::Error> {
+    let mut fork = db.fork();
+    let mut batch_len = 0;
+    let (info, tables) = read_dump(reader, |name, key, value| {
+        if written_tables.last().map(String::as_str) != Some(name) {
+            written_tables.push(name.to_owned());
+        }
+        fork.put(name, key, value);
+        batch_len += 1;
+        if batch_len == IMPORT_BATCH_SIZE {
+            let batch = mem::replace(&mut fork, db.fork());
+            db.merge(batch.into_patch())?;
+            batch_len = 0;
+        }
+        Ok(())
+    })?;
+    db.merge_sync(fork.into_patch())?;
+
+    let recorded = storage::table_names(&*db.snapshot());
+    if let Some(name) = tables.iter().find(|name| !recorded.contains(name)) {
+        bail!(
+            "Table '{}' in the dump is not recorded in the indexes metadata",
+            name
+        );
+    }
+    if let Some(name) = recorded.iter().find(|name| !tables.contains(name)) {
+        bail!(
+            "Table '{}' recorded in the indexes metadata is missing from the dump",
+            name
+        );
+    }
+    Ok((info, tables.len()))
+}
+
+// Reads the dump, passing each entry to `visit` together with the name of its table,
+// and checks the hashes of the tables and the whole dump. Returns the information
+// recorded in the dump and the names of the tables.
+fn read_dump<R, F>(reader: R, mut visit: F) -> Result<(DumpInfo, Vec<String>), failure::Error>
+where
+    R: Read,
+    F: FnMut(&str, Vec<u8>, Vec<u8>) -> Result<(), failure::Error>,
+{
+    let mut reader = DumpReader::new(reader);
+    if reader.read(DUMP_MAGIC.len())? != DUMP_MAGIC {
+        bail!("Data is not an Exonum storage dump");
+    }
+    let version = reader.read_u32()?;
+    if version != DUMP_FORMAT_VERSION {
+        bail!("Unsupported dump format version {}", version);
+    }
+    let info = DumpInfo {
+        height: Height(reader.read_u64()?),
+        block_hash: reader.read_hash()?,
+        state_hash: reader.read_hash()?,
+    };
+
+    let mut tables = Vec::new();
+    loop {
+        reader.start_table();
+        match reader.read_u8()? {
+            TABLE_TAG => {}
+            END_TAG => break,
+            tag => bail!("Unexpected record with tag {} in the dump", tag),
+        }
+        let name = String::from_utf8(reader.read_bytes()?)?;
+
+        let mut entries = 0_u64;
+        loop {
+            match reader.read_u8()? {
+                ENTRY_TAG => {}
+                TABLE_END_TAG => break,
+                tag => bail!("Unexpected record with tag {} in table '{}'", tag, name),
+            }
+            let key = reader.read_bytes()?;
+            let value = reader.read_bytes()?;
+            visit(&name, key, value)?;
+            entries += 1;
+        }
+
+        let table_hash = reader.table_hash();
+        if reader.read_u64()? != entries || reader.read_hash()? != table_hash {
+            bail!("Table '{}' in the dump is corrupted", name);
+        }
+        tables.push(name);
+    }
+
+    let recorded_tables = reader.read_u32()?;
+    let dump_hash = reader.dump_hash();
+    if recorded_tables as usize != tables.len() || reader.read_hash()? != dump_hash {
+        bail!("Dump is corrupted");
+    }
+    Ok((info, tables))
+}
+
+// Writes the dump records and computes the hashes of the current table and the whole dump.
+struct DumpWriter<W> {
+    writer: W,
+    table_hash: HashStream,
+    dump_hash: HashStream,
+}
+
+impl<W: Write> DumpWriter<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            table_hash: HashStream::new(),
+            dump_hash: HashStream::new(),
+        }
+    }
+
+    fn start_table(&mut self) {
+        self.table_hash = HashStream::new();
+    }
+
+    fn table_hash(&mut self) -> Hash {
+        mem::replace(&mut self.table_hash, HashStream::new()).hash()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.table_hash = mem::replace(&mut self.table_hash, HashStream::new()).update(bytes);
+        self.dump_hash = mem::replace(&mut self.dump_hash, HashStream::new()).update(bytes);
+        Ok(())
+    }
+
+    fn write_u8(&mut self, value: u8) -> io::Result<()> {
+        self.write(&[value])
+    }
+
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        let mut buf = [0; 4];
+        LittleEndian::write_u32(&mut buf, value);
+        self.write(&buf)
+    }
+
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        let mut buf = [0; 8];
+        LittleEndian::write_u64(&mut buf, value);
+        self.write(&buf)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_u32(bytes.len() as u32)?;
+        self.write(bytes)
+    }
+
+    // Writes the hash of all the preceding data.
+    fn finish(mut self) -> io::Result<()> {
+        let dump_hash = mem::replace(&mut self.dump_hash, HashStream::new()).hash();
+        self.writer.write_all(dump_hash.as_ref())?;
+        self.writer.flush()
+    }
+}
+
+// Reads the dump records and computes the hashes of the current table and the whole dump.
+struct DumpReader<R> {
+    reader: R,
+    table_hash: HashStream,
+    dump_hash: HashStream,
+}
+
+impl<R: Read> DumpReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            table_hash: HashStream::new(),
+            dump_hash: HashStream::new(),
+        }
+    }
+
+    fn start_table(&mut self) {
+        self.table_hash = HashStream::new();
+    }
+
+    fn table_hash(&mut self) -> Hash {
+        mem::replace(&mut self.table_hash, HashStream::new()).hash()
+    }
+
+    fn dump_hash(&mut self) -> Hash {
+        mem::replace(&mut self.dump_hash, HashStream::new()).hash()
+    }
+
+    fn read(&mut self, len: usize) -> Result<Vec<u8>, failure::Error> {
+        // The buffer is not preallocated, so that a corrupted length cannot exhaust memory.
+        let mut buf = Vec::new();
+        (&mut self.reader).take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() != len {
+            bail!("Unexpected end of the dump");
+        }
+        self.table_hash = mem::replace(&mut self.table_hash, HashStream::new()).update(&buf);
+        self.dump_hash = mem::replace(&mut self.dump_hash, HashStream::new()).update(&buf);
+        Ok(buf)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, failure::Error> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, failure::Error> {
+        Ok(LittleEndian::read_u32(&self.read(4)?))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, failure::Error> {
+        Ok(LittleEndian::read_u64(&self.read(8)?))
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, failure::Error> {
+        let len = self.read_u32()?;
+        self.read(len as usize)
+    }
+
+    fn read_hash(&mut self) -> Result<Hash, failure::Error> {
+        let bytes = self.read(HASH_SIZE)?;
+        Ok(Hash::from_slice(&bytes).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use storage::MemoryDB;
+
+    // Writes a dump with the given tables, which are not recorded in the indexes metadata.
+    fn write_dump(tables: &[(&str, &[u8], &[u8])]) -> Vec<u8> {
+        let mut dump = Vec::new();
+        {
+            let mut writer = DumpWriter::new(&mut dump);
+            writer.write(DUMP_MAGIC).unwrap();
+            writer.write_u32(DUMP_FORMAT_VERSION).unwrap();
+            writer.write_u64(0).unwrap();
+            writer.write(Hash::zero().as_ref()).unwrap();
+            writer.write(Hash::zero().as_ref()).unwrap();
+            for &(name, key, value) in tables {
+                writer.start_table();
+                writer.write_u8(TABLE_TAG).unwrap();
+                writer.write_bytes(name.as_bytes()).unwrap();
+                writer.write_u8(ENTRY_TAG).unwrap();
+                writer.write_bytes(key).unwrap();
+                writer.write_bytes(value).unwrap();
+                writer.write_u8(TABLE_END_TAG).unwrap();
+                let table_hash = writer.table_hash();
+                writer.write_u64(1).unwrap();
+                writer.write(table_hash.as_ref()).unwrap();
+            }
+            writer.write_u8(END_TAG).unwrap();
+            writer.write_u32(tables.len() as u32).unwrap();
+            writer.finish().unwrap();
+        }
+        dump
+    }
+
+    #[test]
+    fn test_unrecorded_table_rejected() {
+        let db = MemoryDB::new();
+        let dump = write_dump(&[("unrecorded", &b"key"[..], &b"value"[..])]);
+
+        let error = import_dump(&dump[..], &db).unwrap_err().to_string();
+        assert!(error.contains("not recorded in the indexes metadata"));
+        assert!(db.snapshot().get("unrecorded", b"key").is_none());
+    }
+}
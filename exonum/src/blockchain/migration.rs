@@ -0,0 +1,348 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Migrations of the service data.
+//!
+//! A service declares the version of its data layout with [`Service::data_version`] and
+//! provides the [`Migration`]s converting the data of the previous versions with
+//! [`Service::migrations`]. The data version of each service is recorded in the storage
+//! metadata; a service that has no recorded version is considered to have version 0.
+//!
+//! Pending migrations of a service, i.e., the ones with versions greater than the recorded one,
+//! are applied in the ascending order of versions:
+//!
+//! - A migration without an activation height is applied when the node is started, unless it
+//!   follows a pending migration with an activation height.
+//! - A migration with an activation height is applied in the first block created at or after
+//!   that height, before the transactions of the block are executed. The following pending
+//!   migrations without an activation height are applied in the same block.
+//!
+//! The version is recorded after each migration, so an interrupted migration process continues
+//! from the first migration that has not been applied.
+//!
+//! When the storage is initialized, the recorded version of a service is the one preceding
+//! its first migration with an activation height, or the declared version if there is no such
+//! migration. Thus, a node synchronizing the blockchain from the genesis block applies
+//! the migrations with activation heights in the same blocks as the nodes that have been
+//! running the previous versions of the service. [`Service::initialize`] must create
+//! the data in the layout of the recorded version.
+//!
+//! Migrations without an activation height change the service data, which may be a part of
+//! the consensus state, outside of any block. Such migrations must not change the data
+//! covered by the state hash unless all the nodes of the network are upgraded together
+//! while the network is stopped; otherwise, upgraded nodes will diverge from the rest.
+//! Use an activation height for migrations changing the consensus state of a running network.
+//!
+//! [`Service::data_version`]: ../trait.Service.html#method.data_version
+//! [`Service::migrations`]: ../trait.Service.html#method.migrations
+//! [`Service::initialize`]: ../trait.Service.html#method.initialize
+//! [`Migration`]: struct.Migration.html
+
+use failure;
+
+use std::{
+    collections::{BTreeMap, HashMap}, fmt,
+};
+
+use super::Service;
+use helpers::Height;
+use storage::{self, Database, Fork, Patch};
+
+// A function converting the service data to a new data version.
+type MigrationFn = dyn Fn(&mut Fork) -> Result<(), failure::Error> + Send + Sync;
+
+/// A migration of the service data to a new data version.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::blockchain::Migration;
+/// use exonum::helpers::Height;
+/// use exonum::storage::{Fork, MapIndex};
+///
+/// // Version 1 stores balances as `u64` instead of `u32`.
+/// let migration = Migration::new(1, "Widen balances", |fork: &mut Fork| {
+///     let balances: Vec<_> = MapIndex::<_, String, u32>::new("wallets.balances", &*fork)
+///         .iter()
+///         .collect();
+///     let mut index = MapIndex::new("wallets.balances", fork);
+///     for (name, balance) in balances {
+///         index.put(&name, u64::from(balance));
+///     }
+///     Ok(())
+/// }).activate_at(Height(1000));
+/// assert_eq!(migration.version(), 1);
+/// ```
+pub struct Migration {
+    version: u32,
+    description: String,
+    activation_height: Option<Height>,
+    migrate: Box<MigrationFn>,
+}
+
+impl Migration {
+    /// Creates a migration producing the data of the given version. The migration is applied
+    /// at the node startup.
+    pub fn new<S, F>(version: u32, description: S, migrate: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&mut Fork) -> Result<(), failure::Error> + Send + Sync + 'static,
+    {
+        Self {
+            version,
+            description: description.into(),
+            activation_height: None,
+            migrate: Box::new(migrate),
+        }
+    }
+
+    /// Sets the height of the block in which the migration is applied.
+    pub fn activate_at(mut self, height: Height) -> Self {
+        self.activation_height = Some(height);
+        self
+    }
+
+    /// Returns the data version produced by the migration.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the description of the migration.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the height of the block in which the migration is applied, or `None`
+    /// if the migration is applied at the node startup.
+    pub fn activation_height(&self) -> Option<Height> {
+        self.activation_height
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("description", &self.description)
+            .field("activation_height", &self.activation_height)
+            .finish()
+    }
+}
+
+/// A report about a migration of the service data.
+///
+/// Reports are returned by [`migrate_services`].
+///
+/// [`migrate_services`]: fn.migrate_services.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    /// Name of the service.
+    pub service_name: String,
+    /// Data version produced by the migration.
+    pub version: u32,
+    /// Description of the migration.
+    pub description: String,
+    /// Height of the block in which the migration is applied, or `None` if the migration
+    /// is applied at the node startup.
+    pub activation_height: Option<Height>,
+    /// Number of the entries changed by the migration in each index.
+    pub changes: BTreeMap<String, usize>,
+}
+
+/// Applies the pending migrations of the service data which are activated at the node startup.
+///
+/// If `dry_run` is `true`, all pending migrations, including the ones with an activation height,
+/// are applied to a fork which is then discarded, so the database is not changed.
+///
+/// Returns the reports about the applied migrations in the order of application.
+///
+/// # Errors
+///
+/// Returns an error if the recorded data version of a service is greater than the declared one,
+/// if the migrations of a service do not lead to the declared version, or if a migration fails.
+/// In the latter case, the migrations applied before the failed one remain in the database.
+pub fn migrate_services<'a, I>(
+    db: &dyn Database,
+    services: I,
+    dry_run: bool,
+) -> Result<Vec<MigrationReport>, failure::Error>
+where
+    I: IntoIterator<Item = &'a Box<dyn Service>>,
+{
+    let mut reports = Vec::new();
+    for service in services {
+        let mut fork = db.fork();
+        for migration in pending_migrations(service.as_ref(), &fork)? {
+            if migration.activation_height.is_some() && !dry_run {
+                break;
+            }
+            reports.push(apply_migration(service.as_ref(), &migration, &mut fork)?);
+            if !dry_run {
+                db.merge(fork.into_patch())?;
+                fork = db.fork();
+            }
+        }
+    }
+    Ok(reports)
+}
+
+/// Applies the pending migrations activated at the given height to the fork of the block.
+pub(crate) fn apply_scheduled_migrations<'a, I>(
+    services: I,
+    height: Height,
+    fork: &mut Fork,
+) -> Result<Vec<MigrationReport>, failure::Error>
+where
+    I: IntoIterator<Item = &'a Box<dyn Service>>,
+{
+    let mut reports = Vec::new();
+    for service in services {
+        let mut activated = false;
+        for migration in pending_migrations(service.as_ref(), fork)? {
+            match migration.activation_height {
+                Some(activation_height) if activation_height <= height => activated = true,
+                Some(_) => break,
+                None => {}
+            }
+            if !activated {
+                break;
+            }
+            reports.push(apply_migration(service.as_ref(), &migration, fork)?);
+        }
+    }
+    Ok(reports)
+}
+
+/// Records the data versions of the services, which is done when the storage is initialized.
+/// The recorded version precedes the first migration with an activation height, so that
+/// the migration is applied at its activation height by the nodes synchronizing
+/// the blockchain from the genesis block.
+pub(crate) fn record_data_versions<'a, I>(services: I, fork: &mut Fork)
+where
+    I: IntoIterator<Item = &'a Box<dyn Service>>,
+{
+    for service in services {
+        let version = service
+            .migrations()
+            .into_iter()
+            .filter(|migration| migration.activation_height.is_some())
+            .map(|migration| migration.version.saturating_sub(1))
+            .min()
+            .unwrap_or_else(|| service.data_version());
+        storage::set_service_data_version(service.service_name(), version, fork);
+    }
+}
+
+// Returns the migrations of the service with versions greater than the recorded one,
+// in the ascending order of versions.
+fn pending_migrations(
+    service: &dyn Service,
+    fork: &Fork,
+) -> Result<Vec<Migration>, failure::Error> {
+    let name = service.service_name();
+    let current = storage::service_data_version(name, fork).unwrap_or(0);
+    let declared = service.data_version();
+    if current > declared {
+        bail!(
+            "Data version {} of service {} is greater than the declared version {}",
+            current,
+            name,
+            declared
+        );
+    }
+    if current == declared {
+        return Ok(Vec::new());
+    }
+
+    let mut migrations: Vec<_> = service
+        .migrations()
+        .into_iter()
+        .filter(|migration| migration.version > current)
+        .collect();
+    migrations.sort_by_key(|migration| migration.version);
+    for pair in migrations.windows(2) {
+        if pair[0].version == pair[1].version {
+            bail!(
+                "Service {} has several migrations to version {}",
+                name,
+                pair[0].version
+            );
+        }
+    }
+    let last_version = migrations
+        .last()
+        .map_or(current, |migration| migration.version);
+    if last_version != declared {
+        bail!(
+            "Migrations of service {} lead from version {} to version {}, while version {} \
+             is declared",
+            name,
+            current,
+            last_version,
+            declared
+        );
+    }
+    Ok(migrations)
+}
+
+fn apply_migration(
+    service: &dyn Service,
+    migration: &Migration,
+    fork: &mut Fork,
+) -> Result<MigrationReport, failure::Error> {
+    let name = service.service_name();
+    let before = fork.patch().clone();
+    (migration.migrate)(fork).map_err(|e| {
+        format_err!(
+            "Migration of service {} to version {} failed: {}",
+            name,
+            migration.version,
+            e
+        )
+    })?;
+    let changes = changed_entries(&before, fork.patch());
+    storage::set_service_data_version(name, migration.version, fork);
+
+    Ok(MigrationReport {
+        service_name: name.to_owned(),
+        version: migration.version,
+        description: migration.description.clone(),
+        activation_height: migration.activation_height,
+        changes,
+    })
+}
+
+// Counts the entries of each index changed in the `after` patch compared to the `before` one.
+fn changed_entries(before: &Patch, after: &Patch) -> BTreeMap<String, usize> {
+    let mut previous = HashMap::new();
+    for (name, changes) in before.iter() {
+        for (key, change) in changes.iter() {
+            previous.insert((name.as_str(), key.as_slice()), change);
+        }
+    }
+
+    let mut counts = BTreeMap::new();
+    for (name, changes) in after.iter() {
+        let count = changes
+            .iter()
+            .filter(|&(key, change)| {
+                previous.get(&(name.as_str(), key.as_slice())) != Some(&change)
+            })
+            .count();
+        if count > 0 {
+            counts.insert(name.clone(), count);
+        }
+    }
+    counts
+}
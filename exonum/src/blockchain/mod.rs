@@ -33,16 +33,23 @@
 //! [doc:create-service]: https://exonum.com/doc/get-started/create-service
 
 pub use self::{
-    block::{Block, BlockProof}, config::{ConsensusConfig, StoredConfiguration, ValidatorKeys},
-    genesis::GenesisConfig, schema::{Schema, TxLocation},
+    backup::{create_backup, restore_backup, BackupInfo}, block::{Block, BlockProof},
+    config::{ConsensusConfig, StoredConfiguration, ValidatorKeys},
+    dump::{export_dump, import_dump, DumpInfo}, genesis::GenesisConfig,
+    migration::{migrate_services, Migration, MigrationReport},
+    patch_log::{apply_patch_log, PatchLog, PatchLogEntry, PatchLogReader},
+    replay::{Divergence, IndexDiff}, schema::{Schema, TxLocation},
     service::{Service, ServiceContext, SharedNodeState},
     transaction::{
         ExecutionError, ExecutionResult, Transaction, TransactionError, TransactionErrorType,
         TransactionResult, TransactionSet,
     },
+    verify::{verify_blockchain, Inconsistency},
 };
+pub(crate) use self::transaction::check_transaction_metadata;
 
 pub mod config;
+pub mod migration;
 
 use byteorder::{ByteOrder, LittleEndian};
 use failure;
@@ -58,14 +65,21 @@ use encoding::Error as MessageError;
 use helpers::{Height, Round, ValidatorId};
 use messages::{Connect, Precommit, RawMessage, CONSENSUS as CORE_SERVICE};
 use node::ApiSender;
-use storage::{self, Database, Error, Fork, Patch, Snapshot};
+use storage::{
+    self, CacheStats, Database, Error, Fork, IndexStats, MemoryDB, Patch, Snapshot,
+};
 
+mod backup;
 mod block;
+mod dump;
 mod genesis;
+mod patch_log;
+mod replay;
 mod schema;
 mod service;
 #[macro_use]
 mod transaction;
+mod verify;
 #[cfg(test)]
 mod tests;
 
@@ -78,6 +92,9 @@ pub struct Blockchain {
     service_map: Arc<VecMap<Box<dyn Service>>>,
     pub(crate) service_keypair: (PublicKey, SecretKey),
     pub(crate) api_sender: ApiSender,
+    keep_blocks: Option<u64>,
+    keep_history: Option<u64>,
+    patch_log: Option<Arc<PatchLog>>,
 }
 
 impl Blockchain {
@@ -100,12 +117,16 @@ impl Blockchain {
             }
             service_map.insert(id, service);
         }
+        check_storage_namespaces(&service_map);
 
         Self {
             db: storage.into(),
             service_map: Arc::new(service_map),
             service_keypair: (service_public_key, service_secret_key),
             api_sender,
+            keep_blocks: None,
+            keep_history: None,
+            patch_log: None,
         }
     }
 
@@ -118,6 +139,53 @@ impl Blockchain {
         }
     }
 
+    /// Sets the number of the latest blocks for which transaction bodies and precommits
+    /// are kept in the storage. Data of older blocks is pruned when a new block is
+    /// committed. `None` disables pruning.
+    ///
+    /// See [`Schema::prune_blocks`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keep_blocks` is zero.
+    ///
+    /// [`Schema::prune_blocks`]: struct.Schema.html#method.prune_blocks
+    pub fn set_keep_blocks(&mut self, keep_blocks: Option<u64>) {
+        assert_ne!(keep_blocks, Some(0), "At least one block should be kept");
+        self.keep_blocks = keep_blocks;
+    }
+
+    /// Sets the number of the latest blocks for which the storage history is kept,
+    /// so that their state can be retrieved with [`snapshot_at`]. The history of older
+    /// blocks is pruned when a new block is committed. `None` keeps the whole history,
+    /// unless it is pruned together with the blocks (see [`set_keep_blocks`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keep_history` is zero.
+    ///
+    /// [`snapshot_at`]: #method.snapshot_at
+    /// [`set_keep_blocks`]: #method.set_keep_blocks
+    pub fn set_keep_history(&mut self, keep_history: Option<u64>) {
+        assert_ne!(
+            keep_history,
+            Some(0),
+            "History of at least one block should be kept"
+        );
+        self.keep_history = keep_history;
+    }
+
+    /// Sets the log into which the patch of each committed block is written. `None`
+    /// disables logging.
+    ///
+    /// The log should be attached before the genesis block is created, so that a follower
+    /// can synchronize an empty database from it. See [`apply_patch_log`] for details.
+    ///
+    /// [`apply_patch_log`]: fn.apply_patch_log.html
+    pub fn set_patch_log(&mut self, patch_log: Option<PatchLog>) {
+        self.patch_log = patch_log.map(Arc::new);
+    }
+
     /// Returns the `VecMap` for all services. This is a map which
     /// contains service identifiers and service interfaces. The VecMap
     /// allows proceeding from the service identifier to the service itself.
@@ -130,6 +198,32 @@ impl Blockchain {
         self.db.snapshot()
     }
 
+    /// Creates a read-only snapshot of the storage state right after the block
+    /// at the given height has been committed.
+    ///
+    /// Returns `None` if the block at `height` has not been committed yet, or if
+    /// the storage does not contain history for this height (e.g., the database was
+    /// created by a node that did not record it).
+    ///
+    /// Note that only the changes made by committed blocks are tracked; node-local
+    /// data updated between blocks, such as the transaction pool or the peers cache,
+    /// may reflect a later state.
+    pub fn snapshot_at(&self, height: Height) -> Option<Box<dyn Snapshot>> {
+        self.db.snapshot_at(height.0)
+    }
+
+    /// Returns storage usage statistics of each index in the blockchain storage.
+    /// See [`storage_stats`](../storage/fn.storage_stats.html) for details.
+    pub fn storage_stats(&self) -> Vec<IndexStats> {
+        storage::storage_stats(&*self.db)
+    }
+
+    /// Returns the statistics of the read cache of the blockchain storage, or `None`
+    /// if the cache is disabled.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.db.cache_stats()
+    }
+
     /// Creates a snapshot of the current storage state that can be later committed into the storage
     /// via the `merge` method.
     pub fn fork(&self) -> Fork {
@@ -186,9 +280,44 @@ impl Blockchain {
             .is_empty();
         if has_genesis_block {
             self.assert_storage_version();
+            self.migrate_services()?;
         } else {
             self.initialize_metadata();
             self.create_genesis_block(cfg)?;
+            self.log_genesis_block()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the whole storage state into the patch log as the patch of the genesis block.
+    fn log_genesis_block(&self) -> Result<(), Error> {
+        let patch_log = match self.patch_log {
+            Some(ref patch_log) => patch_log,
+            None => return Ok(()),
+        };
+        let snapshot = self.snapshot();
+        let mut fork = MemoryDB::new().fork();
+        for name in storage::table_names(&*snapshot) {
+            let mut iter = snapshot.iter(&name, &[]);
+            while let Some((key, value)) = iter.next() {
+                fork.put(&name, key.to_vec(), value.to_vec());
+            }
+        }
+        let patch = fork.into_patch().to_bytes();
+        patch_log
+            .append(Height::zero(), &self.last_hash(), &patch)
+            .map_err(|e| Error::new(format!("Unable to write the patch log: {}", e)))
+    }
+
+    /// Applies the pending migrations of the service data activated at the node startup.
+    fn migrate_services(&self) -> Result<(), Error> {
+        let reports = migration::migrate_services(&*self.db, self.service_map.values(), false)
+            .map_err(|e| Error::new(format!("Unable to migrate service data: {}", e)))?;
+        for report in reports {
+            info!(
+                "Data of service {} migrated to version {}: {}",
+                report.service_name, report.version, report.description
+            );
         }
         Ok(())
     }
@@ -197,6 +326,7 @@ impl Blockchain {
     fn initialize_metadata(&mut self) {
         let mut fork = self.db.fork();
         storage::StorageMetadata::write_current(&mut fork);
+        migration::record_data_versions(self.service_map.values(), &mut fork);
         if self.merge(fork.into_patch()).is_ok() {
             info!(
                 "Storage version successfully initialized with value [{}].",
@@ -257,6 +387,12 @@ impl Blockchain {
             self.create_patch(ValidatorId::zero(), Height::zero(), &[])
                 .1
         };
+        let patch = {
+            let mut fork = self.fork();
+            fork.merge(patch);
+            fork.save_version(Height::zero().into());
+            fork.into_patch()
+        };
         self.merge(patch)?;
         Ok(())
     }
@@ -296,13 +432,24 @@ impl Blockchain {
         let block_hash = {
             // Get last hash.
             let last_hash = self.last_hash();
+            // Apply migrations of service data activated at this height.
+            if height > Height(0) {
+                self.apply_scheduled_migrations(height, &mut fork);
+            }
             // Save & execute transactions.
+            let mut tx_results = Vec::with_capacity(tx_hashes.len());
             for (index, hash) in tx_hashes.iter().enumerate() {
-                self.execute_transaction(*hash, height, index, &mut fork)
+                let tx_result = self.execute_transaction(*hash, height, index, &mut fork)
                     // Execution could fail if the transaction
                     // cannot be deserialized or it isn't in the pool.
                     .expect("Transaction not found in the database.");
+                tx_results.push((*hash, tx_result));
             }
+            // Results are saved at once, so that the branches of the index are updated once
+            // per block.
+            Schema::new(&mut fork)
+                .transaction_results_mut()
+                .put_all(tx_results);
 
             // Invoke execute method for all services.
             for service in self.service_map.values() {
@@ -341,9 +488,7 @@ impl Blockchain {
 
                 let state_hash = {
                     let mut sum_table = schema.state_hash_aggregator_mut();
-                    for (key, hash) in state_hashes {
-                        sum_table.put(&key, hash)
-                    }
+                    sum_table.put_all(state_hashes);
                     sum_table.merkle_root()
                 };
 
@@ -376,14 +521,26 @@ impl Blockchain {
         (block_hash, fork.into_patch())
     }
 
+    fn apply_scheduled_migrations(&self, height: Height, fork: &mut Fork) {
+        let reports =
+            migration::apply_scheduled_migrations(self.service_map.values(), height, fork)
+                .unwrap_or_else(|e| panic!("Unable to migrate service data: {}", e));
+        for report in reports {
+            info!(
+                "Data of service {} migrated to version {} at height {}: {}",
+                report.service_name, report.version, height, report.description
+            );
+        }
+    }
+
     fn execute_transaction(
         &self,
         tx_hash: Hash,
         height: Height,
         index: usize,
         fork: &mut Fork,
-    ) -> Result<(), failure::Error> {
-        let (tx, service_name) = {
+    ) -> Result<TransactionResult, failure::Error> {
+        let (tx, service_name, namespaces) = {
             let schema = Schema::new(&fork);
 
             let tx = schema
@@ -391,10 +548,10 @@ impl Blockchain {
                 .get(&tx_hash)
                 .ok_or_else(|| failure::err_msg("BUG: Cannot find transaction in database."))?;
 
-            let service_name = self.service_map
+            let service = self.service_map
                 .get(tx.service_id() as usize)
-                .ok_or_else(|| failure::err_msg("Service not found."))?
-                .service_name();
+                .ok_or_else(|| failure::err_msg("Service not found."))?;
+            let service_name = service.service_name();
 
             let tx = self.tx_from_raw(tx).or_else(|error| {
                 Err(failure::err_msg(format!(
@@ -405,57 +562,55 @@ impl Blockchain {
                 )))
             })?;
 
-            (tx, service_name)
+            (tx, service_name, service.storage_namespaces())
         };
 
-        fork.checkpoint();
-
-        let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| tx.execute(fork)));
-
-        let tx_result = match catch_result {
-            Ok(execution_result) => {
-                match execution_result {
-                    Ok(()) => {
-                        fork.commit();
-                    }
-                    Err(ref e) => {
-                        // Unlike panic, transaction failure isn't that rare, so logging the
-                        // whole transaction body is an overkill: it can be relatively big.
-                        info!(
-                            "Service <{}>: {:?} transaction execution failed: {:?}",
-                            service_name, tx_hash, e
-                        );
-                        fork.rollback();
-                    }
+        let metadata_check = check_transaction_metadata(&*tx, height, &Schema::new(&*fork));
+        let tx_result = match metadata_check {
+            Ok(()) => {
+                // The nonce and the multisignature sequence are kept even if the transaction
+                // fails.
+                if let Some((sender, nonce)) = tx.nonce() {
+                    Schema::new(&mut *fork)
+                        .sender_nonces_mut()
+                        .put(sender, nonce);
                 }
-                execution_result.map_err(TransactionError::from)
-            }
-            Err(err) => {
-                if err.is::<Error>() {
-                    // Continue panic unwind if the reason is StorageError.
-                    panic::resume_unwind(err);
+                if let Some(statement) = tx.multisig_statement() {
+                    Schema::new(&mut *fork)
+                        .multisig_sequences_mut()
+                        .put(statement.policy_hash(), statement.sequence());
                 }
-                fork.rollback();
-                error!(
-                    "Service <{}>: {:?} transaction execution panicked: {:?}",
-                    service_name, tx, err
+                run_transaction(&*tx, tx_hash, service_name, namespaces, fork)
+            }
+            Err(e) => {
+                info!(
+                    "Service <{}>: {:?} transaction rejected: {}",
+                    service_name, tx_hash, e
                 );
-                Err(TransactionError::from_panic(&err))
+                Err(e)
             }
         };
 
         let mut schema = Schema::new(fork);
-        schema.transaction_results_mut().put(&tx_hash, tx_result);
         schema.commit_transaction(&tx_hash);
         schema.block_transactions_mut(height).push(tx_hash);
         let location = TxLocation::new(height, index as u64);
         schema.transactions_locations_mut().put(&tx_hash, location);
-        Ok(())
+        Ok(tx_result)
     }
 
     /// Commits to the blockchain a new block with the indicated changes (patch),
     /// hash and Precommit messages. After that invokes `after_commit`
     /// for each service in the increasing order of their identifiers.
+    ///
+    /// If a patch log is set with [`set_patch_log`], the changes merged into the storage,
+    /// along with the bodies of the block transactions, are written into the log before
+    /// the merge. If the node crashes before the changes are merged, the record is written
+    /// again once the block is committed after the restart; [`apply_patch_log`] skips
+    /// such repeated records.
+    ///
+    /// [`set_patch_log`]: #method.set_patch_log
+    /// [`apply_patch_log`]: fn.apply_patch_log.html
     pub fn commit<'a, I>(
         &mut self,
         patch: &Patch,
@@ -465,14 +620,14 @@ impl Blockchain {
     where
         I: Iterator<Item = &'a Precommit>,
     {
-        let patch = {
+        let (height, patch) = {
             let mut fork = {
                 let mut fork = self.db.fork();
                 fork.merge(patch.clone()); // FIXME: Avoid cloning here. (ECR-1631)
                 fork
             };
 
-            {
+            let height = {
                 let mut schema = Schema::new(&mut fork);
                 for precommit in precommits {
                     schema.precommits_mut(&block_hash).push(precommit.clone());
@@ -481,15 +636,47 @@ impl Blockchain {
                 // Consensus messages cache is useful only during one height, so it should be
                 // cleared when a new height is achieved.
                 schema.consensus_messages_cache_mut().clear();
-                let txs_in_block = schema.last_block().tx_count();
+                let last_block = schema.last_block();
+                let txs_in_block = last_block.tx_count();
                 let txs_count = schema.transactions_pool_len_index().get().unwrap_or(0);
                 debug_assert!(txs_count >= u64::from(txs_in_block));
                 schema
                     .transactions_pool_len_index_mut()
                     .set(txs_count - u64::from(txs_in_block));
+
+                let expired = schema.remove_expired_transactions(last_block.height().next());
+                if expired > 0 {
+                    info!("Removed {} expired transactions from the pool", expired);
+                }
+                last_block.height()
+            };
+            if self.patch_log.is_some() {
+                // Transaction bodies are written into the storage when the transactions
+                // are added into the pool, so they are repeated in the logged patch.
+                let mut schema = Schema::new(&mut fork);
+                let tx_hashes: Vec<Hash> = schema.block_transactions(height).iter().collect();
+                for tx_hash in tx_hashes {
+                    let tx = schema
+                        .transactions()
+                        .get(&tx_hash)
+                        .expect("BUG: Cannot find transaction in database.");
+                    schema.transactions_mut().put(&tx_hash, tx);
+                }
             }
-            fork.into_patch()
+            fork.save_version(height.into());
+            if let Some(keep_history) = self.keep_history {
+                fork.prune_history((height.0 + 1).saturating_sub(keep_history));
+            }
+            if let Some(keep_blocks) = self.keep_blocks {
+                Schema::new(&mut fork).prune_blocks(keep_blocks);
+            }
+            (height, fork.into_patch())
         };
+        if let Some(ref patch_log) = self.patch_log {
+            patch_log
+                .append(height, &block_hash, &patch.to_bytes())
+                .map_err(|e| Error::new(format!("Unable to write the patch log: {}", e)))?;
+        }
         self.merge(patch)?;
         // Initializes the context after merge.
         let context = ServiceContext::new(
@@ -568,9 +755,60 @@ impl Blockchain {
     }
 }
 
+// Executes the transaction within a checkpoint, so that its changes are rolled back
+// if the execution fails.
+fn run_transaction(
+    tx: &dyn Transaction,
+    tx_hash: Hash,
+    service_name: &str,
+    namespaces: Vec<String>,
+    fork: &mut Fork,
+) -> TransactionResult {
+    fork.checkpoint();
+
+    let scope = fork.set_write_scope(Some(namespaces));
+    let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| tx.execute(fork)));
+    fork.set_write_scope(scope);
+
+    match catch_result {
+        Ok(execution_result) => {
+            match execution_result {
+                Ok(()) => {
+                    fork.commit();
+                }
+                Err(ref e) => {
+                    // Unlike panic, transaction failure isn't that rare, so logging the
+                    // whole transaction body is an overkill: it can be relatively big.
+                    info!(
+                        "Service <{}>: {:?} transaction execution failed: {:?}",
+                        service_name, tx_hash, e
+                    );
+                    fork.rollback();
+                }
+            }
+            execution_result.map_err(TransactionError::from)
+        }
+        Err(err) => {
+            if err.is::<Error>() {
+                // Continue panic unwind if the reason is StorageError.
+                panic::resume_unwind(err);
+            }
+            fork.rollback();
+            error!(
+                "Service <{}>: {:?} transaction execution panicked: {:?}",
+                service_name, tx, err
+            );
+            Err(TransactionError::from_panic(&err))
+        }
+    }
+}
+
 fn before_commit(service: &dyn Service, fork: &mut Fork) {
     fork.checkpoint();
-    match panic::catch_unwind(panic::AssertUnwindSafe(|| service.before_commit(fork))) {
+    let scope = fork.set_write_scope(Some(service.storage_namespaces()));
+    let catch_result = panic::catch_unwind(panic::AssertUnwindSafe(|| service.before_commit(fork)));
+    fork.set_write_scope(scope);
+    match catch_result {
         Ok(..) => fork.commit(),
         Err(err) => {
             if err.is::<Error>() {
@@ -587,6 +825,36 @@ fn before_commit(service: &dyn Service, fork: &mut Fork) {
     }
 }
 
+// Panics if the storage namespaces of different services overlap.
+fn check_storage_namespaces(service_map: &VecMap<Box<dyn Service>>) {
+    let namespaces: Vec<_> = service_map
+        .values()
+        .flat_map(|service| {
+            service
+                .storage_namespaces()
+                .into_iter()
+                .map(move |namespace| (service.as_ref(), namespace))
+        })
+        .collect();
+    for (i, &(service, ref namespace)) in namespaces.iter().enumerate() {
+        for &(other_service, ref other_namespace) in &namespaces[i + 1..] {
+            if service.service_id() != other_service.service_id()
+                && (storage::is_in_namespace(namespace, other_namespace)
+                    || storage::is_in_namespace(other_namespace, namespace))
+            {
+                panic!(
+                    "Storage namespace `{}` of service {} overlaps with namespace `{}` \
+                     of service {}, please change it.",
+                    namespace,
+                    service.service_name(),
+                    other_namespace,
+                    other_service.service_name()
+                );
+            }
+        }
+    }
+}
+
 impl fmt::Debug for Blockchain {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Blockchain(..)")
@@ -600,6 +868,9 @@ impl Clone for Blockchain {
             service_map: Arc::clone(&self.service_map),
             api_sender: self.api_sender.clone(),
             service_keypair: self.service_keypair.clone(),
+            keep_blocks: self.keep_blocks,
+            keep_history: self.keep_history,
+            patch_log: self.patch_log.clone(),
         }
     }
 }
@@ -0,0 +1,231 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A side log of the patches of committed blocks.
+//!
+//! The log allows a follower, such as a read replica, to reproduce the storage state of
+//! a node by applying the logged patches instead of re-executing transactions. Each
+//! record of the log contains the height and the hash of a block, the hash of its patch
+//! and the patch serialized with [`Patch::to_bytes`]. The patch of a block also contains
+//! the bodies of its transactions, which are written into the storage of the node when
+//! the transactions are added into the pool. The record of the genesis block contains
+//! the whole storage state after the genesis block has been created.
+//!
+//! [`Patch::to_bytes`]: ../storage/struct.Patch.html#method.to_bytes
+
+use byteorder::{ByteOrder, LittleEndian};
+use failure;
+
+use std::{
+    fmt, fs::OpenOptions, io::{self, BufWriter, Read, Write}, path::Path, sync::Mutex,
+};
+
+use super::Schema;
+use crypto::{self, CryptoHash, Hash, HASH_SIZE};
+use helpers::Height;
+use storage::{Database, Patch};
+
+// Size of a record header: the block height, the block hash, the patch hash and
+// the length of the patch.
+const HEADER_SIZE: usize = 8 + HASH_SIZE * 2 + 4;
+
+/// A writer of the patch log.
+///
+/// A patch log is attached to the blockchain with [`Blockchain::set_patch_log`].
+/// See the [module documentation](index.html) for details.
+///
+/// [`Blockchain::set_patch_log`]: struct.Blockchain.html#method.set_patch_log
+pub struct PatchLog {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl PatchLog {
+    /// Creates a patch log writing records into the given writer.
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Opens a patch log stored in the file at the given path. Records are appended
+    /// to the file; the file is created if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Appends a record with the serialized patch of the block.
+    pub(crate) fn append(&self, height: Height, block_hash: &Hash, patch: &[u8]) -> io::Result<()> {
+        let mut header = [0; HEADER_SIZE];
+        LittleEndian::write_u64(&mut header[..8], height.0);
+        header[8..8 + HASH_SIZE].copy_from_slice(block_hash.as_ref());
+        header[8 + HASH_SIZE..8 + HASH_SIZE * 2].copy_from_slice(crypto::hash(patch).as_ref());
+        LittleEndian::write_u32(&mut header[8 + HASH_SIZE * 2..], patch.len() as u32);
+
+        let mut writer = self.writer.lock().expect("Patch log lock is poisoned");
+        writer.write_all(&header)?;
+        writer.write_all(patch)?;
+        writer.flush()
+    }
+}
+
+impl fmt::Debug for PatchLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PatchLog(..)")
+    }
+}
+
+/// A record of the patch log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchLogEntry {
+    /// Height of the block.
+    pub height: Height,
+    /// Hash of the block.
+    pub block_hash: Hash,
+    /// Patch of the block.
+    pub patch: Patch,
+}
+
+/// An iterator over the records of a patch log.
+///
+/// The integrity of each record is checked against the patch hash recorded in the log.
+#[derive(Debug)]
+pub struct PatchLogReader<R> {
+    reader: R,
+}
+
+impl<R: Read> PatchLogReader<R> {
+    /// Creates a reader of the patch log from the given source.
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn read_entry(&mut self) -> Result<Option<PatchLogEntry>, failure::Error> {
+        let mut header = Vec::new();
+        (&mut self.reader)
+            .take(HEADER_SIZE as u64)
+            .read_to_end(&mut header)?;
+        if header.is_empty() {
+            return Ok(None);
+        }
+        if header.len() != HEADER_SIZE {
+            bail!("Unexpected end of the patch log");
+        }
+
+        let height = Height(LittleEndian::read_u64(&header[..8]));
+        let block_hash = Hash::from_slice(&header[8..8 + HASH_SIZE]).unwrap();
+        let patch_hash = Hash::from_slice(&header[8 + HASH_SIZE..8 + HASH_SIZE * 2]).unwrap();
+        let len = LittleEndian::read_u32(&header[8 + HASH_SIZE * 2..]);
+
+        // The buffer is not preallocated, so that a corrupted length cannot exhaust memory.
+        let mut bytes = Vec::new();
+        (&mut self.reader)
+            .take(u64::from(len))
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != len as usize {
+            bail!("Unexpected end of the patch log");
+        }
+        if crypto::hash(&bytes) != patch_hash {
+            bail!("Patch of the block at height {} is corrupted", height);
+        }
+
+        Ok(Some(PatchLogEntry {
+            height,
+            block_hash,
+            patch: Patch::from_bytes(&bytes)?,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for PatchLogReader<R> {
+    type Item = Result<PatchLogEntry, failure::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_entry() {
+            Ok(entry) => entry.map(Ok),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Applies the patches from a patch log to the given database.
+///
+/// Records of the blocks already present in the database are skipped, so a follower
+/// can apply the same log repeatedly as it grows. An empty database should be synchronized
+/// from the genesis record; otherwise, the database should be a copy of the source one
+/// (e.g., restored from a backup) at a height covered by the log.
+///
+/// Returns the height of the latest block in the database.
+///
+/// # Errors
+///
+/// Returns an error if the log cannot be read or is corrupted, if a record does not
+/// directly follow the latest block in the database, or if the block created by a patch
+/// does not match the one recorded in the log or does not refer to the previous block.
+pub fn apply_patch_log<R: Read>(reader: R, db: &dyn Database) -> Result<Height, failure::Error> {
+    let (mut next_height, mut last_hash) = {
+        let snapshot = db.snapshot();
+        let block_hashes = Schema::new(&snapshot).block_hashes_by_height();
+        let last_hash = block_hashes.last().unwrap_or_else(Hash::zero);
+        (Height(block_hashes.len()), last_hash)
+    };
+    for entry in PatchLogReader::new(reader) {
+        let entry = entry?;
+        if entry.height < next_height {
+            continue;
+        }
+        if entry.height > next_height {
+            bail!(
+                "Patch log contains the block at height {}, while the block at height {} \
+                 is expected",
+                entry.height,
+                next_height
+            );
+        }
+
+        // The patch is checked before being merged, so that the database is not changed
+        // by an invalid record.
+        let mut fork = db.fork();
+        fork.merge(entry.patch);
+        let block = {
+            let schema = Schema::new(&fork);
+            schema
+                .block_hash_by_height(entry.height)
+                .and_then(|block_hash| schema.blocks().get(&block_hash))
+        };
+        match block {
+            Some(ref block) if block.hash() == entry.block_hash => {}
+            _ => bail!(
+                "Patch of the block at height {} does not result in the block {:?}",
+                entry.height,
+                entry.block_hash
+            ),
+        }
+        if entry.height > Height::zero() && *block.unwrap().prev_hash() != last_hash {
+            bail!(
+                "Block at height {} does not refer to the previous block {:?}",
+                entry.height,
+                last_hash
+            );
+        }
+        db.merge(fork.into_patch())?;
+        last_hash = entry.block_hash;
+        next_height.increment();
+    }
+
+    if next_height == Height::zero() {
+        bail!("Database does not contain blocks");
+    }
+    Ok(next_height.previous())
+}
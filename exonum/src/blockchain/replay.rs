@@ -0,0 +1,275 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-execution of the blocks stored in another database.
+
+use failure::{self, Fail};
+
+use std::{
+    cmp::Ordering::{Equal, Greater, Less}, collections::BTreeSet, fmt,
+};
+
+use super::{
+    schema::{
+        CONSENSUS_MESSAGES_CACHE, CONSENSUS_ROUND, PEERS_CACHE, PRECOMMITS, PRUNED_HEIGHT,
+        TRANSACTIONS, TRANSACTIONS_POOL, TRANSACTIONS_POOL_LEN,
+    },
+    Blockchain, GenesisConfig, Schema,
+};
+use crypto::Hash;
+use helpers::Height;
+use storage::{self, Database, Patch, Snapshot};
+
+// Indices that contain node-local data or data that is not recorded in the storage
+// history; they are not compared when a divergence is found.
+const NOT_COMPARED_INDEXES: &[&str] = &[
+    TRANSACTIONS,
+    TRANSACTIONS_POOL,
+    TRANSACTIONS_POOL_LEN,
+    PRECOMMITS,
+    PEERS_CACHE,
+    CONSENSUS_MESSAGES_CACHE,
+    CONSENSUS_ROUND,
+    PRUNED_HEIGHT,
+];
+
+/// A re-executed block that differs from the stored one.
+///
+/// This error is returned by [`Blockchain::replay`].
+///
+/// [`Blockchain::replay`]: struct.Blockchain.html#method.replay
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Height of the block.
+    pub height: Height,
+    /// Hash of the re-executed block.
+    pub block_hash: Hash,
+    /// Hash of the stored block.
+    pub expected_block_hash: Hash,
+    /// State hash of the re-executed block.
+    pub state_hash: Hash,
+    /// State hash of the stored block.
+    pub expected_state_hash: Hash,
+    /// Indices whose contents after the re-executed block differ from the stored state
+    /// at the same height, or `None` if the source database does not contain the history
+    /// of this height.
+    pub diff: Option<Vec<IndexDiff>>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Re-executed block at height {} diverges from the stored one: \
+             block hash {:?} (expected {:?}), state hash {:?} (expected {:?})",
+            self.height,
+            self.block_hash,
+            self.expected_block_hash,
+            self.state_hash,
+            self.expected_state_hash
+        )
+    }
+}
+
+impl Fail for Divergence {}
+
+/// Difference between the contents of an index after a re-executed block and
+/// the stored state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexDiff {
+    /// Name of the index.
+    pub name: String,
+    /// Number of keys present only after the re-executed block.
+    pub added: u64,
+    /// Number of keys present only in the stored state.
+    pub removed: u64,
+    /// Number of keys with different values.
+    pub changed: u64,
+}
+
+impl Blockchain {
+    /// Rebuilds the blockchain from the blocks stored in the `source` database,
+    /// re-executing them with the current set of services.
+    ///
+    /// The blockchain must be empty. The genesis block is created from the genesis
+    /// configuration stored in `source`; then transactions of each committed block are
+    /// taken from `source` and executed with [`create_patch`]. The hash of each resulting
+    /// block is compared with the stored one, and the block is committed with the stored
+    /// precommits.
+    ///
+    /// Returns the height of the latest replayed block.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Divergence`] error on the first block whose hash differs from
+    /// the stored one. To find the indices that cause the divergence, the state after
+    /// the block is compared with the state of `source` at the same height,
+    /// excluding the node-local data.
+    ///
+    /// An error is also returned if the blockchain is not empty or if `source` does
+    /// not contain blocks or the transactions of a block (e.g., if the block
+    /// has been pruned).
+    ///
+    /// [`create_patch`]: #method.create_patch
+    /// [`Divergence`]: struct.Divergence.html
+    pub fn replay(&mut self, source: &dyn Database) -> Result<Height, failure::Error> {
+        if !Schema::new(&self.snapshot())
+            .block_hashes_by_height()
+            .is_empty()
+        {
+            bail!("Blocks cannot be replayed into a non-empty blockchain");
+        }
+
+        let snapshot = source.snapshot();
+        let schema = Schema::new(&snapshot);
+        let blocks_count = schema.block_hashes_by_height().len();
+        if blocks_count == 0 {
+            bail!("Source database does not contain blocks");
+        }
+
+        let genesis_config = schema.configuration_by_height(Height::zero());
+        self.initialize(GenesisConfig::new_with_consensus(
+            genesis_config.consensus,
+            genesis_config.validator_keys.into_iter(),
+        ))?;
+        let genesis_hash = self.last_hash();
+        self.check_replayed_block(source, Height::zero(), genesis_hash, None)?;
+
+        for height in 1..blocks_count {
+            let height = Height(height);
+            let expected_hash = schema.block_hash_by_height(height).unwrap();
+            let block = schema.blocks().get(&expected_hash).unwrap();
+
+            let tx_hashes: Vec<Hash> = schema.block_transactions(height).iter().collect();
+            let mut fork = self.fork();
+            for tx_hash in &tx_hashes {
+                let tx = match schema.transactions().get(tx_hash) {
+                    Some(tx) => tx,
+                    None => bail!(
+                        "Transaction {:?} of the block at height {} is absent \
+                         in the source database",
+                        tx_hash,
+                        height
+                    ),
+                };
+                Schema::new(&mut fork).add_transaction_into_pool(tx);
+            }
+            self.merge(fork.into_patch())?;
+
+            let (block_hash, patch) = self.create_patch(block.proposer_id(), height, &tx_hashes);
+            self.check_replayed_block(source, height, block_hash, Some(&patch))?;
+            self.commit(&patch, block_hash, schema.precommits(&block_hash).iter())?;
+        }
+
+        let height = Height(blocks_count - 1);
+        info!("Replayed blocks up to height {}", height);
+        Ok(height)
+    }
+
+    // Compares the replayed block with the stored one. If the `patch` of the block
+    // is not specified, the block is expected to be already committed.
+    fn check_replayed_block(
+        &self,
+        source: &dyn Database,
+        height: Height,
+        block_hash: Hash,
+        patch: Option<&Patch>,
+    ) -> Result<(), failure::Error> {
+        let snapshot = source.snapshot();
+        let source_schema = Schema::new(&snapshot);
+        let expected_hash = source_schema.block_hash_by_height(height).unwrap();
+        if block_hash == expected_hash {
+            return Ok(());
+        }
+
+        let mut replayed = self.fork();
+        if let Some(patch) = patch {
+            replayed.merge(patch.clone());
+        }
+        let block = Schema::new(&replayed).blocks().get(&block_hash).unwrap();
+        let expected_block = source_schema.blocks().get(&expected_hash).unwrap();
+        let diff = source
+            .snapshot_at(height.0)
+            .map(|stored| diff_indexes(&replayed, &*stored));
+
+        Err(Divergence {
+            height,
+            block_hash,
+            expected_block_hash: expected_hash,
+            state_hash: *block.state_hash(),
+            expected_state_hash: *expected_block.state_hash(),
+            diff,
+        }.into())
+    }
+}
+
+fn diff_indexes(replayed: &dyn Snapshot, stored: &dyn Snapshot) -> Vec<IndexDiff> {
+    let names: BTreeSet<String> = storage::indexes(replayed)
+        .into_iter()
+        .chain(storage::indexes(stored))
+        .map(|(name, ..)| name)
+        .filter(|name| !NOT_COMPARED_INDEXES.contains(&name.as_str()))
+        .collect();
+    names
+        .into_iter()
+        .filter_map(|name| diff_index(name, replayed, stored))
+        .collect()
+}
+
+fn diff_index(name: String, replayed: &dyn Snapshot, stored: &dyn Snapshot) -> Option<IndexDiff> {
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    {
+        let mut replayed_iter = replayed.iter(&name, &[]);
+        let mut stored_iter = stored.iter(&name, &[]);
+        loop {
+            let ordering = match (replayed_iter.peek(), stored_iter.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Less,
+                (None, Some(_)) => Greater,
+                (Some((key, value)), Some((stored_key, stored_value))) => {
+                    let ordering = key.cmp(stored_key);
+                    if ordering == Equal && value != stored_value {
+                        changed += 1;
+                    }
+                    ordering
+                }
+            };
+            match ordering {
+                Less => {
+                    added += 1;
+                    replayed_iter.next();
+                }
+                Greater => {
+                    removed += 1;
+                    stored_iter.next();
+                }
+                Equal => {
+                    replayed_iter.next();
+                    stored_iter.next();
+                }
+            }
+        }
+    }
+
+    if added == 0 && removed == 0 && changed == 0 {
+        None
+    } else {
+        Some(IndexDiff {
+            name,
+            added,
+            removed,
+            changed,
+        })
+    }
+}
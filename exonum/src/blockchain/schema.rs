@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+
 use super::{config::StoredConfiguration, Block, BlockProof, Blockchain, TransactionResult};
 use crypto::{CryptoHash, Hash, PublicKey};
 use helpers::{Height, Round};
@@ -28,7 +30,7 @@ macro_rules! define_names {
             $name:ident => $value:expr;
         )+
     ) => (
-        $(const $name: &str = concat!("core.", $value);)*
+        $(pub(super) const $name: &str = concat!("core.", $value);)*
     )
 }
 
@@ -37,6 +39,9 @@ define_names!(
     TRANSACTION_RESULTS => "transaction_results";
     TRANSACTIONS_POOL => "transactions_pool";
     TRANSACTIONS_POOL_LEN => "transactions_pool_len";
+    TRANSACTIONS_POOL_EXPIRY => "transactions_pool_expiry";
+    TRANSACTIONS_POOL_NONCES => "transactions_pool_nonces";
+    TRANSACTIONS_POOL_SENDERS => "transactions_pool_senders";
     TRANSACTIONS_LOCATIONS => "transactions_locations";
     BLOCKS => "blocks";
     BLOCK_HASHES_BY_HEIGHT => "block_hashes_by_height";
@@ -48,6 +53,9 @@ define_names!(
     PEERS_CACHE => "peers_cache";
     CONSENSUS_MESSAGES_CACHE => "consensus_messages_cache";
     CONSENSUS_ROUND => "consensus_round";
+    PRUNED_HEIGHT => "pruned_height";
+    SENDER_NONCES => "sender_nonces";
+    MULTISIG_SEQUENCES => "multisig_sequences";
 );
 
 encoding_struct! {
@@ -60,6 +68,16 @@ encoding_struct! {
     }
 }
 
+encoding_struct! {
+    /// Sender and nonce of a transaction in the pool.
+    struct PoolTransactionNonce {
+        /// Sender of the transaction.
+        sender: &PublicKey,
+        /// Nonce of the transaction.
+        nonce: u64,
+    }
+}
+
 encoding_struct! {
     /// Transaction location in a block.
     /// The given entity defines the block where the transaction was
@@ -122,6 +140,89 @@ where
         pool.get().unwrap_or(0)
     }
 
+    /// Returns a set of the heights until which the transactions of the pool are valid
+    /// along with the transaction hashes. Only the transactions that declare
+    /// [`valid_until`] are present in the set.
+    ///
+    /// [`valid_until`]: trait.Transaction.html#method.valid_until
+    pub(crate) fn transactions_pool_expiry(&self) -> KeySetIndex<&T, (u64, Hash)> {
+        KeySetIndex::new(TRANSACTIONS_POOL_EXPIRY, &self.view)
+    }
+
+    /// Returns a set of the senders and nonces of the transactions in the pool that
+    /// declare a [`nonce`], along with the transaction hashes. The set is ordered by
+    /// the senders and then by the nonces.
+    ///
+    /// [`nonce`]: trait.Transaction.html#method.nonce
+    pub(crate) fn transactions_pool_nonces(&self) -> KeySetIndex<&T, (PublicKey, u64, Hash)> {
+        KeySetIndex::new(TRANSACTIONS_POOL_NONCES, &self.view)
+    }
+
+    /// Returns a table that keeps the sender and the nonce of every transaction in the pool
+    /// that declares a [`nonce`], indexed by the transaction hash.
+    ///
+    /// [`nonce`]: trait.Transaction.html#method.nonce
+    pub(crate) fn transactions_pool_senders(&self) -> MapIndex<&T, Hash, PoolTransactionNonce> {
+        MapIndex::new(TRANSACTIONS_POOL_SENDERS, &self.view)
+    }
+
+    /// Orders the given transactions from the pool so that the transactions of each sender
+    /// go in the ascending order of their nonces. The slots taken by the transactions of
+    /// a sender are filled with the transactions of this sender with the lowest nonces
+    /// in the pool, which may be absent among the given ones; transactions without nonces
+    /// stay in their places.
+    ///
+    /// Transactions with nonces are accepted only if their nonces grow within a block,
+    /// hence this order has to be used when a propose is created from the pool. Only the
+    /// nonces recorded with [`set_transaction_nonce`] are taken into account.
+    ///
+    /// [`set_transaction_nonce`]: #method.set_transaction_nonce
+    pub(crate) fn order_by_nonce<I>(&self, tx_hashes: I) -> Vec<Hash>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        let senders = self.transactions_pool_senders();
+        let nonces = self.transactions_pool_nonces();
+        let mut tx_hashes: Vec<Hash> = tx_hashes.into_iter().collect();
+        let mut slots: HashMap<PublicKey, Vec<usize>> = HashMap::new();
+        for (position, hash) in tx_hashes.iter().enumerate() {
+            if let Some(record) = senders.get(hash) {
+                slots
+                    .entry(*record.sender())
+                    .or_insert_with(Vec::new)
+                    .push(position);
+            }
+        }
+        for (sender, positions) in slots {
+            let lowest_nonces = nonces
+                .iter_from(&(sender, 0, Hash::zero()))
+                .take_while(|&(ref tx_sender, ..)| *tx_sender == sender)
+                .map(|(.., hash)| hash);
+            for (position, hash) in positions.into_iter().zip(lowest_nonces) {
+                tx_hashes[position] = hash;
+            }
+        }
+        tx_hashes
+    }
+
+    /// Returns a table that keeps the nonce of the latest committed transaction
+    /// for every sender of the transactions that declare a [`nonce`].
+    ///
+    /// [`nonce`]: trait.Transaction.html#method.nonce
+    pub fn sender_nonces(&self) -> MapIndex<&T, PublicKey, u64> {
+        MapIndex::new(SENDER_NONCES, &self.view)
+    }
+
+    /// Returns a table that keeps the sequence number of the latest committed statement
+    /// for every threshold policy, indexed by the hash of the policy.
+    ///
+    /// See [`Transaction::multisig_statement`].
+    ///
+    /// [`Transaction::multisig_statement`]: trait.Transaction.html#method.multisig_statement
+    pub fn multisig_sequences(&self) -> MapIndex<&T, Hash, u64> {
+        MapIndex::new(MULTISIG_SEQUENCES, &self.view)
+    }
+
     /// Returns a table that keeps the block height and transaction position inside the block for every
     /// transaction hash.
     pub fn transactions_locations(&self) -> MapIndex<&T, Hash, TxLocation> {
@@ -201,6 +302,22 @@ where
             .unwrap_or_else(Round::first)
     }
 
+    /// Returns the height of the latest block which transaction bodies and precommits
+    /// have been pruned from the storage, or `None` if no blocks have been pruned.
+    ///
+    /// Pruning always removes the data of the oldest blocks first, so the data of all
+    /// the blocks up to the returned height is unavailable.
+    pub fn pruned_height(&self) -> Option<Height> {
+        Entry::new(PRUNED_HEIGHT, &self.view).get().map(Height)
+    }
+
+    /// Returns `true` if transaction bodies and precommits of the block at the given
+    /// height have been pruned from the storage.
+    pub fn is_pruned(&self, height: Height) -> bool {
+        self.pruned_height()
+            .map_or(false, |pruned_height| height <= pruned_height)
+    }
+
     /// Returns the block hash for the given height.
     pub fn block_hash_by_height(&self, height: Height) -> Option<Hash> {
         self.block_hashes_by_height().get(height.into())
@@ -401,6 +518,41 @@ impl<'a> Schema<&'a mut Fork> {
         Entry::new(TRANSACTIONS_POOL_LEN, self.view)
     }
 
+    /// Mutable reference to the [`transactions_pool_expiry`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.transactions_pool_expiry
+    fn transactions_pool_expiry_mut(&mut self) -> KeySetIndex<&mut Fork, (u64, Hash)> {
+        KeySetIndex::new(TRANSACTIONS_POOL_EXPIRY, self.view)
+    }
+
+    /// Mutable reference to the [`transactions_pool_nonces`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.transactions_pool_nonces
+    fn transactions_pool_nonces_mut(&mut self) -> KeySetIndex<&mut Fork, (PublicKey, u64, Hash)> {
+        KeySetIndex::new(TRANSACTIONS_POOL_NONCES, &mut self.view)
+    }
+
+    /// Mutable reference to the [`transactions_pool_senders`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.transactions_pool_senders
+    fn transactions_pool_senders_mut(&mut self) -> MapIndex<&mut Fork, Hash, PoolTransactionNonce> {
+        MapIndex::new(TRANSACTIONS_POOL_SENDERS, &mut self.view)
+    }
+
+    /// Mutable reference to the [`sender_nonces`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.sender_nonces
+    pub(crate) fn sender_nonces_mut(&mut self) -> MapIndex<&mut Fork, PublicKey, u64> {
+        MapIndex::new(SENDER_NONCES, self.view)
+    }
+
+    /// Mutable reference to the [`multisig_sequences`][1] index.
+    ///
+    /// [1]: struct.Schema.html#method.multisig_sequences
+    pub(crate) fn multisig_sequences_mut(&mut self) -> MapIndex<&mut Fork, Hash, u64> {
+        MapIndex::new(MULTISIG_SEQUENCES, self.view)
+    }
+
     /// Mutable reference to the [`transactions_locations`][1] index.
     ///
     /// [1]: struct.Schema.html#method.transactions_locations
@@ -481,6 +633,40 @@ impl<'a> Schema<&'a mut Fork> {
         entry.set(round);
     }
 
+    /// Prunes transaction bodies and precommits of all the blocks except for
+    /// the `keep_blocks` latest ones, together with the storage history of these blocks.
+    ///
+    /// Block headers, lists of transaction hashes in blocks, transaction locations and
+    /// the current state are kept intact; the node relies on the transaction locations
+    /// to reject the pruned transactions if they are broadcast again. Returns the height
+    /// of the latest pruned block, if any.
+    pub fn prune_blocks(&mut self, keep_blocks: u64) -> Option<Height> {
+        let blocks_count = self.block_hashes_by_height().len();
+        if blocks_count <= keep_blocks {
+            return self.pruned_height();
+        }
+        let last_pruned = Height(blocks_count - keep_blocks - 1);
+        let first_unpruned = self.pruned_height().map_or(Height(0), |height| height.next());
+        if first_unpruned > last_pruned {
+            return self.pruned_height();
+        }
+
+        for height in first_unpruned.0..=last_pruned.0 {
+            let height = Height(height);
+            let block_hash = self.block_hash_by_height(height)
+                .expect("Block hash is absent for the committed height");
+            let tx_hashes: Vec<Hash> = self.block_transactions(height).iter().collect();
+            for tx_hash in &tx_hashes {
+                self.transactions_mut().remove(tx_hash);
+            }
+            self.precommits_mut(&block_hash).clear();
+        }
+        Entry::new(PRUNED_HEIGHT, &mut *self.view).set(last_pruned.0);
+        self.view.prune_history(last_pruned.next().into());
+        info!("Pruned blocks up to height {}", last_pruned);
+        Some(last_pruned)
+    }
+
     /// Adds a new configuration to the blockchain, which will become actual at
     /// the `actual_from` height in `config_data`.
     pub fn commit_configuration(&mut self, config_data: StoredConfiguration) {
@@ -529,9 +715,57 @@ impl<'a> Schema<&'a mut Fork> {
         self.transactions_mut().put(&tx.hash(), tx);
     }
 
+    /// Records the height until which the transaction in the persistent pool is valid,
+    /// so that the transaction is removed from the pool by
+    /// [`remove_expired_transactions`](#method.remove_expired_transactions) afterwards.
+    pub(crate) fn set_transaction_expiry(&mut self, hash: &Hash, valid_until: Height) {
+        self.transactions_pool_expiry_mut()
+            .insert((valid_until.0, *hash));
+    }
+
+    /// Records the sender and the nonce of the transaction in the persistent pool, so that
+    /// the transactions of the sender are proposed in the order of their nonces.
+    /// See [`order_by_nonce`](#method.order_by_nonce).
+    pub(crate) fn set_transaction_nonce(&mut self, hash: &Hash, sender: &PublicKey, nonce: u64) {
+        self.transactions_pool_nonces_mut()
+            .insert((*sender, nonce, *hash));
+        self.transactions_pool_senders_mut()
+            .put(hash, PoolTransactionNonce::new(sender, nonce));
+    }
+
+    // Removes the record about the sender and the nonce of the transaction leaving the pool.
+    fn remove_transaction_nonce(&mut self, hash: &Hash) {
+        if let Some(record) = self.transactions_pool_senders().get(hash) {
+            self.transactions_pool_nonces_mut()
+                .remove(&(*record.sender(), record.nonce(), *hash));
+            self.transactions_pool_senders_mut().remove(hash);
+        }
+    }
+
+    /// Removes the transactions which are not valid at the given height from the persistent
+    /// pool. Returns the number of the removed transactions.
+    pub(crate) fn remove_expired_transactions(&mut self, height: Height) -> u64 {
+        let expired: Vec<(u64, Hash)> = self.transactions_pool_expiry()
+            .iter()
+            .take_while(|&(valid_until, _)| valid_until < height.0)
+            .collect();
+        let mut removed = 0;
+        for (valid_until, hash) in expired {
+            self.transactions_pool_expiry_mut()
+                .remove(&(valid_until, hash));
+            // Committed transactions have already left the pool.
+            if self.transactions_pool().contains(&hash) {
+                self.reject_transaction(&hash).unwrap();
+                removed += 1;
+            }
+        }
+        removed
+    }
+
     /// Changes the transaction status from `in_pool`, to `committed`.
     pub(crate) fn commit_transaction(&mut self, hash: &Hash) {
         self.transactions_pool_mut().remove(hash);
+        self.remove_transaction_nonce(hash);
     }
 
     /// Removes transaction from the persistent pool.
@@ -540,6 +774,7 @@ impl<'a> Schema<&'a mut Fork> {
         let contains = self.transactions_pool_mut().contains(hash);
         self.transactions_pool_mut().remove(hash);
         self.transactions_mut().remove(hash);
+        self.remove_transaction_nonce(hash);
         if contains {
             let x = self.transactions_pool_len_index().get().unwrap();
             self.transactions_pool_len_index_mut().set(x - 1);
@@ -19,10 +19,10 @@ use actix::{Addr, Syn};
 use serde_json::Value;
 
 use std::{
-    collections::{HashMap, HashSet}, fmt, net::SocketAddr, sync::{Arc, RwLock},
+    collections::{HashMap, HashSet}, fmt, net::SocketAddr, path::PathBuf, sync::{Arc, RwLock},
 };
 
-use super::transaction::Transaction;
+use super::{migration::Migration, transaction::Transaction};
 use api::{websocket, ServiceApiBuilder};
 use blockchain::{ConsensusConfig, Schema, StoredConfiguration, ValidatorKeys};
 use crypto::{Hash, PublicKey, SecretKey};
@@ -184,6 +184,49 @@ pub trait Service: Send + Sync + 'static {
         Value::Null
     }
 
+    /// Returns the storage namespaces of the service. An index belongs to a namespace if
+    /// its name is equal to the namespace or starts with the namespace followed by a dot,
+    /// e.g., `cryptocurrency.wallets` belongs to the `cryptocurrency` namespace.
+    ///
+    /// The fork passed to [`Transaction::execute`] and [`before_commit`] only allows changing
+    /// the indexes within these namespaces, while the indexes of other services and the core
+    /// can be read through the read-only view returned by `fork.as_ref()`. Namespaces of
+    /// different services must not overlap, which is checked when the blockchain is created.
+    ///
+    /// A service changing the core schema, such as the configuration service,
+    /// should include the `core` namespace.
+    ///
+    /// *Default implementation returns the service name*
+    ///
+    /// [`Transaction::execute`]: trait.Transaction.html#tymethod.execute
+    /// [`before_commit`]: #method.before_commit
+    fn storage_namespaces(&self) -> Vec<String> {
+        vec![self.service_name().to_owned()]
+    }
+
+    /// Returns the version of the data layout of the service. The version is recorded
+    /// in the storage when the blockchain is initialized and is updated by the migrations
+    /// returned by [`migrations`]. If some of the migrations have activation heights,
+    /// the version preceding the first of them is recorded at initialization instead.
+    ///
+    /// *Default implementation returns 0*
+    ///
+    /// [`migrations`]: #method.migrations
+    fn data_version(&self) -> u32 {
+        0
+    }
+
+    /// Returns the migrations converting the service data of the previous data versions
+    /// to the one returned by [`data_version`]. See the [`migration`] module for details.
+    ///
+    /// *Default implementation returns no migrations*
+    ///
+    /// [`data_version`]: #method.data_version
+    /// [`migration`]: migration/index.html
+    fn migrations(&self) -> Vec<Migration> {
+        Vec::new()
+    }
+
     /// A service execution. This method is invoked for each service after execution
     /// of all transactions in the block but before `after_commit` handler.
     ///
@@ -358,6 +401,8 @@ pub struct SharedNodeState {
     state: Arc<RwLock<ApiNodeState>>,
     /// Timeout to update API state.
     pub state_update_timeout: Milliseconds,
+    /// Directory in which backups can be created with the private API.
+    pub backup_dir: Option<PathBuf>,
 }
 
 impl SharedNodeState {
@@ -366,6 +411,7 @@ impl SharedNodeState {
         Self {
             state: Arc::new(RwLock::new(ApiNodeState::new())),
             state_update_timeout,
+            backup_dir: None,
         }
     }
     /// Returns a list of connected addresses of other nodes.
@@ -15,15 +15,22 @@
 #![allow(dead_code, unsafe_code)]
 
 use chrono::{DateTime, TimeZone, Utc};
+use futures::sync::mpsc;
 use rand::{thread_rng, Rng};
 use serde_json;
 
-use blockchain::{Blockchain, ExecutionResult, Schema, Service, Transaction};
-use crypto::{gen_keypair, CryptoHash, Hash};
+use std::{io::Cursor, sync::Arc};
+
+use blockchain::{
+    export_dump, import_dump, Blockchain, ExecutionResult, GenesisConfig, Schema, Service,
+    Transaction, ValidatorKeys,
+};
+use crypto::{gen_keypair, CryptoHash, Hash, SecretKey};
 use encoding::Error as MessageError;
-use helpers::{Height, ValidatorId};
-use messages::{Message, RawTransaction};
-use storage::{Database, Error, Fork, ListIndex, Snapshot};
+use helpers::{Height, Round, ValidatorId};
+use messages::{Message, Precommit, RawTransaction};
+use node::ApiSender;
+use storage::{Database, Error, Fork, ListIndex, MemoryDB, Snapshot};
 
 const IDX_NAME: &'static str = "idx_name";
 const TEST_SERVICE_ID: u16 = 255;
@@ -46,6 +53,10 @@ impl Service for TestService {
     fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
         Ok(Box::new(Tx::from_raw(raw)?))
     }
+
+    fn storage_namespaces(&self) -> Vec<String> {
+        vec![IDX_NAME.to_owned()]
+    }
 }
 
 transactions! {
@@ -73,6 +84,66 @@ impl Transaction for Tx {
     }
 }
 
+// Creates a blockchain with the given services. The blockchain is not initialized.
+fn create_blockchain<D>(db: D, services: Vec<Box<dyn Service>>) -> Blockchain
+where
+    D: Into<Arc<dyn Database>>,
+{
+    let (service_key, service_secret_key) = gen_keypair();
+    let api_channel = mpsc::channel(1);
+    Blockchain::new(
+        db,
+        services,
+        service_key,
+        service_secret_key,
+        ApiSender::new(api_channel.0),
+    )
+}
+
+// Initializes the blockchain with a single validator and returns its consensus secret key.
+fn initialize_blockchain(blockchain: &mut Blockchain) -> SecretKey {
+    let (consensus_key, consensus_secret_key) = gen_keypair();
+    let validator_keys = ValidatorKeys {
+        consensus_key,
+        service_key: gen_keypair().0,
+    };
+    blockchain
+        .initialize(GenesisConfig::new(vec![validator_keys].into_iter()))
+        .unwrap();
+    consensus_secret_key
+}
+
+// Adds the transactions into the pool of the blockchain.
+fn add_into_pool(blockchain: &mut Blockchain, txs: &[Tx]) {
+    let mut fork = blockchain.fork();
+    {
+        let mut schema = Schema::new(&mut fork);
+        for tx in txs {
+            schema.add_transaction_into_pool(tx.raw().clone());
+        }
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+}
+
+// Commits the next block with the given transactions from the pool. The block is
+// precommitted by the single validator with the given consensus secret key.
+fn commit_block(blockchain: &mut Blockchain, secret_key: &SecretKey, tx_hashes: &[Hash]) {
+    let height = Schema::new(&blockchain.snapshot()).height().next();
+    let (block_hash, patch) = blockchain.create_patch(ValidatorId::zero(), height, tx_hashes);
+    let precommit = Precommit::new(
+        ValidatorId::zero(),
+        height,
+        Round::first(),
+        &Hash::zero(),
+        &block_hash,
+        Utc::now(),
+        secret_key,
+    );
+    blockchain
+        .commit(&patch, block_hash, [precommit].iter())
+        .unwrap();
+}
+
 #[test]
 fn test_encode_decode() {
     encoding_struct! {
@@ -182,6 +253,29 @@ fn test_segments_has_spaces_between() {
     <StructWithTwoSegments as Field>::check(&buffer, 0.into(), 8.into(), 8.into()).unwrap();
 }
 
+#[test]
+fn test_keep_history() {
+    let services = vec![Box::new(TestService) as Box<dyn Service>];
+    let mut blockchain = create_blockchain(MemoryDB::new(), services);
+    blockchain.set_keep_history(Some(2));
+    let secret_key = initialize_blockchain(&mut blockchain);
+    let (_, tx_secret_key) = gen_keypair();
+    for value in 1..5 {
+        let tx = Tx::new(value, &tx_secret_key);
+        add_into_pool(&mut blockchain, &[tx.clone()]);
+        commit_block(&mut blockchain, &secret_key, &[tx.hash()]);
+    }
+
+    // Only the history of the two latest blocks is kept.
+    for height in 0..3 {
+        assert!(blockchain.snapshot_at(Height(height)).is_none());
+    }
+    let snapshot = blockchain.snapshot_at(Height(3)).unwrap();
+    let values: Vec<u64> = ListIndex::new(IDX_NAME, &snapshot).iter().collect();
+    assert_eq!(values, vec![1, 42, 2, 21, 3, 14]);
+    assert!(blockchain.snapshot_at(Height(4)).is_some());
+}
+
 fn gen_tempdir_name() -> String {
     thread_rng().gen_ascii_chars().take(10).collect()
 }
@@ -380,6 +474,10 @@ impl Service for ServiceGood {
         unimplemented!()
     }
 
+    fn storage_namespaces(&self) -> Vec<String> {
+        vec![IDX_NAME.to_owned()]
+    }
+
     fn before_commit(&self, fork: &mut Fork) {
         let mut index = ListIndex::new(IDX_NAME, fork);
         index.push(1);
@@ -451,6 +549,53 @@ fn assert_service_execute_panic(blockchain: &Blockchain, db: &mut Box<dyn Databa
     assert!(index.is_empty());
 }
 
+fn dump_roundtrip(blockchain: &mut Blockchain, target: &dyn Database) {
+    let (_, patch) = blockchain.create_patch(ValidatorId::zero(), Height::zero(), &[]);
+    blockchain.merge(patch).unwrap();
+
+    let mut dump = Vec::new();
+    let info = blockchain.export_dump(&mut dump).unwrap();
+    assert_eq!(info.height, Height::zero());
+    assert_eq!(info.block_hash, blockchain.last_hash());
+    // The dump is read in a single pass, so it does not need to be seekable.
+    assert_eq!(import_dump(&dump[..], target).unwrap(), info);
+
+    // A dump of the imported database is the same as the source one.
+    let mut imported_dump = Vec::new();
+    export_dump(target, &mut imported_dump).unwrap();
+    assert_eq!(imported_dump, dump);
+
+    assert!(import_dump(Cursor::new(&dump), target).is_err());
+}
+
+fn dump_corrupted(blockchain: &mut Blockchain, target: &dyn Database) {
+    let (_, patch) = blockchain.create_patch(ValidatorId::zero(), Height::zero(), &[]);
+    blockchain.merge(patch).unwrap();
+    // Enough entries for several import batches.
+    let mut fork = blockchain.fork();
+    {
+        let mut index = ListIndex::new(IDX_NAME, &mut fork);
+        index.extend(0..25_000_u32);
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    let mut dump = Vec::new();
+    blockchain.export_dump(&mut dump).unwrap();
+    let middle = dump.len() / 2;
+    dump[middle] ^= 1;
+    assert!(import_dump(Cursor::new(&dump), target).is_err());
+
+    // The data imported before the hash of the whole dump is checked is removed.
+    dump[middle] ^= 1;
+    let last = dump.len() - 1;
+    dump[last] ^= 1;
+    assert!(import_dump(Cursor::new(&dump), target).is_err());
+
+    let snapshot = target.snapshot();
+    assert!(ListIndex::<_, u32>::new(IDX_NAME, &snapshot).is_empty());
+    assert!(Schema::new(&snapshot).block_hashes_by_height().is_empty());
+}
+
 mod memorydb_tests {
     use blockchain::{Blockchain, Service};
     use crypto::gen_keypair;
@@ -501,6 +646,18 @@ mod memorydb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[test]
+    fn test_dump_roundtrip() {
+        let mut blockchain = create_blockchain();
+        super::dump_roundtrip(&mut blockchain, &MemoryDB::new());
+    }
+
+    #[test]
+    fn test_dump_corrupted() {
+        let mut blockchain = create_blockchain();
+        super::dump_corrupted(&mut blockchain, &MemoryDB::new());
+    }
+
     #[test]
     fn test_service_execute() {
         let blockchain = create_blockchain_with_service(Box::new(ServiceGood));
@@ -524,13 +681,14 @@ mod memorydb_tests {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
     use blockchain::{Blockchain, Service};
     use crypto::gen_keypair;
     use futures::sync::mpsc;
     use node::ApiSender;
     use std::path::Path;
-    use storage::{Database, DbOptions, RocksDB};
+    use storage::{Database, DbOptions, MemoryDB, RocksDB};
     use tempdir::TempDir;
 
     use super::{ServiceGood, ServicePanic, ServicePanicStorageError};
@@ -585,6 +743,13 @@ mod rocksdb_tests {
         super::handling_tx_panic_storage_error(&mut blockchain);
     }
 
+    #[test]
+    fn test_dump_into_memorydb() {
+        let dir = create_temp_dir();
+        let mut blockchain = create_blockchain(dir.path());
+        super::dump_roundtrip(&mut blockchain, &MemoryDB::new());
+    }
+
     #[test]
     fn test_service_execute() {
         let dir = create_temp_dir();
@@ -614,3 +779,1022 @@ mod rocksdb_tests {
         super::assert_service_execute(&blockchain, &mut db);
     }
 }
+
+mod verify_tests {
+    use blockchain::{verify_blockchain, Blockchain, Schema, Service, Transaction};
+    use crypto::{gen_keypair, Hash, SecretKey};
+    use encoding::Error as MessageError;
+    use helpers::Height;
+    use messages::RawTransaction;
+    use storage::{Fork, MemoryDB, ProofListIndex, Snapshot};
+
+    use super::{commit_block, initialize_blockchain, TestService};
+
+    const VALUES: &str = "verified.values";
+
+    // A service with a table participating in the state hash.
+    struct VerifiedService;
+
+    impl Service for VerifiedService {
+        fn service_id(&self) -> u16 {
+            5
+        }
+
+        fn service_name(&self) -> &'static str {
+            "verified"
+        }
+
+        fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+            vec![ProofListIndex::<_, u64>::new(VALUES, snapshot).merkle_root()]
+        }
+
+        fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+            unimplemented!()
+        }
+
+        fn before_commit(&self, fork: &mut Fork) {
+            ProofListIndex::new(VALUES, fork).push(1_u64);
+        }
+    }
+
+    fn services() -> Vec<Box<dyn Service>> {
+        vec![
+            Box::new(TestService) as Box<dyn Service>,
+            Box::new(VerifiedService) as Box<dyn Service>,
+        ]
+    }
+
+    fn create_blockchain() -> (Blockchain, SecretKey) {
+        let mut blockchain = super::create_blockchain(MemoryDB::new(), services());
+        let secret_key = initialize_blockchain(&mut blockchain);
+        (blockchain, secret_key)
+    }
+
+    #[test]
+    fn test_verify_valid_blockchain() {
+        let (mut blockchain, secret_key) = create_blockchain();
+        let snapshot = blockchain.snapshot();
+        assert_eq!(verify_blockchain(&*snapshot, &services()), Ok(Height(0)));
+
+        commit_block(&mut blockchain, &secret_key, &[]);
+        commit_block(&mut blockchain, &secret_key, &[]);
+        let snapshot = blockchain.snapshot();
+        assert_eq!(verify_blockchain(&*snapshot, &services()), Ok(Height(2)));
+    }
+
+    #[test]
+    fn test_verify_invalid_precommit() {
+        let (mut blockchain, secret_key) = create_blockchain();
+        commit_block(&mut blockchain, &secret_key, &[]);
+        commit_block(&mut blockchain, &gen_keypair().1, &[]);
+
+        let inconsistency = verify_blockchain(&*blockchain.snapshot(), &services()).unwrap_err();
+        assert_eq!(inconsistency.height, Height(2));
+        assert_eq!(inconsistency.index_name, "core.precommits");
+    }
+
+    #[test]
+    fn test_verify_block_transactions() {
+        let (mut blockchain, secret_key) = create_blockchain();
+        commit_block(&mut blockchain, &secret_key, &[]);
+        commit_block(&mut blockchain, &secret_key, &[]);
+
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork)
+            .block_transactions_mut(Height(1))
+            .push(Hash::zero());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let inconsistency = verify_blockchain(&*blockchain.snapshot(), &services()).unwrap_err();
+        assert_eq!(inconsistency.height, Height(1));
+        assert_eq!(inconsistency.index_name, "core.block_transactions");
+    }
+
+    #[test]
+    fn test_verify_state_hash() {
+        let (mut blockchain, secret_key) = create_blockchain();
+        commit_block(&mut blockchain, &secret_key, &[]);
+
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork)
+            .state_hash_aggregator_mut()
+            .put(&Hash::zero(), Hash::zero());
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let inconsistency = verify_blockchain(&*blockchain.snapshot(), &services()).unwrap_err();
+        assert_eq!(inconsistency.height, Height(1));
+        assert_eq!(inconsistency.index_name, "core.state_hash_aggregator");
+    }
+
+    #[test]
+    fn test_verify_core_table() {
+        let (mut blockchain, secret_key) = create_blockchain();
+        commit_block(&mut blockchain, &secret_key, &[]);
+
+        let mut fork = blockchain.fork();
+        Schema::new(&mut fork)
+            .transaction_results_mut()
+            .put(&Hash::zero(), Ok(()));
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let inconsistency = verify_blockchain(&*blockchain.snapshot(), &services()).unwrap_err();
+        assert_eq!(inconsistency.height, Height(1));
+        assert_eq!(inconsistency.index_name, "core.transaction_results");
+    }
+
+    #[test]
+    fn test_verify_service_table() {
+        let (mut blockchain, secret_key) = create_blockchain();
+        commit_block(&mut blockchain, &secret_key, &[]);
+
+        let mut fork = blockchain.fork();
+        ProofListIndex::new(VALUES, &mut fork).push(2_u64);
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        let inconsistency = verify_blockchain(&*blockchain.snapshot(), &services()).unwrap_err();
+        assert_eq!(inconsistency.height, Height(1));
+        assert_eq!(inconsistency.index_name, "verified (table 0)");
+
+        // The tables of a service missing from the list are reported as unknown entries.
+        let no_services: Vec<Box<dyn Service>> = Vec::new();
+        let inconsistency = verify_blockchain(&*blockchain.snapshot(), &no_services).unwrap_err();
+        assert_eq!(inconsistency.index_name, "core.state_hash_aggregator");
+    }
+}
+
+mod replay_tests {
+    use blockchain::{
+        Blockchain, Divergence, ExecutionError, ExecutionResult, Schema, Service, Transaction,
+    };
+    use crypto::{gen_keypair, Hash};
+    use encoding::Error as MessageError;
+    use helpers::Height;
+    use messages::{Message, RawTransaction};
+    use storage::{Fork, MemoryDB, Snapshot};
+
+    use super::{
+        add_into_pool, commit_block, initialize_blockchain, TestService, Tx, TEST_SERVICE_ID,
+    };
+
+    // A version of `TestService` whose transactions always fail.
+    struct FailingService;
+
+    impl Service for FailingService {
+        fn service_id(&self) -> u16 {
+            TEST_SERVICE_ID
+        }
+
+        fn service_name(&self) -> &'static str {
+            "test service"
+        }
+
+        fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+            vec![]
+        }
+
+        fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+            Ok(Box::new(FailingTx::from_raw(raw)?))
+        }
+    }
+
+    transactions! {
+        FailingServiceTxs {
+            const SERVICE_ID = TEST_SERVICE_ID;
+            // Has the same layout as `Tx`.
+            struct FailingTx {
+                value: u64,
+            }
+        }
+    }
+
+    impl Transaction for FailingTx {
+        fn verify(&self) -> bool {
+            true
+        }
+
+        fn execute(&self, _: &mut Fork) -> ExecutionResult {
+            Err(ExecutionError::new(0))
+        }
+    }
+
+    fn create_blockchain(service: Box<dyn Service>) -> Blockchain {
+        super::create_blockchain(MemoryDB::new(), vec![service])
+    }
+
+    // Creates a blockchain with two blocks containing transactions of `TestService`.
+    fn create_source() -> Blockchain {
+        let mut blockchain = create_blockchain(Box::new(TestService));
+        let consensus_secret_key = initialize_blockchain(&mut blockchain);
+
+        let (_, secret_key) = gen_keypair();
+        let blocks = [
+            vec![Tx::new(3, &secret_key)],
+            vec![Tx::new(4, &secret_key), Tx::new(0, &secret_key)],
+        ];
+        for txs in &blocks {
+            add_into_pool(&mut blockchain, txs);
+            let tx_hashes: Vec<Hash> = txs.iter().map(|tx| tx.hash()).collect();
+            commit_block(&mut blockchain, &consensus_secret_key, &tx_hashes);
+        }
+        blockchain
+    }
+
+    #[test]
+    fn test_replay() {
+        let source = create_source();
+        let mut blockchain = create_blockchain(Box::new(TestService));
+
+        let height = blockchain.replay(&*source.db).unwrap();
+        assert_eq!(height, Height(2));
+        assert_eq!(blockchain.last_hash(), source.last_hash());
+        let schema = Schema::new(blockchain.snapshot());
+        assert_eq!(schema.precommits(&source.last_hash()).len(), 1);
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let source = create_source();
+        let mut blockchain = create_blockchain(Box::new(FailingService));
+
+        let error = blockchain.replay(&*source.db).unwrap_err();
+        let divergence = error.downcast::<Divergence>().unwrap();
+        assert_eq!(divergence.height, Height(1));
+        assert_ne!(divergence.state_hash, divergence.expected_state_hash);
+        let names: Vec<_> = divergence
+            .diff
+            .unwrap()
+            .into_iter()
+            .map(|diff| diff.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "core.block_hashes_by_height",
+                "core.blocks",
+                "core.state_hash_aggregator",
+                "core.transaction_results",
+                "idx_name",
+            ]
+        );
+        // The diverged block is not committed.
+        assert_eq!(blockchain.last_block().height(), Height(0));
+    }
+
+    #[test]
+    fn test_replay_into_non_empty_blockchain() {
+        let source = create_source();
+        let mut blockchain = create_source();
+        assert!(blockchain.replay(&*source.db).is_err());
+    }
+}
+
+mod patch_log_tests {
+    use tempdir::TempDir;
+
+    use std::fs::{self, File};
+
+    use blockchain::{apply_patch_log, Blockchain, PatchLog, Schema, Service};
+    use crypto::{gen_keypair, SecretKey};
+    use helpers::Height;
+    use messages::Message;
+    use storage::{ListIndex, MemoryDB, StorageMetadata};
+
+    use super::{add_into_pool, initialize_blockchain, TestService, Tx, IDX_NAME};
+
+    fn create_blockchain(patch_log: PatchLog) -> (Blockchain, SecretKey) {
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        let mut blockchain = super::create_blockchain(MemoryDB::new(), services);
+        blockchain.set_patch_log(Some(patch_log));
+        let secret_key = initialize_blockchain(&mut blockchain);
+        (blockchain, secret_key)
+    }
+
+    fn commit_block(blockchain: &mut Blockchain, secret_key: &SecretKey, tx: &Tx) {
+        add_into_pool(blockchain, &[tx.clone()]);
+        super::commit_block(blockchain, secret_key, &[tx.hash()]);
+    }
+
+    fn values(blockchain: &Blockchain) -> Vec<u64> {
+        ListIndex::new(IDX_NAME, &blockchain.snapshot())
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_patch_log() {
+        let dir = TempDir::new("exonum_patch_log").unwrap();
+        let path = dir.path().join("patches");
+        let (mut blockchain, secret_key) = create_blockchain(PatchLog::open(&path).unwrap());
+        let (_, tx_secret_key) = gen_keypair();
+        let txs = [Tx::new(3, &tx_secret_key), Tx::new(4, &tx_secret_key)];
+        commit_block(&mut blockchain, &secret_key, &txs[0]);
+        commit_block(&mut blockchain, &secret_key, &txs[1]);
+
+        let db = MemoryDB::new();
+        let height = apply_patch_log(File::open(&path).unwrap(), &db).unwrap();
+        assert_eq!(height, Height(2));
+        // Applied records are skipped.
+        let height = apply_patch_log(File::open(&path).unwrap(), &db).unwrap();
+        assert_eq!(height, Height(2));
+
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        let follower = super::create_blockchain(db, services);
+        assert_eq!(follower.last_hash(), blockchain.last_hash());
+        assert_eq!(values(&follower), values(&blockchain));
+        assert_eq!(values(&follower), vec![3, 14, 4, 10]);
+        assert!(StorageMetadata::read(follower.snapshot()).is_ok());
+        // Transaction bodies are logged along with the blocks.
+        let snapshot = follower.snapshot();
+        let schema = Schema::new(&snapshot);
+        for tx in &txs {
+            assert_eq!(
+                schema.transactions().get(&tx.hash()),
+                Some(tx.raw().clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_patch_log_corrupted() {
+        let dir = TempDir::new("exonum_patch_log_corrupted").unwrap();
+        let path = dir.path().join("patches");
+        let (mut blockchain, secret_key) = create_blockchain(PatchLog::open(&path).unwrap());
+        commit_block(&mut blockchain, &secret_key, &Tx::new(3, &gen_keypair().1));
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(apply_patch_log(bytes.as_slice(), &MemoryDB::new()).is_err());
+
+        // Records of another blockchain are rejected.
+        let db = MemoryDB::new();
+        let (mut other, secret_key) =
+            create_blockchain(PatchLog::open(dir.path().join("other")).unwrap());
+        commit_block(&mut other, &secret_key, &Tx::new(3, &gen_keypair().1));
+        commit_block(&mut other, &secret_key, &Tx::new(4, &gen_keypair().1));
+        apply_patch_log(File::open(&path).unwrap(), &db).unwrap();
+        assert!(apply_patch_log(File::open(dir.path().join("other")).unwrap(), &db).is_err());
+    }
+}
+
+mod migration_tests {
+    use serde_json::Value;
+
+    use std::sync::Arc;
+
+    use blockchain::{migrate_services, Blockchain, Migration, Service, Transaction};
+    use crypto::{Hash, SecretKey};
+    use encoding::Error as MessageError;
+    use helpers::Height;
+    use messages::RawTransaction;
+    use storage::{self, Database, Fork, ListIndex, MemoryDB, Snapshot};
+
+    use super::initialize_blockchain;
+
+    const VALUES: &str = "migrated.values";
+
+    struct MigratedService {
+        version: u32,
+    }
+
+    impl Service for MigratedService {
+        fn service_id(&self) -> u16 {
+            3
+        }
+
+        fn service_name(&self) -> &'static str {
+            "migrated"
+        }
+
+        fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+            vec![]
+        }
+
+        fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+            unimplemented!()
+        }
+
+        fn initialize(&self, fork: &mut Fork) -> Value {
+            ListIndex::new(VALUES, fork).extend(vec![1_u64, 2, 3]);
+            Value::Null
+        }
+
+        fn data_version(&self) -> u32 {
+            self.version
+        }
+
+        fn migrations(&self) -> Vec<Migration> {
+            let migrations = vec![
+                Migration::new(1, "Double values", |fork: &mut Fork| {
+                    let values: Vec<u64> = ListIndex::new(VALUES, &*fork).iter().collect();
+                    let mut list = ListIndex::new(VALUES, fork);
+                    for (i, value) in values.into_iter().enumerate() {
+                        list.set(i as u64, value * 2);
+                    }
+                    Ok(())
+                }),
+                Migration::new(2, "Append a value", |fork: &mut Fork| {
+                    ListIndex::new(VALUES, fork).push(100_u64);
+                    Ok(())
+                })
+                .activate_at(Height(2)),
+            ];
+            migrations
+                .into_iter()
+                .filter(|migration| migration.version() <= self.version)
+                .collect()
+        }
+    }
+
+    fn create_blockchain(db: &Arc<dyn Database>, version: u32) -> (Blockchain, SecretKey) {
+        let services = vec![Box::new(MigratedService { version }) as Box<dyn Service>];
+        let mut blockchain = super::create_blockchain(Arc::clone(db), services);
+        let secret_key = initialize_blockchain(&mut blockchain);
+        (blockchain, secret_key)
+    }
+
+    fn commit_block(blockchain: &mut Blockchain, secret_key: &SecretKey) {
+        super::commit_block(blockchain, secret_key, &[]);
+    }
+
+    fn values(db: &Arc<dyn Database>) -> Vec<u64> {
+        ListIndex::new(VALUES, &db.snapshot()).iter().collect()
+    }
+
+    fn data_version(db: &Arc<dyn Database>) -> Option<u32> {
+        storage::service_data_version("migrated", &*db.snapshot())
+    }
+
+    #[test]
+    fn test_migrations() {
+        let db: Arc<dyn Database> = MemoryDB::new().into();
+        create_blockchain(&db, 0);
+        assert_eq!(data_version(&db), Some(0));
+
+        let services = vec![Box::new(MigratedService { version: 2 }) as Box<dyn Service>];
+        let reports = migrate_services(&*db, &services, true).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].version, 1);
+        assert_eq!(reports[0].activation_height, None);
+        assert_eq!(reports[0].changes[VALUES], 3);
+        assert_eq!(reports[1].version, 2);
+        assert_eq!(reports[1].activation_height, Some(Height(2)));
+        // Dry run does not change the database.
+        assert_eq!(values(&db), vec![1, 2, 3]);
+        assert_eq!(data_version(&db), Some(0));
+
+        let (mut blockchain, secret_key) = create_blockchain(&db, 2);
+        assert_eq!(values(&db), vec![2, 4, 6]);
+        assert_eq!(data_version(&db), Some(1));
+
+        commit_block(&mut blockchain, &secret_key);
+        assert_eq!(data_version(&db), Some(1));
+        commit_block(&mut blockchain, &secret_key);
+        assert_eq!(values(&db), vec![2, 4, 6, 100]);
+        assert_eq!(data_version(&db), Some(2));
+
+        // Applied migrations are not repeated.
+        commit_block(&mut blockchain, &secret_key);
+        assert_eq!(values(&db), vec![2, 4, 6, 100]);
+        assert!(migrate_services(&*db, &services, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrations_sync_from_genesis() {
+        // The network is started with data version 1, and its nodes are upgraded
+        // to version 2 before the activation height of the migration.
+        let source: Arc<dyn Database> = MemoryDB::new().into();
+        let (mut blockchain, secret_key) = create_blockchain(&source, 1);
+        commit_block(&mut blockchain, &secret_key);
+        let (mut blockchain, secret_key) = create_blockchain(&source, 2);
+        commit_block(&mut blockchain, &secret_key);
+        commit_block(&mut blockchain, &secret_key);
+        assert_eq!(values(&source), vec![1, 2, 3, 100]);
+
+        // A new node synchronizes the blockchain from the genesis block.
+        let db: Arc<dyn Database> = MemoryDB::new().into();
+        let services = vec![Box::new(MigratedService { version: 2 }) as Box<dyn Service>];
+        let mut blockchain = super::create_blockchain(Arc::clone(&db), services);
+        assert_eq!(blockchain.replay(&*source).unwrap(), Height(3));
+        assert_eq!(values(&db), values(&source));
+        assert_eq!(data_version(&db), Some(2));
+    }
+
+    #[test]
+    fn test_migrations_mismatch() {
+        let db: Arc<dyn Database> = MemoryDB::new().into();
+        create_blockchain(&db, 1);
+
+        // There is no migration to version 3.
+        let services = vec![Box::new(MigratedService { version: 3 }) as Box<dyn Service>];
+        assert!(migrate_services(&*db, &services, true).is_err());
+        // Data cannot be downgraded.
+        let services = vec![Box::new(MigratedService { version: 0 }) as Box<dyn Service>];
+        assert!(migrate_services(&*db, &services, false).is_err());
+    }
+}
+
+mod namespace_tests {
+    use blockchain::{
+        Blockchain, ExecutionResult, Schema, Service, Transaction, TransactionErrorType,
+    };
+    use crypto::{gen_keypair, Hash};
+    use encoding::Error as MessageError;
+    use helpers::{Height, ValidatorId};
+    use messages::{Message, RawTransaction};
+    use storage::{Fork, ListIndex, MemoryDB, Snapshot};
+
+    const OWNER_VALUES: &str = "owner.values";
+    const INTRUDER_VALUES: &str = "intruder.values";
+    const INTRUDER_SERVICE_ID: u16 = 2;
+
+    struct OwnerService;
+
+    impl Service for OwnerService {
+        fn service_id(&self) -> u16 {
+            1
+        }
+
+        fn service_name(&self) -> &'static str {
+            "owner"
+        }
+
+        fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+            vec![]
+        }
+
+        fn tx_from_raw(&self, _raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+            unimplemented!()
+        }
+
+        fn before_commit(&self, fork: &mut Fork) {
+            ListIndex::new(OWNER_VALUES, fork).push(1_u64);
+        }
+    }
+
+    struct IntruderService {
+        namespaces: Vec<String>,
+    }
+
+    impl Service for IntruderService {
+        fn service_id(&self) -> u16 {
+            INTRUDER_SERVICE_ID
+        }
+
+        fn service_name(&self) -> &'static str {
+            "intruder"
+        }
+
+        fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+            vec![]
+        }
+
+        fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+            Ok(Box::new(IntruderTx::from_raw(raw)?))
+        }
+
+        fn storage_namespaces(&self) -> Vec<String> {
+            self.namespaces.clone()
+        }
+    }
+
+    transactions! {
+        IntruderServiceTxs {
+            const SERVICE_ID = INTRUDER_SERVICE_ID;
+
+            struct IntruderTx {
+                overwrite: bool,
+            }
+        }
+    }
+
+    impl Transaction for IntruderTx {
+        fn verify(&self) -> bool {
+            true
+        }
+
+        fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+            let owner_len = ListIndex::<_, u64>::new(OWNER_VALUES, fork.as_ref()).len();
+            ListIndex::new(INTRUDER_VALUES, &mut *fork).push(owner_len);
+            if self.overwrite() {
+                ListIndex::new(OWNER_VALUES, fork).push(0_u64);
+            }
+            Ok(())
+        }
+    }
+
+    fn create_blockchain(namespaces: &[&str]) -> Blockchain {
+        let intruder = IntruderService {
+            namespaces: namespaces
+                .iter()
+                .map(|&namespace| namespace.to_owned())
+                .collect(),
+        };
+        let services = vec![
+            Box::new(OwnerService) as Box<dyn Service>,
+            Box::new(intruder) as Box<dyn Service>,
+        ];
+        super::create_blockchain(MemoryDB::new(), services)
+    }
+
+    #[test]
+    fn test_writes_outside_namespace() {
+        let mut blockchain = create_blockchain(&["intruder"]);
+        let (_, sec_key) = gen_keypair();
+        let tx_read = IntruderTx::new(false, &sec_key);
+        let tx_write = IntruderTx::new(true, &sec_key);
+
+        let patch = {
+            let mut fork = blockchain.fork();
+            {
+                let mut schema = Schema::new(&mut fork);
+                schema.add_transaction_into_pool(tx_read.raw().clone());
+                schema.add_transaction_into_pool(tx_write.raw().clone());
+            }
+            fork.into_patch()
+        };
+        blockchain.merge(patch).unwrap();
+
+        let (_, patch) = blockchain.create_patch(
+            ValidatorId::zero(),
+            Height::zero(),
+            &[tx_read.hash(), tx_write.hash()],
+        );
+        blockchain.merge(patch).unwrap();
+
+        let snapshot = blockchain.snapshot();
+        let intruder_values: Vec<u64> = ListIndex::new(INTRUDER_VALUES, &snapshot).iter().collect();
+        assert_eq!(intruder_values, vec![0]);
+        // The changes of the failed transaction are rolled back, while the service
+        // can change its own index in `before_commit`.
+        let owner_values: Vec<u64> = ListIndex::new(OWNER_VALUES, &snapshot).iter().collect();
+        assert_eq!(owner_values, vec![1]);
+
+        let schema = Schema::new(&snapshot);
+        assert_eq!(
+            schema.transaction_results().get(&tx_read.hash()),
+            Some(Ok(()))
+        );
+        let error = schema
+            .transaction_results()
+            .get(&tx_write.hash())
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.error_type(), TransactionErrorType::Panic);
+        assert!(error.description().unwrap().contains(OWNER_VALUES));
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps with namespace")]
+    fn test_overlapping_namespaces() {
+        create_blockchain(&["intruder", OWNER_VALUES]);
+    }
+}
+
+mod metadata_tests {
+    use super::{commit_block, initialize_blockchain};
+    use blockchain::{
+        Blockchain, ExecutionError, ExecutionResult, Schema, Service, Transaction,
+        TransactionErrorType, TransactionSet,
+    };
+    use crypto::{gen_keypair, CryptoHash, Hash, PublicKey, SecretKey};
+    use encoding::Error as MessageError;
+    use helpers::Height;
+    use messages::{Message, MultisigStatement, PartialSignature, RawTransaction, ThresholdPolicy};
+    use storage::{Fork, ListIndex, MemoryDB, Snapshot};
+
+    const VALUES: &str = "metadata.values";
+    const METADATA_SERVICE_ID: u16 = 4;
+
+    struct MetadataService;
+
+    impl Service for MetadataService {
+        fn service_id(&self) -> u16 {
+            METADATA_SERVICE_ID
+        }
+
+        fn service_name(&self) -> &'static str {
+            "metadata"
+        }
+
+        fn state_hash(&self, _snapshot: &dyn Snapshot) -> Vec<Hash> {
+            vec![]
+        }
+
+        fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, MessageError> {
+            let tx = MetadataServiceTxs::tx_from_raw(raw)?;
+            Ok(tx.into())
+        }
+    }
+
+    transactions! {
+        MetadataServiceTxs {
+            const SERVICE_ID = METADATA_SERVICE_ID;
+
+            struct MetadataTx {
+                from: &PublicKey,
+                sequence: u64,
+                valid_until_height: Height,
+                value: u64,
+            }
+
+            struct MultisigTx {
+                submitter: &PublicKey,
+                policy: ThresholdPolicy,
+                sequence: u64,
+                value: u64,
+                signatures: Vec<PartialSignature>,
+            }
+        }
+    }
+
+    impl Transaction for MetadataTx {
+        fn verify(&self) -> bool {
+            self.verify_signature(self.from())
+        }
+
+        fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+            if self.value() == 0 {
+                return Err(ExecutionError::new(0));
+            }
+            ListIndex::new(VALUES, fork).push(self.value());
+            Ok(())
+        }
+
+        fn valid_until(&self) -> Option<Height> {
+            Some(self.valid_until_height())
+        }
+
+        fn nonce(&self) -> Option<(&PublicKey, u64)> {
+            Some((self.from(), self.sequence()))
+        }
+    }
+
+    impl Transaction for MultisigTx {
+        fn verify(&self) -> bool {
+            let statement = self.multisig_statement().unwrap();
+            self.verify_signature(self.submitter())
+                && self.policy().verify(&statement, &self.signatures())
+        }
+
+        fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+            ListIndex::new(VALUES, fork).push(self.value());
+            Ok(())
+        }
+
+        fn multisig_statement(&self) -> Option<MultisigStatement> {
+            Some(MultisigStatement::for_message::<Self>(
+                self.submitter(),
+                &self.policy(),
+                self.sequence(),
+                &self.value().hash(),
+            ))
+        }
+    }
+
+    fn create_blockchain() -> (Blockchain, SecretKey) {
+        let services = vec![Box::new(MetadataService) as Box<dyn Service>];
+        let mut blockchain = super::create_blockchain(MemoryDB::new(), services);
+        let consensus_secret_key = initialize_blockchain(&mut blockchain);
+        (blockchain, consensus_secret_key)
+    }
+
+    fn add_into_pool(blockchain: &mut Blockchain, txs: &[&MetadataTx]) {
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = Schema::new(&mut fork);
+            for tx in txs {
+                schema.add_transaction_into_pool(tx.raw().clone());
+                schema.set_transaction_expiry(&tx.hash(), tx.valid_until_height());
+                schema.set_transaction_nonce(&tx.hash(), tx.from(), tx.sequence());
+            }
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+
+    #[test]
+    fn test_transaction_metadata() {
+        let (mut blockchain, consensus_secret_key) = create_blockchain();
+        let (public_key, secret_key) = gen_keypair();
+        let tx_ok = MetadataTx::new(&public_key, 1, Height(10), 1, &secret_key);
+        let tx_replayed = MetadataTx::new(&public_key, 1, Height(10), 2, &secret_key);
+        let tx_expired = MetadataTx::new(&public_key, 2, Height(0), 3, &secret_key);
+        let tx_failed = MetadataTx::new(&public_key, 3, Height(10), 0, &secret_key);
+        let tx_outdated = MetadataTx::new(&public_key, 3, Height(10), 5, &secret_key);
+        let txs = [&tx_ok, &tx_replayed, &tx_expired, &tx_failed, &tx_outdated];
+        add_into_pool(&mut blockchain, &txs);
+
+        let tx_hashes: Vec<_> = txs.iter().map(|tx| tx.hash()).collect();
+        commit_block(&mut blockchain, &consensus_secret_key, &tx_hashes);
+
+        let snapshot = blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let error_type = |tx: &MetadataTx| {
+            schema
+                .transaction_results()
+                .get(&tx.hash())
+                .unwrap()
+                .map_err(|e| e.error_type())
+        };
+        assert_eq!(error_type(&tx_ok), Ok(()));
+        assert_eq!(
+            error_type(&tx_replayed),
+            Err(TransactionErrorType::InvalidNonce)
+        );
+        assert_eq!(error_type(&tx_expired), Err(TransactionErrorType::Expired));
+        assert_eq!(error_type(&tx_failed), Err(TransactionErrorType::Code(0)));
+        assert_eq!(
+            error_type(&tx_outdated),
+            Err(TransactionErrorType::InvalidNonce)
+        );
+
+        let values: Vec<u64> = ListIndex::new(VALUES, &snapshot).iter().collect();
+        assert_eq!(values, vec![1]);
+        // The nonce of a failed transaction is kept.
+        assert_eq!(schema.sender_nonces().get(&public_key), Some(3));
+    }
+
+    #[test]
+    fn test_expired_transactions_removed_from_pool() {
+        let (mut blockchain, consensus_secret_key) = create_blockchain();
+        let (public_key, secret_key) = gen_keypair();
+        let tx_committed = MetadataTx::new(&public_key, 1, Height(1), 1, &secret_key);
+        let tx_first = MetadataTx::new(&public_key, 2, Height(1), 2, &secret_key);
+        let tx_second = MetadataTx::new(&public_key, 3, Height(2), 3, &secret_key);
+        add_into_pool(&mut blockchain, &[&tx_committed, &tx_first, &tx_second]);
+
+        commit_block(
+            &mut blockchain,
+            &consensus_secret_key,
+            &[tx_committed.hash()],
+        );
+        {
+            let snapshot = blockchain.snapshot();
+            let schema = Schema::new(&snapshot);
+            let pool: Vec<Hash> = schema.transactions_pool().iter().collect();
+            assert_eq!(pool, vec![tx_second.hash()]);
+            assert_eq!(schema.transactions_pool_len(), 1);
+            assert!(schema.transactions().contains(&tx_committed.hash()));
+            assert!(!schema.transactions().contains(&tx_first.hash()));
+        }
+
+        commit_block(&mut blockchain, &consensus_secret_key, &[]);
+        let snapshot = blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        assert_eq!(schema.transactions_pool_len(), 0);
+        assert!(schema.transactions_pool_expiry().iter().next().is_none());
+    }
+
+    #[test]
+    fn test_pending_nonces_ordered() {
+        let (mut blockchain, consensus_secret_key) = create_blockchain();
+        let (public_key, secret_key) = gen_keypair();
+        let (other_public_key, other_secret_key) = gen_keypair();
+        let tx_first = MetadataTx::new(&public_key, 5, Height(10), 1, &secret_key);
+        let tx_second = MetadataTx::new(&public_key, 6, Height(10), 2, &secret_key);
+        let tx_other = MetadataTx::new(&other_public_key, 1, Height(10), 3, &other_secret_key);
+        add_into_pool(&mut blockchain, &[&tx_second, &tx_other, &tx_first]);
+
+        let tx_hashes = {
+            let snapshot = blockchain.snapshot();
+            let schema = Schema::new(&snapshot);
+            let reversed = vec![tx_second.hash(), tx_other.hash(), tx_first.hash()];
+            assert_eq!(
+                schema.order_by_nonce(reversed),
+                vec![tx_first.hash(), tx_other.hash(), tx_second.hash()]
+            );
+            // The lowest nonce of the sender is taken from the pool.
+            assert_eq!(
+                schema.order_by_nonce(vec![tx_second.hash(), tx_other.hash()]),
+                vec![tx_first.hash(), tx_other.hash()]
+            );
+            schema.order_by_nonce(schema.transactions_pool().iter())
+        };
+        commit_block(&mut blockchain, &consensus_secret_key, &tx_hashes);
+
+        let snapshot = blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        for tx in &[&tx_first, &tx_second, &tx_other] {
+            assert_eq!(schema.transaction_results().get(&tx.hash()), Some(Ok(())));
+        }
+        assert_eq!(schema.sender_nonces().get(&public_key), Some(6));
+        assert!(schema.transactions_pool_nonces().iter().next().is_none());
+        assert!(schema.transactions_pool_senders().iter().next().is_none());
+    }
+
+    #[test]
+    fn test_multisig_statement_replay_rejected() {
+        let (mut blockchain, consensus_secret_key) = create_blockchain();
+        let keys: Vec<_> = (0..3).map(|_| gen_keypair()).collect();
+        let policy = ThresholdPolicy::new(2, keys.iter().map(|&(pk, _)| pk).collect());
+        let (submitter, submitter_key) = gen_keypair();
+        let statement =
+            MultisigStatement::for_message::<MultisigTx>(&submitter, &policy, 1, &1_u64.hash());
+        let signatures: Vec<_> = keys
+            .iter()
+            .map(|(pk, sk)| PartialSignature::sign(&statement, pk, sk))
+            .collect();
+        // The same statement authorized by different subsets of the signers.
+        let tx_first = MultisigTx::new(
+            &submitter,
+            policy.clone(),
+            1,
+            1,
+            signatures[..2].to_vec(),
+            &submitter_key,
+        );
+        let tx_replayed = MultisigTx::new(
+            &submitter,
+            policy.clone(),
+            1,
+            1,
+            signatures[1..].to_vec(),
+            &submitter_key,
+        );
+        assert!(tx_first.verify());
+        assert!(tx_replayed.verify());
+
+        let mut fork = blockchain.fork();
+        {
+            let mut schema = Schema::new(&mut fork);
+            schema.add_transaction_into_pool(tx_first.raw().clone());
+            schema.add_transaction_into_pool(tx_replayed.raw().clone());
+        }
+        blockchain.merge(fork.into_patch()).unwrap();
+
+        commit_block(&mut blockchain, &consensus_secret_key, &[tx_first.hash()]);
+        commit_block(
+            &mut blockchain,
+            &consensus_secret_key,
+            &[tx_replayed.hash()],
+        );
+
+        let snapshot = blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let results = schema.transaction_results();
+        assert_eq!(results.get(&tx_first.hash()), Some(Ok(())));
+        assert_eq!(
+            results
+                .get(&tx_replayed.hash())
+                .unwrap()
+                .map_err(|e| e.error_type()),
+            Err(TransactionErrorType::InvalidNonce)
+        );
+        let values: Vec<u64> = ListIndex::new(VALUES, &snapshot).iter().collect();
+        assert_eq!(values, vec![1]);
+        assert_eq!(schema.multisig_sequences().get(&policy.hash()), Some(1));
+    }
+}
+
+mod backup_tests {
+    use tempdir::TempDir;
+
+    use blockchain::{restore_backup, Blockchain, Schema, Service};
+    use crypto::{gen_keypair, Hash};
+    use messages::Message;
+    use storage::{open_database, DatabaseBackend, DbOptions};
+
+    use super::{add_into_pool, commit_block, initialize_blockchain, TestService, Tx};
+
+    fn state_hash(blockchain: &Blockchain) -> Hash {
+        Schema::new(&blockchain.snapshot())
+            .state_hash_aggregator()
+            .merkle_root()
+    }
+
+    #[test]
+    fn test_backup_restore() {
+        let dir = TempDir::new("exonum_backup_restore").unwrap();
+        let options = DbOptions {
+            backend: DatabaseBackend::LogFile,
+            ..DbOptions::default()
+        };
+        let db = open_database(dir.path().join("db"), &options).unwrap();
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        let mut blockchain = super::create_blockchain(db, services);
+        let secret_key = initialize_blockchain(&mut blockchain);
+        let (_, tx_secret_key) = gen_keypair();
+        for value in 1..4 {
+            let tx = Tx::new(value, &tx_secret_key);
+            add_into_pool(&mut blockchain, &[tx.clone()]);
+            commit_block(&mut blockchain, &secret_key, &[tx.hash()]);
+        }
+
+        let backup_path = dir.path().join("backup");
+        let info = blockchain.create_backup(&backup_path).unwrap();
+        assert_eq!(info.block_hash, blockchain.last_hash());
+
+        let restored_path = dir.path().join("restored");
+        let restored_info = restore_backup(&backup_path, &restored_path, &options).unwrap();
+        assert_eq!(restored_info, info);
+
+        let db = open_database(&restored_path, &options).unwrap();
+        let services = vec![Box::new(TestService) as Box<dyn Service>];
+        let restored = super::create_blockchain(db, services);
+        assert_eq!(restored.last_hash(), blockchain.last_hash());
+        assert_eq!(state_hash(&restored), state_hash(&blockchain));
+
+        // A backup is not restored over an existing database.
+        assert!(restore_backup(&backup_path, &restored_path, &options).is_err());
+    }
+}
@@ -18,10 +18,12 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use std::{any::Any, borrow::Cow, convert::Into, error::Error, fmt, u8};
 
-use crypto::{CryptoHash, Hash};
+use super::Schema;
+use crypto::{CryptoHash, Hash, PublicKey};
 use encoding::{self, serialize::json::ExonumJson};
-use messages::{Message, RawTransaction};
-use storage::{Fork, StorageValue};
+use helpers::Height;
+use messages::{Message, MultisigStatement, RawTransaction};
+use storage::{Fork, Snapshot, StorageValue};
 
 //  User-defined error codes (`TransactionErrorType::Code(u8)`) have a `0...255` range.
 #[cfg_attr(feature = "cargo-clippy", allow(cast_lossless))]
@@ -30,6 +32,10 @@ const MAX_ERROR_CODE: u16 = u8::max_value() as u16;
 const TRANSACTION_STATUS_OK: u16 = MAX_ERROR_CODE + 1;
 // `Err(TransactionErrorType::Panic)`.
 const TRANSACTION_STATUS_PANIC: u16 = TRANSACTION_STATUS_OK + 1;
+// `Err(TransactionErrorType::Expired)`.
+const TRANSACTION_STATUS_EXPIRED: u16 = TRANSACTION_STATUS_PANIC + 1;
+// `Err(TransactionErrorType::InvalidNonce)`.
+const TRANSACTION_STATUS_INVALID_NONCE: u16 = TRANSACTION_STATUS_EXPIRED + 1;
 
 /// Returns a result of the `Transaction` `execute` method. This result may be
 /// either an empty unit type, in case of success, or an `ExecutionError`, if execution has
@@ -143,6 +149,104 @@ pub trait Transaction: Message + ExonumJson + 'static {
     /// }
     /// # fn main() {}
     fn execute(&self, fork: &mut Fork) -> ExecutionResult;
+
+    /// Returns the height of the latest block into which the transaction can be committed.
+    ///
+    /// An expired transaction is not accepted into the transaction pool, and a transaction
+    /// in the pool is removed from it once it expires. If an expired transaction is included
+    /// into a block nevertheless, it is not executed and its result is
+    /// `TransactionErrorType::Expired`.
+    ///
+    /// *Default implementation returns `None`, so the transaction never expires.*
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate exonum;
+    /// #
+    /// use exonum::blockchain::Transaction;
+    /// use exonum::crypto::PublicKey;
+    /// use exonum::helpers::Height;
+    /// use exonum::messages::Message;
+    /// # use exonum::blockchain::ExecutionResult;
+    /// # use exonum::storage::Fork;
+    ///
+    /// transactions! {
+    ///     MyTransactions {
+    ///         const SERVICE_ID = 1;
+    ///
+    ///         struct MyTransaction {
+    ///             from: &PublicKey,
+    ///             sequence: u64,
+    ///             valid_until_height: Height,
+    ///             // Other fields...
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// impl Transaction for MyTransaction {
+    ///     fn verify(&self) -> bool {
+    ///         self.verify_signature(self.from())
+    ///     }
+    ///
+    ///     fn valid_until(&self) -> Option<Height> {
+    ///         Some(self.valid_until_height())
+    ///     }
+    ///
+    ///     fn nonce(&self) -> Option<(&PublicKey, u64)> {
+    ///         Some((self.from(), self.sequence()))
+    ///     }
+    ///
+    ///     // Other methods...
+    ///     // ...
+    /// #   fn execute(&self, _: &mut Fork) -> ExecutionResult { Ok(()) }
+    /// }
+    /// # fn main() {}
+    /// ```
+    fn valid_until(&self) -> Option<Height> {
+        None
+    }
+
+    /// Returns the sender of the transaction along with the nonce protecting the transaction
+    /// from replays. The transaction should be signed by the sender.
+    ///
+    /// The core keeps the nonce of the latest committed transaction of each sender
+    /// in [`Schema::sender_nonces`]. A transaction is accepted into the transaction pool and
+    /// executed only if its nonce is greater than the kept one; otherwise, the transaction
+    /// is not executed and its result is `TransactionErrorType::InvalidNonce`. The nonce
+    /// is kept even if the execution of the transaction fails. Several transactions of
+    /// the sender may wait in the pool; the leader proposes them in the ascending order
+    /// of their nonces.
+    ///
+    /// See [`valid_until`](#method.valid_until) for an example.
+    ///
+    /// *Default implementation returns `None`, so the nonce is not checked.*
+    ///
+    /// [`Schema::sender_nonces`]: struct.Schema.html#method.sender_nonces
+    fn nonce(&self) -> Option<(&PublicKey, u64)> {
+        None
+    }
+
+    /// Returns the statement signed by the partial signatures of a multisignature
+    /// transaction. The transaction should check the signatures against the statement
+    /// in `verify`.
+    ///
+    /// The core keeps the sequence number of the latest committed statement of each threshold
+    /// policy in [`Schema::multisig_sequences`]. A transaction is accepted into the transaction
+    /// pool and executed only if the sequence number of its statement is greater than the kept
+    /// one; otherwise, the transaction is not executed and its result is
+    /// `TransactionErrorType::InvalidNonce`. The sequence number is kept even if the execution
+    /// of the transaction fails.
+    ///
+    /// See [`MultisigStatement`] for an example.
+    ///
+    /// *Default implementation returns `None`, so no sequence number is checked.*
+    ///
+    /// [`Schema::multisig_sequences`]: struct.Schema.html#method.multisig_sequences
+    /// [`MultisigStatement`]: ../messages/struct.MultisigStatement.html
+    fn multisig_statement(&self) -> Option<MultisigStatement> {
+        None
+    }
 }
 
 /// Result of unsuccessful transaction execution.
@@ -188,6 +292,12 @@ pub enum TransactionErrorType {
     /// User-defined error code. Can have different meanings for different transactions and
     /// services.
     Code(u8),
+    /// The transaction has not been executed because it has expired,
+    /// see `Transaction::valid_until`.
+    Expired,
+    /// The transaction has not been executed because its nonce is not greater than the nonce
+    /// of the previous transaction of the sender, see `Transaction::nonce`.
+    InvalidNonce,
 }
 
 /// Result of unsuccessful transaction execution encompassing both service and framework-wide error
@@ -257,6 +367,16 @@ impl TransactionError {
         Self::new(TransactionErrorType::Panic, description)
     }
 
+    /// Creates a new `TransactionError` representing an expired transaction.
+    pub(crate) fn expired(description: Option<String>) -> Self {
+        Self::new(TransactionErrorType::Expired, description)
+    }
+
+    /// Creates a new `TransactionError` representing a transaction with an invalid nonce.
+    pub(crate) fn invalid_nonce(description: Option<String>) -> Self {
+        Self::new(TransactionErrorType::InvalidNonce, description)
+    }
+
     /// Creates a new `TransactionError` instance from `std::thread::Result`'s `Err`.
     pub(crate) fn from_panic(panic: &Box<dyn Any + Send>) -> Self {
         Self::panic(panic_description(panic))
@@ -285,6 +405,8 @@ impl fmt::Display for TransactionError {
         match self.error_type {
             TransactionErrorType::Panic => write!(f, "Panic during execution")?,
             TransactionErrorType::Code(c) => write!(f, "Error code: {}", c)?,
+            TransactionErrorType::Expired => write!(f, "Transaction expired")?,
+            TransactionErrorType::InvalidNonce => write!(f, "Invalid nonce")?,
         }
 
         if let Some(ref description) = self.description {
@@ -339,6 +461,8 @@ impl StorageValue for TransactionResult {
             value @ 0...MAX_ERROR_CODE => Err(TransactionError::code(value as u8, description)),
             TRANSACTION_STATUS_OK => Ok(()),
             TRANSACTION_STATUS_PANIC => Err(TransactionError::panic(description)),
+            TRANSACTION_STATUS_EXPIRED => Err(TransactionError::expired(description)),
+            TRANSACTION_STATUS_INVALID_NONCE => Err(TransactionError::invalid_nonce(description)),
             value => panic!("Invalid TransactionResult value: {}", value),
         }
     }
@@ -350,10 +474,53 @@ fn status_as_u16(status: &TransactionResult) -> u16 {
         Err(ref e) => match e.error_type {
             TransactionErrorType::Panic => TRANSACTION_STATUS_PANIC,
             TransactionErrorType::Code(c) => u16::from(c),
+            TransactionErrorType::Expired => TRANSACTION_STATUS_EXPIRED,
+            TransactionErrorType::InvalidNonce => TRANSACTION_STATUS_INVALID_NONCE,
         },
     }
 }
 
+/// Checks the validity period and the nonce of the transaction against the blockchain state
+/// before the transaction is committed into the block of the given height.
+pub(crate) fn check_transaction_metadata<T: AsRef<dyn Snapshot>>(
+    tx: &dyn Transaction,
+    height: Height,
+    schema: &Schema<T>,
+) -> Result<(), TransactionError> {
+    if let Some(valid_until) = tx.valid_until() {
+        if valid_until < height {
+            return Err(TransactionError::expired(Some(format!(
+                "Transaction is valid until height {}, block height is {}",
+                valid_until, height
+            ))));
+        }
+    }
+    if let Some((sender, nonce)) = tx.nonce() {
+        if let Some(last_nonce) = schema.sender_nonces().get(sender) {
+            if nonce <= last_nonce {
+                return Err(TransactionError::invalid_nonce(Some(format!(
+                    "Nonce {} is not greater than the latest nonce {} of the sender",
+                    nonce, last_nonce
+                ))));
+            }
+        }
+    }
+    if let Some(statement) = tx.multisig_statement() {
+        let sequences = schema.multisig_sequences();
+        if let Some(last_sequence) = sequences.get(statement.policy_hash()) {
+            if statement.sequence() <= last_sequence {
+                return Err(TransactionError::invalid_nonce(Some(format!(
+                    "Sequence number {} is not greater than the latest sequence number {} \
+                     of the threshold policy",
+                    statement.sequence(),
+                    last_sequence
+                ))));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// `TransactionSet` trait describes a type which is an `enum` of several transactions.
 /// The implementation of this trait is generated automatically by the `transactions!`
 /// macro.
@@ -661,6 +828,7 @@ mod tests {
     use storage::{Database, Entry, MemoryDB, Snapshot};
 
     const TX_RESULT_SERVICE_ID: u16 = 255;
+    const ENTRY_NAME: &str = "transaction_status_test";
 
     lazy_static! {
         static ref EXECUTION_STATUS: Mutex<ExecutionResult> = Mutex::new(Ok(()));
@@ -876,6 +1044,10 @@ mod tests {
         ) -> Result<Box<dyn Transaction>, encoding::Error> {
             Ok(Box::new(TxResult::from_raw(raw)?))
         }
+
+        fn storage_namespaces(&self) -> Vec<String> {
+            vec![ENTRY_NAME.to_owned()]
+        }
     }
 
     transactions! {
@@ -901,6 +1073,6 @@ mod tests {
     }
 
     fn create_entry(fork: &mut Fork) -> Entry<&mut Fork, u64> {
-        Entry::new("transaction_status_test", fork)
+        Entry::new(ENTRY_NAME, fork)
     }
 }
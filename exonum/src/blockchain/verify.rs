@@ -0,0 +1,337 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline verification of the blockchain storage consistency.
+
+use failure::Fail;
+
+use std::{collections::HashSet, fmt};
+
+use super::{
+    config::StoredConfiguration,
+    schema::{
+        BLOCKS, BLOCK_HASHES_BY_HEIGHT, BLOCK_TRANSACTIONS, CONFIGS, CONFIGS_ACTUAL_FROM,
+        PRECOMMITS, STATE_HASH_AGGREGATOR, TRANSACTION_RESULTS,
+    },
+    Blockchain, Schema, Service,
+};
+use crypto::{CryptoHash, Hash};
+use helpers::Height;
+use messages::{Message, Precommit, CONSENSUS as CORE_SERVICE};
+use node::state::State;
+use storage::{Database, MemoryDB, ProofMapIndex, Snapshot};
+
+/// An inconsistency found in the blockchain storage by [`verify_blockchain`].
+///
+/// [`verify_blockchain`]: fn.verify_blockchain.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inconsistency {
+    /// Height of the block being verified.
+    pub height: Height,
+    /// Name of the index with inconsistent data.
+    pub index_name: String,
+    /// Description of the inconsistency.
+    pub description: String,
+}
+
+impl fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Inconsistency at height {} in index '{}': {}",
+            self.height, self.index_name, self.description
+        )
+    }
+}
+
+impl Fail for Inconsistency {}
+
+/// Verifies the consistency of the blockchain storage.
+///
+/// Blocks are verified in the ascending order of their heights. For each block, the
+/// following is checked:
+///
+/// - The block is stored under its hash and has the corresponding height.
+/// - `prev_hash` of the block is the hash of the previous block.
+/// - `tx_hash` and `tx_count` of the block match the list of its transactions.
+/// - Precommits of the block are signed by the majority of validators of the
+///   configuration actual at the height of the block. Precommits of the genesis
+///   block and of pruned blocks are not checked.
+///
+/// Finally, the state hash of the latest block is recomputed from the root hashes of
+/// the core tables and of the tables of the given `services`, the same way as it is done
+/// when a block is created. A root hash that differs from the entry of the state hash
+/// aggregator is reported with the name of the core index or with the name of the service
+/// and the number of its table.
+///
+/// Returns the height of the latest block, or the first inconsistency found.
+///
+/// # Panics
+///
+/// Panics if the stored data cannot be decoded, like any other read of corrupted indices.
+pub fn verify_blockchain<'a, I>(
+    snapshot: &dyn Snapshot,
+    services: I,
+) -> Result<Height, Inconsistency>
+where
+    I: IntoIterator<Item = &'a Box<dyn Service>>,
+{
+    let mut verifier = Verifier {
+        schema: Schema::new(snapshot),
+        height: Height::zero(),
+        index_name: BLOCK_HASHES_BY_HEIGHT,
+    };
+
+    let blocks_count = verifier.schema.block_hashes_by_height().len();
+    if blocks_count == 0 {
+        return Err(verifier.error("database does not contain blocks"));
+    }
+
+    let mut block_hash = Hash::zero();
+    for height in 0..blocks_count {
+        verifier.height = Height(height);
+        block_hash = verifier.verify_block(&block_hash)?;
+    }
+    verifier.verify_state_hash(&block_hash, snapshot, services)?;
+
+    Ok(verifier.height)
+}
+
+struct Verifier<'a> {
+    schema: Schema<&'a dyn Snapshot>,
+    height: Height,
+    // Name of the index being verified, which is reported in case of an inconsistency.
+    index_name: &'static str,
+}
+
+impl<'a> Verifier<'a> {
+    fn error<S: Into<String>>(&self, description: S) -> Inconsistency {
+        self.error_in(self.index_name, description)
+    }
+
+    fn error_in<N, S>(&self, index_name: N, description: S) -> Inconsistency
+    where
+        N: Into<String>,
+        S: Into<String>,
+    {
+        Inconsistency {
+            height: self.height,
+            index_name: index_name.into(),
+            description: description.into(),
+        }
+    }
+
+    // Verifies the block at the current height and returns its hash.
+    fn verify_block(&mut self, prev_hash: &Hash) -> Result<Hash, Inconsistency> {
+        self.index_name = BLOCK_HASHES_BY_HEIGHT;
+        let block_hash = self.schema
+            .block_hash_by_height(self.height)
+            .ok_or_else(|| self.error("block hash is absent"))?;
+
+        self.index_name = BLOCKS;
+        let block = self.schema
+            .blocks()
+            .get(&block_hash)
+            .ok_or_else(|| self.error(format!("block {:?} is absent", block_hash)))?;
+        if block.hash() != block_hash {
+            return Err(self.error(format!("block does not match its hash {:?}", block_hash)));
+        }
+        if block.height() != self.height {
+            return Err(self.error(format!("block has height {}", block.height())));
+        }
+        if block.prev_hash() != prev_hash {
+            return Err(self.error(format!(
+                "prev_hash {:?} does not match the previous block {:?}",
+                block.prev_hash(),
+                prev_hash
+            )));
+        }
+
+        self.index_name = BLOCK_TRANSACTIONS;
+        let transactions = self.schema.block_transactions(self.height);
+        if *block.tx_hash() != transactions.merkle_root() {
+            return Err(self.error("tx_hash of the block does not match its transactions"));
+        }
+        if u64::from(block.tx_count()) != transactions.len() {
+            return Err(self.error(format!(
+                "tx_count of the block is {}, while it contains {} transactions",
+                block.tx_count(),
+                transactions.len()
+            )));
+        }
+
+        if self.height > Height::zero() && !self.schema.is_pruned(self.height) {
+            self.verify_precommits(&block_hash)?;
+        }
+        Ok(block_hash)
+    }
+
+    fn verify_precommits(&mut self, block_hash: &Hash) -> Result<(), Inconsistency> {
+        let config = self.configuration()?;
+        let validators = &config.validator_keys;
+
+        self.index_name = PRECOMMITS;
+        let precommits: Vec<Precommit> = self.schema.precommits(block_hash).iter().collect();
+        let majority_count = State::byzantine_majority_count(validators.len());
+        if precommits.len() < majority_count {
+            return Err(self.error(format!(
+                "{} precommits are not enough for the majority of {} validators",
+                precommits.len(),
+                validators.len()
+            )));
+        }
+
+        let round = precommits[0].round();
+        let mut voted = HashSet::with_capacity(precommits.len());
+        for precommit in &precommits {
+            let validator = precommit.validator();
+            let keys = validators.get(validator.0 as usize).ok_or_else(|| {
+                self.error(format!("precommit of unknown validator {}", validator))
+            })?;
+            if !voted.insert(validator) {
+                return Err(self.error(format!("several precommits of validator {}", validator)));
+            }
+            if !precommit.verify_signature(&keys.consensus_key) {
+                return Err(self.error(format!(
+                    "precommit of validator {} has invalid signature",
+                    validator
+                )));
+            }
+            if precommit.block_hash() != block_hash || precommit.height() != self.height
+                || precommit.round() != round
+            {
+                return Err(self.error(format!(
+                    "precommit of validator {} does not match the block",
+                    validator
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the configuration actual at the current height.
+    fn configuration(&mut self) -> Result<StoredConfiguration, Inconsistency> {
+        self.index_name = CONFIGS_ACTUAL_FROM;
+        let height = self.height;
+        let config_ref = self.schema
+            .configs_actual_from()
+            .iter()
+            .take_while(|config_ref| config_ref.actual_from() <= height)
+            .last()
+            .ok_or_else(|| self.error("no configuration is actual at this height"))?;
+
+        self.index_name = CONFIGS;
+        let config_hash = config_ref.cfg_hash();
+        self.schema.configs().get(config_hash).ok_or_else(|| {
+            self.error(format!("configuration {:?} is absent", config_hash))
+        })
+    }
+
+    fn verify_state_hash<'b, I>(
+        &mut self,
+        block_hash: &Hash,
+        snapshot: &dyn Snapshot,
+        services: I,
+    ) -> Result<(), Inconsistency>
+    where
+        I: IntoIterator<Item = &'b Box<dyn Service>>,
+    {
+        self.index_name = STATE_HASH_AGGREGATOR;
+        // Looks up the name of the core table by its key in the state hash aggregator.
+        let core_table_name = |key: &Hash| {
+            [CONFIGS, TRANSACTION_RESULTS]
+                .iter()
+                .enumerate()
+                .find(|&(idx, _)| Blockchain::service_table_unique_key(CORE_SERVICE, idx) == *key)
+                .map(|(_, &name)| name)
+        };
+        // Entries of the state hash aggregator with the names of the tables they belong to.
+        let mut entries = Vec::new();
+        for (idx, table_hash) in self.schema.core_state_hash().into_iter().enumerate() {
+            let key = Blockchain::service_table_unique_key(CORE_SERVICE, idx);
+            let name = match core_table_name(&key) {
+                Some(name) => name,
+                None => {
+                    return Err(self.error(format!(
+                        "entry {:?} of the core state hash does not belong to any core table",
+                        key
+                    )))
+                }
+            };
+            entries.push((key, name.to_owned(), table_hash));
+        }
+        for service in services {
+            let service_id = service.service_id();
+            for (idx, table_hash) in service.state_hash(snapshot).into_iter().enumerate() {
+                let key = Blockchain::service_table_unique_key(service_id, idx);
+                let name = format!("{} (table {})", service.service_name(), idx);
+                entries.push((key, name, table_hash));
+            }
+        }
+
+        let aggregator = self.schema.state_hash_aggregator();
+        for &(ref key, ref name, ref table_hash) in &entries {
+            match aggregator.get(key) {
+                Some(ref stored_hash) if stored_hash == table_hash => {}
+                Some(stored_hash) => {
+                    return Err(self.error_in(
+                        name.as_str(),
+                        format!(
+                            "root hash {:?} does not match the entry {:?} of the state hash \
+                             aggregator",
+                            table_hash, stored_hash
+                        ),
+                    ))
+                }
+                None => {
+                    return Err(self.error_in(
+                        name.as_str(),
+                        "root hash is absent in the state hash aggregator",
+                    ))
+                }
+            }
+        }
+        if let Some(key) = aggregator
+            .keys()
+            .find(|key| entries.iter().all(|entry| entry.0 != *key))
+        {
+            return Err(self.error(format!("entry {:?} does not belong to any table", key)));
+        }
+
+        // The root hash is recomputed in a separate database, so that the hashes
+        // of branches stored along with the index are not taken into account.
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let recomputed_root = {
+            let mut index = ProofMapIndex::new(STATE_HASH_AGGREGATOR, &mut fork);
+            for (key, _, table_hash) in entries {
+                index.put(&key, table_hash);
+            }
+            index.merkle_root()
+        };
+        if aggregator.merkle_root() != recomputed_root {
+            return Err(self.error("stored root hash does not match the index entries"));
+        }
+
+        let block = self.schema.blocks().get(block_hash).unwrap();
+        if *block.state_hash() != recomputed_root {
+            return Err(self.error(format!(
+                "state_hash of the latest block {:?} does not match the root hash {:?}",
+                block.state_hash(),
+                recomputed_root
+            )));
+        }
+        Ok(())
+    }
+}
@@ -183,8 +183,19 @@ impl<'a> BlockInfo<'a> {
         Ref::map(self.txs.borrow(), |cache| cache.as_ref().unwrap().as_ref())
     }
 
+    /// Returns `true` if transaction bodies and precommits of this block have been pruned
+    /// from the storage.
+    pub fn is_pruned(&self) -> bool {
+        self.explorer.is_pruned(self.height())
+    }
+
     /// Returns a transaction with the specified index in the block.
+    ///
+    /// Returns `None` if there is no such transaction, or if the block has been pruned.
     pub fn transaction(&self, index: usize) -> Option<CommittedTransaction> {
+        if self.is_pruned() {
+            return None;
+        }
         self.transaction_hashes()
             .get(index)
             .map(|hash| self.explorer.committed_transaction(hash, None))
@@ -200,7 +211,17 @@ impl<'a> BlockInfo<'a> {
     }
 
     /// Loads transactions and precommits for the block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block has been pruned.
     pub fn with_transactions(self) -> BlockWithTransactions {
+        assert!(
+            !self.is_pruned(),
+            "Block at height {} has been pruned",
+            self.height()
+        );
+
         let (explorer, header, precommits, transactions) =
             (self.explorer, self.header, self.precommits, self.txs);
 
@@ -372,6 +393,13 @@ impl<'a, T> IntoIterator for &'a BlockWithTransactions<T> {
 /// { type: 'panic', description?: string }
 /// ```
 ///
+/// For transactions that have not been executed because they have expired or their nonce
+/// is outdated, `status` has the type `expired` or `invalid-nonce` respectively:
+///
+/// ```javascript
+/// { type: 'expired' | 'invalid-nonce', description?: string }
+/// ```
+///
 /// [`Transaction`]: ../blockchain/trait.Transaction.html
 /// [`TxLocation`]: ../blockchain/struct.TxLocation.html
 /// [`ListProof`]: ../storage/enum.ListProof.html
@@ -453,6 +481,8 @@ enum TxStatus<'a> {
     Success,
     Panic { description: &'a str },
     Error { code: u8, description: &'a str },
+    Expired { description: &'a str },
+    InvalidNonce { description: &'a str },
 }
 
 impl<'a> TxStatus<'a> {
@@ -484,6 +514,8 @@ impl<'a> From<&'a TransactionResult> for TxStatus<'a> {
                 match e.error_type() {
                     Panic => TxStatus::Panic { description },
                     Code(code) => TxStatus::Error { code, description },
+                    Expired => TxStatus::Expired { description },
+                    InvalidNonce => TxStatus::InvalidNonce { description },
                 }
             }
         }
@@ -506,6 +538,12 @@ impl<'a> From<TxStatus<'a>> for TransactionResult {
             TxStatus::Error { code, description } => {
                 Err(TransactionError::code(code, to_option(description)))
             }
+            TxStatus::Expired { description } => {
+                Err(TransactionError::expired(to_option(description)))
+            }
+            TxStatus::InvalidNonce { description } => {
+                Err(TransactionError::invalid_nonce(to_option(description)))
+            }
         }
     }
 }
@@ -729,6 +767,22 @@ impl<'a> BlockchainExplorer<'a> {
         }
     }
 
+    /// Creates a new `BlockchainExplorer` instance over the blockchain state right after
+    /// the block at the given height has been committed.
+    ///
+    /// Returns `None` if the state at this height is not available; see
+    /// [`Blockchain::snapshot_at`] for details.
+    ///
+    /// [`Blockchain::snapshot_at`]: ../blockchain/struct.Blockchain.html#method.snapshot_at
+    pub fn at_height(blockchain: &'a Blockchain, height: Height) -> Option<Self> {
+        blockchain
+            .snapshot_at(height)
+            .map(|snapshot| BlockchainExplorer {
+                snapshot,
+                transaction_parser: Box::new(move |raw| blockchain.tx_from_raw(raw)),
+            })
+    }
+
     /// Returns information about the transaction identified by the hash.
     pub fn transaction(&self, tx_hash: &Hash) -> Option<TransactionInfo> {
         let schema = Schema::new(&self.snapshot);
@@ -797,6 +851,23 @@ impl<'a> BlockchainExplorer<'a> {
         }
     }
 
+    /// Returns `true` if transaction bodies and precommits of the block at the given height
+    /// have been pruned from the storage.
+    pub fn is_pruned(&self, height: Height) -> bool {
+        Schema::new(&self.snapshot).is_pruned(height)
+    }
+
+    /// Returns `true` if the transaction with the given hash has been committed,
+    /// but its body has been pruned from the storage.
+    pub fn is_transaction_pruned(&self, tx_hash: &Hash) -> bool {
+        let schema = Schema::new(&self.snapshot);
+        !schema.transactions().contains(tx_hash)
+            && schema
+                .transactions_locations()
+                .get(tx_hash)
+                .map_or(false, |location| schema.is_pruned(location.block_height()))
+    }
+
     /// Returns the height of the blockchain.
     pub fn height(&self) -> Height {
         let schema = Schema::new(&self.snapshot);
@@ -813,9 +884,12 @@ impl<'a> BlockchainExplorer<'a> {
     }
 
     /// Returns block together with its transactions for the specified height, or `None`
-    /// if there is no such block.
+    /// if there is no such block or it has been pruned.
     pub fn block_with_txs(&self, height: Height) -> Option<BlockWithTransactions> {
         let schema = Schema::new(&self.snapshot);
+        if schema.is_pruned(height) {
+            return None;
+        }
         let txs_table = schema.block_transactions(height);
         let block_proof = schema.block_and_precommits(height);
 
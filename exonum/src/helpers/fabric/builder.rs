@@ -78,6 +78,22 @@ impl NodeBuilder {
                 let node = Node::new(db, services, config, config_file_path);
                 Some(node)
             }
+            Feedback::Migrate(ref ctx) => {
+                let services: Vec<Box<dyn Service>> = self.service_factories
+                    .into_iter()
+                    .map(|mut factory| factory.make_service(ctx))
+                    .collect();
+                Maintenance::migrate(ctx, &services);
+                None
+            }
+            Feedback::Verify(ref ctx) => {
+                let services: Vec<Box<dyn Service>> = self.service_factories
+                    .into_iter()
+                    .map(|mut factory| factory.make_service(ctx))
+                    .collect();
+                Maintenance::verify(ctx, &services);
+                None
+            }
             _ => None,
         }
     }
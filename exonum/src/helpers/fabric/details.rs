@@ -21,6 +21,7 @@ use toml;
 
 use std::{
     collections::{BTreeMap, HashMap}, fs, net::{IpAddr, SocketAddr}, path::{Path, PathBuf},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, thread, time::Duration,
 };
 
 use super::{
@@ -35,9 +36,13 @@ use blockchain::{config::ValidatorKeys, GenesisConfig};
 use crypto;
 use helpers::{config::ConfigFile, generate_testnet_config};
 use node::{ConnectListConfig, NodeApiConfig, NodeConfig};
-use storage::{Database, DbOptions, RocksDB};
+use storage::{
+    open_database, Database, DbOptions, MemoryDB, Patch, Result as StorageResult, Snapshot,
+    TableStats,
+};
 
 const DATABASE_PATH: &str = "DATABASE_PATH";
+const MEMORY_DB_PATH: &str = "MEMORY_DB_PATH";
 const OUTPUT_DIR: &str = "OUTPUT_DIR";
 const PEER_ADDRESS: &str = "PEER_ADDRESS";
 const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
@@ -46,15 +51,21 @@ const PRIVATE_API_ADDRESS: &str = "PRIVATE_API_ADDRESS";
 const PUBLIC_ALLOW_ORIGIN: &str = "PUBLIC_ALLOW_ORIGIN";
 const PRIVATE_ALLOW_ORIGIN: &str = "PRIVATE_ALLOW_ORIGIN";
 
+// Interval between the saves of the in-memory database of `run-dev`.
+const MEMORY_DB_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Run command.
 pub struct Run;
 
 impl Run {
     /// Returns created database instance.
     pub fn db_helper(ctx: &Context, options: &DbOptions) -> Box<dyn Database> {
+        if let Ok(path) = ctx.arg::<String>(MEMORY_DB_PATH) {
+            return Box::new(PersistentMemoryDB::open(PathBuf::from(path)));
+        }
         let path = ctx.arg::<String>(DATABASE_PATH)
             .unwrap_or_else(|_| panic!("{} not found.", DATABASE_PATH));
-        Box::new(RocksDB::open(Path::new(&path), options).expect("Can't load database file"))
+        open_database(Path::new(&path), options).expect("Can't load database file")
     }
 
     fn node_config_path(ctx: &Context) -> String {
@@ -154,6 +165,94 @@ impl Command for Run {
     }
 }
 
+// In-memory database of `run-dev`, which is saved to a file, so that the blockchain is
+// restored after a restart. The database is saved in the background if it has been changed
+// since the previous save, and when it is dropped; changes made after the latest save are
+// lost if the process is killed.
+struct PersistentMemoryDB {
+    inner: Arc<PersistentMemoryDBInner>,
+}
+
+struct PersistentMemoryDBInner {
+    db: MemoryDB,
+    path: PathBuf,
+    // Indicates that the database has been changed since the previous save.
+    changed: AtomicBool,
+    // Prevents concurrent saves to the same file.
+    save_lock: Mutex<()>,
+}
+
+impl PersistentMemoryDB {
+    fn open(path: PathBuf) -> Self {
+        let db = if path.exists() {
+            info!("Loading in-memory database from {}", path.display());
+            MemoryDB::load_from(&path).expect("Can't load in-memory database file")
+        } else {
+            MemoryDB::new()
+        };
+        let inner = Arc::new(PersistentMemoryDBInner {
+            db,
+            path,
+            changed: AtomicBool::new(false),
+            save_lock: Mutex::new(()),
+        });
+
+        // The thread stops once the database is dropped.
+        let weak_inner = Arc::downgrade(&inner);
+        thread::spawn(move || loop {
+            thread::sleep(MEMORY_DB_SAVE_INTERVAL);
+            match weak_inner.upgrade() {
+                Some(inner) => inner.save(),
+                None => break,
+            }
+        });
+        Self { inner }
+    }
+}
+
+impl PersistentMemoryDBInner {
+    fn save(&self) {
+        let _guard = self.save_lock.lock().unwrap();
+        if !self.changed.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = self.db.save_to(&self.path) {
+            self.changed.store(true, Ordering::SeqCst);
+            error!(
+                "Can't save in-memory database to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+impl Database for PersistentMemoryDB {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        self.inner.db.snapshot()
+    }
+
+    fn merge(&self, patch: Patch) -> StorageResult<()> {
+        self.inner.db.merge(patch)?;
+        self.inner.changed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn merge_sync(&self, patch: Patch) -> StorageResult<()> {
+        self.merge(patch)
+    }
+
+    fn table_stats(&self, name: &str) -> TableStats {
+        self.inner.db.table_stats(name)
+    }
+}
+
+impl Drop for PersistentMemoryDB {
+    fn drop(&mut self) {
+        self.inner.save();
+    }
+}
+
 /// Command for running service in dev mode.
 pub struct RunDev;
 
@@ -170,6 +269,19 @@ impl RunDev {
         path.to_str().expect("Expected correct path").into()
     }
 
+    fn memory_db_path(ctx: &Context) -> Option<PathBuf> {
+        ctx.arg::<String>(MEMORY_DB_PATH).ok().map(PathBuf::from)
+    }
+
+    // Checks whether the in-memory blockchain saved by the previous run can be restored.
+    // The blockchain is restored along with the configuration, which contains
+    // the keys of the validator.
+    fn can_restore_memory_db(ctx: &Context) -> bool {
+        let node_config_path = Self::artifacts_path("output.toml", ctx);
+        Self::memory_db_path(ctx).map_or(false, |path| path.exists())
+            && Path::new(&node_config_path).exists()
+    }
+
     fn set_config_command_arguments(ctx: &mut Context) {
         let common_config_path = Self::artifacts_path("common.toml", &ctx);
         let validators_count = "1";
@@ -231,14 +343,25 @@ impl RunDev {
 
 impl Command for RunDev {
     fn args(&self) -> Vec<Argument> {
-        vec![Argument::new_named(
-            "ARTIFACTS_DIR",
-            false,
-            "The path where configuration and db files will be generated.",
-            "a",
-            "artifacts-dir",
-            false,
-        )]
+        vec![
+            Argument::new_named(
+                "ARTIFACTS_DIR",
+                false,
+                "The path where configuration and db files will be generated.",
+                "a",
+                "artifacts-dir",
+                false,
+            ),
+            Argument::new_named(
+                MEMORY_DB_PATH,
+                false,
+                "Keep the blockchain in memory and save it to the given file, \
+                 so that it is restored after a restart.",
+                None,
+                "memory-db",
+                false,
+            ),
+        ]
     }
 
     fn name(&self) -> CommandName {
@@ -257,11 +380,23 @@ impl Command for RunDev {
     ) -> Feedback {
         let db_path = Self::artifacts_path("db", &context);
         context.set_arg(DATABASE_PATH, db_path);
-        Self::cleanup(&context);
+        let restore = Self::can_restore_memory_db(&context);
+        if !restore {
+            Self::cleanup(&context);
+            if let Some(path) = Self::memory_db_path(&context) {
+                if path.exists() {
+                    fs::remove_file(path).expect("Expected MEMORY_DB_PATH file being removable.");
+                }
+            }
+        }
 
         Self::set_config_command_arguments(&mut context);
         let context = exts(context);
-        let context = Self::generate_config(commands, context);
+        let context = if restore {
+            context
+        } else {
+            Self::generate_config(commands, context)
+        };
 
         commands
             .get(Run.name())
@@ -23,6 +23,10 @@ use super::{Argument, CommandExtension, CommandName, Context};
 pub enum Feedback {
     /// Run node with current context.
     RunNode(Context),
+    /// Report the pending migrations of the service data with current context.
+    Migrate(Context),
+    /// Verify the blockchain storage with current context.
+    Verify(Context),
     /// Do nothing
     None,
 }
@@ -14,15 +14,21 @@
 
 //! This module implements node maintenance actions.
 
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap, fs::{File, OpenOptions}, io::{self, BufReader, BufWriter, Read},
+    path::Path,
+};
 
 use super::{
-    internal::{CollectedCommand, Command, Feedback}, Argument, CommandName, Context,
+    internal::{CollectedCommand, Command, Feedback}, keys, Argument, CommandName, Context,
+};
+use blockchain::{
+    create_backup, export_dump, import_dump, migrate_services, restore_backup, verify_blockchain,
+    Schema, Service,
 };
-use blockchain::Schema;
 use helpers::config::ConfigFile;
 use node::NodeConfig;
-use storage::{Database, DbOptions, RocksDB};
+use storage::{open_database, storage_stats, Database, DbOptions};
 
 // Context entry for the path to the node config.
 const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
@@ -30,10 +36,37 @@ const NODE_CONFIG_PATH: &str = "NODE_CONFIG_PATH";
 const DATABASE_PATH: &str = "DATABASE_PATH";
 // Context entry for the type of action to be performed.
 const MAINTENANCE_ACTION_PATH: &str = "MAINTENANCE_ACTION_PATH";
+// Context entry for the number of the latest blocks to keep while pruning.
+const KEEP_BLOCKS: &str = "KEEP_BLOCKS";
+// Context entry for the path to the backup directory.
+const BACKUP_PATH: &str = "BACKUP_PATH";
+// Context entry for the path to the dump file.
+const DUMP_PATH: &str = "DUMP_PATH";
 
 /// Maintenance command. Supported actions:
 ///
 /// - `clear-cache` - clear message cache.
+/// - `prune` - prune transaction bodies and precommits of old blocks. The number of
+///   the latest blocks to keep is taken from the `--keep-blocks` argument or, if it is
+///   absent, from the `keep_blocks` database option of the node config.
+/// - `backup` - create a backup of the database in the `--backup-path` directory.
+///   The node must be stopped; use the `v1/backup` endpoint of the private API
+///   to create a backup of a running node.
+/// - `restore` - restore the backup from the `--backup-path` directory into a new
+///   database at `--db-path`.
+/// - `export` - write a portable dump of the database into the `--dump-path` file.
+/// - `import` - import the dump from the `--dump-path` file into a new database
+///   at `--db-path`, checking the integrity of the dump and the latest block.
+///   The dump is read from the standard input if `--dump-path` is `-`.
+/// - `verify` - check the consistency of the stored blocks, their transactions and
+///   precommits, and recompute the state hash of the latest block from the core and service
+///   tables. The first inconsistency found is reported with the height of the block and
+///   the name of the index.
+/// - `stats` - print the number of keys and the approximate size of each index,
+///   from the largest to the smallest one.
+/// - `migrate` - report the pending migrations of the service data and the number of entries
+///   each of them would change, without changing the database. The migrations are applied
+///   by the node at startup or at their activation heights.
 #[derive(Debug)]
 pub struct Maintenance;
 
@@ -44,10 +77,24 @@ impl Maintenance {
         ConfigFile::load(path).expect("Can't load node config file")
     }
 
+    fn database_path(ctx: &Context) -> String {
+        ctx.arg::<String>(DATABASE_PATH)
+            .unwrap_or_else(|_| panic!("{} not found.", DATABASE_PATH))
+    }
+
+    fn backup_path(ctx: &Context) -> String {
+        ctx.arg::<String>(BACKUP_PATH)
+            .unwrap_or_else(|_| panic!("{} not found.", BACKUP_PATH))
+    }
+
+    fn dump_path(ctx: &Context) -> String {
+        ctx.arg::<String>(DUMP_PATH)
+            .unwrap_or_else(|_| panic!("{} not found.", DUMP_PATH))
+    }
+
     fn database(ctx: &Context, options: &DbOptions) -> Box<dyn Database> {
-        let path = ctx.arg::<String>(DATABASE_PATH)
-            .unwrap_or_else(|_| panic!("{} not found.", DATABASE_PATH));
-        Box::new(RocksDB::open(Path::new(&path), options).expect("Can't load database file"))
+        let path = Self::database_path(ctx);
+        open_database(Path::new(&path), options).expect("Can't load database file")
     }
 
     fn clear_cache(context: &Context) {
@@ -64,6 +111,159 @@ impl Maintenance {
 
         info!("Cache cleared successfully");
     }
+
+    fn prune(context: &Context) {
+        let config = Self::node_config(context);
+        let keep_blocks = context
+            .arg::<u64>(KEEP_BLOCKS)
+            .ok()
+            .or(config.database.keep_blocks)
+            .expect("Number of blocks to keep is specified neither in arguments nor in config");
+        assert!(keep_blocks > 0, "At least one block should be kept");
+
+        info!("Pruning blocks, keeping {} latest ones", keep_blocks);
+
+        let db = Self::database(context, &config.database);
+        let mut fork = db.fork();
+        let pruned_height = Schema::new(&mut fork).prune_blocks(keep_blocks);
+        db.merge_sync(fork.into_patch()).expect("Can't prune blocks");
+
+        match pruned_height {
+            Some(height) => info!("Blocks up to height {} are pruned", height),
+            None => info!("There are no blocks to prune"),
+        }
+    }
+
+    fn backup(context: &Context) {
+        let config = Self::node_config(context);
+        let backup_path = Self::backup_path(context);
+        info!("Creating backup in {}", backup_path);
+
+        let db = Self::database(context, &config.database);
+        let info = create_backup(&*db, &backup_path).expect("Can't create backup");
+
+        info!("Backup created successfully at height {}", info.height);
+    }
+
+    fn restore(context: &Context) {
+        let config = Self::node_config(context);
+        let backup_path = Self::backup_path(context);
+        let db_path = Self::database_path(context);
+        info!("Restoring backup from {} into {}", backup_path, db_path);
+
+        let info =
+            restore_backup(&backup_path, &db_path, &config.database).expect("Can't restore backup");
+
+        info!("Backup restored successfully at height {}", info.height);
+    }
+
+    fn export(context: &Context) {
+        let config = Self::node_config(context);
+        let dump_path = Self::dump_path(context);
+        info!("Exporting database into {}", dump_path);
+
+        let db = Self::database(context, &config.database);
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&dump_path)
+            .expect("Can't create dump file");
+        let info = export_dump(&*db, BufWriter::new(file)).expect("Can't export database");
+
+        info!("Database exported successfully at height {}", info.height);
+    }
+
+    fn import(context: &Context) {
+        let config = Self::node_config(context);
+        let dump_path = Self::dump_path(context);
+        info!(
+            "Importing dump from {} into {}",
+            dump_path,
+            Self::database_path(context)
+        );
+
+        let reader: Box<dyn Read> = if dump_path == "-" {
+            Box::new(io::stdin())
+        } else {
+            Box::new(File::open(&dump_path).expect("Can't open dump file"))
+        };
+        let db = Self::database(context, &config.database);
+        let info = import_dump(BufReader::new(reader), &*db).expect("Can't import dump");
+
+        info!("Dump imported successfully at height {}", info.height);
+    }
+
+    /// Verifies the blockchain storage, including the state tables of the given services.
+    /// The services are created by `NodeBuilder` from the context returned by the `verify`
+    /// action.
+    pub(crate) fn verify(context: &Context, services: &[Box<dyn Service>]) {
+        let config = Self::node_config(context);
+        info!("Verifying blockchain");
+
+        let db = Self::database(context, &config.database);
+        match verify_blockchain(&*db.snapshot(), services) {
+            Ok(height) => info!("Blockchain verified successfully up to height {}", height),
+            Err(e) => panic!("Blockchain verification failed. {}", e),
+        }
+    }
+
+    fn stats(context: &Context) {
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+
+        let mut stats = storage_stats(&*db);
+        stats.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+        println!(
+            "{:<48} {:<12} {:>14} {:>16}",
+            "Index", "Type", "Keys", "Size (bytes)"
+        );
+        for index in &stats {
+            let index_type = if index.is_family {
+                format!("{:?}*", index.index_type)
+            } else {
+                format!("{:?}", index.index_type)
+            };
+            println!(
+                "{:<48} {:<12} {:>14} {:>16}",
+                index.name, index_type, index.key_count, index.size
+            );
+        }
+        println!(
+            "{:<48} {:<12} {:>14} {:>16}",
+            "Total",
+            "",
+            stats.iter().map(|index| index.key_count).sum::<u64>(),
+            stats.iter().map(|index| index.size).sum::<u64>()
+        );
+        println!("* - index family, the figures cover all its members.");
+    }
+
+    /// Reports the pending migrations of the given services. The services are created
+    /// by `NodeBuilder` from the context returned by the `migrate` action.
+    pub(crate) fn migrate(context: &Context, services: &[Box<dyn Service>]) {
+        let config = Self::node_config(context);
+        let db = Self::database(context, &config.database);
+
+        let reports = migrate_services(&*db, services, true).expect("Can't migrate service data");
+        if reports.is_empty() {
+            println!("There are no pending migrations");
+            return;
+        }
+        for report in &reports {
+            let activation = match report.activation_height {
+                Some(height) => format!("at height {}", height),
+                None => "at startup".to_owned(),
+            };
+            println!(
+                "{} -> version {} ({}): {}",
+                report.service_name, report.version, activation, report.description
+            );
+            for (index, count) in &report.changes {
+                println!("    {:<48} {:>14}", index, count);
+            }
+        }
+    }
 }
 
 impl Command for Maintenance {
@@ -93,6 +293,30 @@ impl Command for Maintenance {
                 "action",
                 false,
             ),
+            Argument::new_named(
+                KEEP_BLOCKS,
+                false,
+                "Number of the latest blocks to keep while pruning.",
+                None,
+                "keep-blocks",
+                false,
+            ),
+            Argument::new_named(
+                BACKUP_PATH,
+                false,
+                "Path to the backup directory.",
+                None,
+                "backup-path",
+                false,
+            ),
+            Argument::new_named(
+                DUMP_PATH,
+                false,
+                "Path to the dump file, or `-` to import the dump from the standard input.",
+                None,
+                "dump-path",
+                false,
+            ),
         ]
     }
 
@@ -101,7 +325,8 @@ impl Command for Maintenance {
     }
 
     fn about(&self) -> &str {
-        "Maintenance module. Available actions: clear-cache."
+        "Maintenance module. Available actions: clear-cache, prune, backup, restore, export, \
+         import, verify, stats, migrate."
     }
 
     fn execute(
@@ -116,6 +341,28 @@ impl Command for Maintenance {
 
         if action == "clear-cache" {
             Self::clear_cache(&context);
+        } else if action == "prune" {
+            Self::prune(&context);
+        } else if action == "backup" {
+            Self::backup(&context);
+        } else if action == "restore" {
+            Self::restore(&context);
+        } else if action == "export" {
+            Self::export(&context);
+        } else if action == "import" {
+            Self::import(&context);
+        } else if action == "verify" {
+            let mut context = context;
+            let config = Self::node_config(&context);
+            context.set(keys::NODE_CONFIG, config);
+            return Feedback::Verify(context);
+        } else if action == "stats" {
+            Self::stats(&context);
+        } else if action == "migrate" {
+            let mut context = context;
+            let config = Self::node_config(&context);
+            context.set(keys::NODE_CONFIG, config);
+            return Feedback::Migrate(context);
         } else {
             println!("Unsupported maintenance action: {}", action);
         }
@@ -47,6 +47,7 @@ extern crate chrono;
 #[macro_use(crate_version, crate_authors)]
 extern crate clap;
 extern crate env_logger;
+#[cfg(feature = "rocksdb")]
 extern crate exonum_rocksdb as rocksdb;
 #[cfg(feature = "sodiumoxide-crypto")]
 extern crate exonum_sodiumoxide as sodiumoxide;
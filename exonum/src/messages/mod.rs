@@ -15,6 +15,10 @@
 //! Consensus and other messages and related utilities.
 
 pub use self::{
+    multisig::{
+        MultisigError, MultisigStatement, PartialSignature, SignatureSet, ThresholdPolicy,
+        MULTISIG_SIGNATURE_DOMAIN,
+    },
     protocol::*,
     raw::{
         Message, MessageBuffer, MessageWriter, RawMessage, ServiceMessage, HEADER_LENGTH,
@@ -32,6 +36,7 @@ use helpers::{Height, Round, ValidatorId};
 
 #[macro_use]
 mod spec;
+mod multisig;
 mod protocol;
 mod raw;
 
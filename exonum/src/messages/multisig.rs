@@ -0,0 +1,608 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Multisignature transactions.
+//!
+//! A multisignature transaction carries a payload, which is usually an `encoding_struct`,
+//! and several [`PartialSignature`]s. The transaction is authorized if the signatures satisfy
+//! a [`ThresholdPolicy`], i.e., if at least `threshold` of the policy signers have signed it.
+//!
+//! The signers do not sign the payload itself, but a [`MultisigStatement`], which binds
+//! the hash of the payload to the transaction type, the submitter of the transaction,
+//! the policy and a sequence number. The signed data is prefixed with
+//! [`MULTISIG_SIGNATURE_DOMAIN`], so the partial signatures cannot be confused with
+//! the signatures of other data. The transaction returns its statement from
+//! [`Transaction::multisig_statement`], and the core executes only the statements with
+//! the sequence numbers greater than the latest committed one of the policy. Thus
+//! the signatures cannot be replayed under another submitter, in another transaction or
+//! after the statement has been committed.
+//!
+//! All types are `encoding_struct`s, so they can be used as the fields of the transactions
+//! declared with the `transactions!` macro and are serialized both in the binary and
+//! in the JSON form.
+//!
+//! Partial signatures may be collected off-chain with [`SignatureSet`] or with the
+//! `v1/multisig` endpoints of the private explorer API before the transaction is submitted.
+//!
+//! [`PartialSignature`]: struct.PartialSignature.html
+//! [`ThresholdPolicy`]: struct.ThresholdPolicy.html
+//! [`MultisigStatement`]: struct.MultisigStatement.html
+//! [`MULTISIG_SIGNATURE_DOMAIN`]: constant.MULTISIG_SIGNATURE_DOMAIN.html
+//! [`SignatureSet`]: struct.SignatureSet.html
+//! [`Transaction::multisig_statement`]:
+//! ../blockchain/trait.Transaction.html#method.multisig_statement
+
+use failure::Fail;
+
+use std::{collections::HashSet, fmt};
+
+use super::ServiceMessage;
+use crypto::{self, CryptoHash, Hash, PublicKey, SecretKey, Signature};
+
+/// Prefix of the data signed by the partial signatures, which separates them from
+/// the signatures of other data.
+pub const MULTISIG_SIGNATURE_DOMAIN: &[u8] = b"exonum.multisig.v1:";
+
+encoding_struct! {
+    /// Statement signed by the signers of a multisignature transaction.
+    ///
+    /// The transaction should return its statement from [`Transaction::multisig_statement`],
+    /// so that the core rejects the statements with the already used sequence numbers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate exonum;
+    /// use exonum::blockchain::{ExecutionResult, Transaction};
+    /// use exonum::crypto::{gen_keypair, CryptoHash, PublicKey};
+    /// use exonum::messages::{Message, MultisigStatement, PartialSignature, ThresholdPolicy};
+    /// use exonum::storage::Fork;
+    ///
+    /// encoding_struct! {
+    ///     struct Transfer {
+    ///         to: &PublicKey,
+    ///         amount: u64,
+    ///     }
+    /// }
+    ///
+    /// transactions! {
+    ///     TreasuryTransactions {
+    ///         const SERVICE_ID = 1;
+    ///
+    ///         struct TxTreasuryTransfer {
+    ///             submitter: &PublicKey,
+    ///             sequence: u64,
+    ///             transfer: Transfer,
+    ///             signatures: Vec<PartialSignature>,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # fn treasury_policy() -> ThresholdPolicy {
+    /// #     ThresholdPolicy::new(2, vec![gen_keypair().0, gen_keypair().0, gen_keypair().0])
+    /// # }
+    /// impl Transaction for TxTreasuryTransfer {
+    ///     fn verify(&self) -> bool {
+    ///         let statement = self.multisig_statement().unwrap();
+    ///         self.verify_signature(self.submitter())
+    ///             && treasury_policy().verify(&statement, &self.signatures())
+    ///     }
+    ///
+    ///     fn execute(&self, _fork: &mut Fork) -> ExecutionResult {
+    ///         // Transfer funds from the treasury.
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn multisig_statement(&self) -> Option<MultisigStatement> {
+    ///         Some(MultisigStatement::for_message::<Self>(
+    ///             self.submitter(),
+    ///             &treasury_policy(),
+    ///             self.sequence(),
+    ///             &self.transfer().hash(),
+    ///         ))
+    ///     }
+    /// }
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`Transaction::multisig_statement`]:
+    /// ../blockchain/trait.Transaction.html#method.multisig_statement
+    struct MultisigStatement {
+        /// Identifier of the service of the transaction.
+        service_id: u16,
+        /// Identifier of the transaction type within the service.
+        message_type: u16,
+        /// Public key of the submitter of the transaction.
+        submitter: &PublicKey,
+        /// Hash of the threshold policy authorizing the transaction.
+        policy_hash: &Hash,
+        /// Sequence number of the statement among the statements of the policy.
+        sequence: u64,
+        /// Hash of the payload of the transaction.
+        payload_hash: &Hash,
+    }
+}
+
+impl MultisigStatement {
+    /// Creates a statement for the transaction of the given type.
+    pub fn for_message<M: ServiceMessage>(
+        submitter: &PublicKey,
+        policy: &ThresholdPolicy,
+        sequence: u64,
+        payload_hash: &Hash,
+    ) -> Self {
+        Self::new(
+            M::SERVICE_ID,
+            M::MESSAGE_ID,
+            submitter,
+            &policy.hash(),
+            sequence,
+            payload_hash,
+        )
+    }
+
+    // Returns the data signed by the partial signatures.
+    fn signed_data(&self) -> Vec<u8> {
+        let mut data = MULTISIG_SIGNATURE_DOMAIN.to_vec();
+        data.extend_from_slice(self.hash().as_ref());
+        data
+    }
+}
+
+encoding_struct! {
+    /// Signature of a single signer over the statement of a multisignature transaction.
+    struct PartialSignature {
+        /// Public key of the signer.
+        public_key: &PublicKey,
+        /// Signature over the statement.
+        signature: &Signature,
+    }
+}
+
+impl PartialSignature {
+    /// Signs the statement with the given keys.
+    pub fn sign(
+        statement: &MultisigStatement,
+        public_key: &PublicKey,
+        secret_key: &SecretKey,
+    ) -> Self {
+        let signature = crypto::sign(&statement.signed_data(), secret_key);
+        Self::new(public_key, &signature)
+    }
+
+    /// Verifies the signature against the statement.
+    pub fn verify(&self, statement: &MultisigStatement) -> bool {
+        crypto::verify(
+            self.signature(),
+            &statement.signed_data(),
+            self.public_key(),
+        )
+    }
+}
+
+encoding_struct! {
+    /// M-of-N policy requiring the statement to be signed by at least `threshold`
+    /// of the `signers`.
+    struct ThresholdPolicy {
+        /// Minimum number of the signers required to authorize the statement.
+        threshold: u32,
+        /// Public keys of the signers.
+        signers: Vec<PublicKey>,
+    }
+}
+
+impl ThresholdPolicy {
+    /// Checks that the threshold is positive and does not exceed the number of the signers,
+    /// and that the signers are distinct.
+    pub fn is_valid(&self) -> bool {
+        let signers = self.signers();
+        let distinct: HashSet<_> = signers.iter().collect();
+        self.threshold() > 0
+            && self.threshold() as usize <= signers.len()
+            && distinct.len() == signers.len()
+    }
+
+    /// Returns `true` if the given key belongs to one of the signers.
+    pub fn is_signer(&self, public_key: &PublicKey) -> bool {
+        self.signers().contains(public_key)
+    }
+
+    /// Verifies the signatures of the statement against the policy.
+    ///
+    /// The signatures are accepted if the policy is valid, the statement is made for this
+    /// policy, each signature is valid and made by a distinct signer of the policy, and
+    /// the number of the signatures is not less than the threshold.
+    ///
+    /// Note that the sequence number of the statement is not checked here, see
+    /// [`Transaction::multisig_statement`].
+    ///
+    /// [`Transaction::multisig_statement`]:
+    /// ../blockchain/trait.Transaction.html#method.multisig_statement
+    pub fn verify(&self, statement: &MultisigStatement, signatures: &[PartialSignature]) -> bool {
+        if !self.is_valid()
+            || statement.policy_hash() != &self.hash()
+            || signatures.len() < self.threshold() as usize
+        {
+            return false;
+        }
+
+        let signers = self.signers();
+        let mut signed = HashSet::new();
+        signatures.iter().all(|signature| {
+            signers.contains(signature.public_key())
+                && signed.insert(*signature.public_key())
+                && signature.verify(statement)
+        })
+    }
+}
+
+/// An error occurred while adding a partial signature to a [`SignatureSet`].
+///
+/// [`SignatureSet`]: struct.SignatureSet.html
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultisigError {
+    /// The threshold policy is not valid.
+    InvalidPolicy,
+    /// The statement is made for another threshold policy.
+    PolicyMismatch,
+    /// The signer does not belong to the policy signers.
+    UnknownSigner(PublicKey),
+    /// The signature does not match the statement.
+    InvalidSignature(PublicKey),
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MultisigError::InvalidPolicy => write!(f, "Threshold policy is not valid"),
+            MultisigError::PolicyMismatch => {
+                write!(f, "Statement is made for another threshold policy")
+            }
+            MultisigError::UnknownSigner(ref key) => {
+                write!(f, "Key {:?} is not a signer of the policy", key)
+            }
+            MultisigError::InvalidSignature(ref key) => {
+                write!(f, "Signature of key {:?} is not valid", key)
+            }
+        }
+    }
+}
+
+impl Fail for MultisigError {}
+
+/// Partial signatures of a statement collected off-chain before the multisignature transaction
+/// is submitted.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::crypto::{gen_keypair, hash, CryptoHash};
+/// use exonum::messages::{MultisigStatement, PartialSignature, SignatureSet, ThresholdPolicy};
+///
+/// let keys = vec![gen_keypair(), gen_keypair(), gen_keypair()];
+/// let policy = ThresholdPolicy::new(2, keys.iter().map(|&(pk, _)| pk).collect());
+/// let (submitter, _) = gen_keypair();
+/// let statement = MultisigStatement::new(1, 0, &submitter, &policy.hash(), 1, &hash(b"payload"));
+///
+/// let mut set = SignatureSet::new(statement.clone(), policy.clone()).unwrap();
+/// for (pk, sk) in &keys[..2] {
+///     set.add(PartialSignature::sign(&statement, pk, sk)).unwrap();
+/// }
+/// assert!(set.is_complete());
+/// assert!(policy.verify(&statement, set.signatures()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureSet {
+    statement: MultisigStatement,
+    policy: ThresholdPolicy,
+    signatures: Vec<PartialSignature>,
+}
+
+impl SignatureSet {
+    /// Creates an empty set of signatures of the statement.
+    ///
+    /// Returns an error if the policy is not valid or if the statement is made
+    /// for another policy.
+    pub fn new(
+        statement: MultisigStatement,
+        policy: ThresholdPolicy,
+    ) -> Result<Self, MultisigError> {
+        if !policy.is_valid() {
+            return Err(MultisigError::InvalidPolicy);
+        }
+        if statement.policy_hash() != &policy.hash() {
+            return Err(MultisigError::PolicyMismatch);
+        }
+        Ok(Self {
+            statement,
+            policy,
+            signatures: Vec::new(),
+        })
+    }
+
+    /// Returns the signed statement.
+    pub fn statement(&self) -> &MultisigStatement {
+        &self.statement
+    }
+
+    /// Returns the threshold policy of the statement.
+    pub fn policy(&self) -> &ThresholdPolicy {
+        &self.policy
+    }
+
+    /// Returns the collected signatures in the order of addition.
+    pub fn signatures(&self) -> &[PartialSignature] {
+        &self.signatures
+    }
+
+    /// Returns `true` if the collected signatures satisfy the threshold of the policy.
+    pub fn is_complete(&self) -> bool {
+        self.signatures.len() >= self.policy.threshold() as usize
+    }
+
+    /// Adds the signature to the set.
+    ///
+    /// Returns `false` if a signature of the same signer has already been added,
+    /// in which case the set is not changed.
+    pub fn add(&mut self, signature: PartialSignature) -> Result<bool, MultisigError> {
+        let public_key = *signature.public_key();
+        if !self.policy.is_signer(&public_key) {
+            return Err(MultisigError::UnknownSigner(public_key));
+        }
+        if !signature.verify(&self.statement) {
+            return Err(MultisigError::InvalidSignature(public_key));
+        }
+        if self
+            .signatures
+            .iter()
+            .any(|existing| existing.public_key() == &public_key)
+        {
+            return Ok(false);
+        }
+        self.signatures.push(signature);
+        Ok(true)
+    }
+
+    /// Converts the set into the collected signatures.
+    pub fn into_signatures(self) -> Vec<PartialSignature> {
+        self.signatures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use super::*;
+    use crypto::{gen_keypair, hash};
+    use messages::{Message, RawMessage};
+
+    encoding_struct! {
+        struct Withdrawal {
+            to: &PublicKey,
+            amount: u64,
+        }
+    }
+
+    messages! {
+        const SERVICE_ID = 1;
+
+        struct TxWithdraw {
+            submitter: &PublicKey,
+            sequence: u64,
+            withdrawal: Withdrawal,
+            signatures: Vec<PartialSignature>,
+        }
+
+        struct TxDeposit {
+            submitter: &PublicKey,
+            sequence: u64,
+            withdrawal: Withdrawal,
+            signatures: Vec<PartialSignature>,
+        }
+    }
+
+    fn keys(count: usize) -> Vec<(PublicKey, SecretKey)> {
+        (0..count).map(|_| gen_keypair()).collect()
+    }
+
+    fn policy(threshold: u32, keys: &[(PublicKey, SecretKey)]) -> ThresholdPolicy {
+        ThresholdPolicy::new(threshold, keys.iter().map(|&(pk, _)| pk).collect())
+    }
+
+    fn statement(policy: &ThresholdPolicy, sequence: u64) -> MultisigStatement {
+        MultisigStatement::new(
+            1,
+            0,
+            &PublicKey::zero(),
+            &policy.hash(),
+            sequence,
+            &hash(b"payload"),
+        )
+    }
+
+    fn sign(
+        statement: &MultisigStatement,
+        keys: &[(PublicKey, SecretKey)],
+    ) -> Vec<PartialSignature> {
+        keys.iter()
+            .map(|(pk, sk)| PartialSignature::sign(statement, pk, sk))
+            .collect()
+    }
+
+    #[test]
+    fn test_policy_validity() {
+        let keys = keys(3);
+        assert!(policy(1, &keys).is_valid());
+        assert!(policy(3, &keys).is_valid());
+        assert!(!policy(0, &keys).is_valid());
+        assert!(!policy(4, &keys).is_valid());
+        assert!(!ThresholdPolicy::new(1, vec![keys[0].0, keys[0].0]).is_valid());
+    }
+
+    #[test]
+    fn test_threshold_verification() {
+        let keys = keys(3);
+        let policy = policy(2, &keys);
+        let statement = statement(&policy, 1);
+        let signatures = sign(&statement, &keys);
+
+        assert!(policy.verify(&statement, &signatures[..2]));
+        assert!(policy.verify(&statement, &signatures));
+        assert!(!policy.verify(&statement, &signatures[..1]));
+
+        // Duplicate signers are not counted twice.
+        let duplicates = vec![signatures[0].clone(), signatures[0].clone()];
+        assert!(!policy.verify(&statement, &duplicates));
+
+        // Signatures of keys outside of the policy are rejected.
+        let (pk, sk) = gen_keypair();
+        let mut foreign = signatures[..2].to_vec();
+        foreign.push(PartialSignature::sign(&statement, &pk, &sk));
+        assert!(!policy.verify(&statement, &foreign));
+
+        // Invalid signatures are rejected even if the threshold is reached without them.
+        let mut forged = signatures[..2].to_vec();
+        forged.push(PartialSignature::new(&keys[2].0, signatures[0].signature()));
+        assert!(!policy.verify(&statement, &forged));
+
+        // Statements made for another policy are rejected.
+        let other_policy = ThresholdPolicy::new(2, policy.signers()[..2].to_vec());
+        assert!(!other_policy.verify(&statement, &signatures[..2]));
+    }
+
+    #[test]
+    fn test_replayed_signatures_rejected() {
+        let keys = keys(3);
+        let policy = policy(2, &keys);
+        let (submitter, submitter_key) = gen_keypair();
+        let withdrawal = Withdrawal::new(&submitter, 100);
+        let statement = MultisigStatement::for_message::<TxWithdraw>(
+            &submitter,
+            &policy,
+            1,
+            &withdrawal.hash(),
+        );
+        let signatures = sign(&statement, &keys[..2]);
+        let tx = TxWithdraw::new(
+            &submitter,
+            1,
+            withdrawal.clone(),
+            signatures.clone(),
+            &submitter_key,
+        );
+        assert!(policy.verify(&statement, &tx.signatures()));
+
+        // The signatures cannot be submitted by another submitter...
+        let (attacker, _) = gen_keypair();
+        let replayed =
+            MultisigStatement::for_message::<TxWithdraw>(&attacker, &policy, 1, &withdrawal.hash());
+        assert!(!policy.verify(&replayed, &signatures));
+        // ...with another sequence number...
+        let replayed = MultisigStatement::for_message::<TxWithdraw>(
+            &submitter,
+            &policy,
+            2,
+            &withdrawal.hash(),
+        );
+        assert!(!policy.verify(&replayed, &signatures));
+        // ...or in a transaction of another type.
+        let replayed =
+            MultisigStatement::for_message::<TxDeposit>(&submitter, &policy, 1, &withdrawal.hash());
+        assert!(!policy.verify(&replayed, &signatures));
+
+        // Signatures of the statement hash without the domain prefix are rejected.
+        let unprefixed: Vec<_> = keys[..2]
+            .iter()
+            .map(|(pk, sk)| PartialSignature::new(pk, &crypto::sign(statement.hash().as_ref(), sk)))
+            .collect();
+        assert!(!policy.verify(&statement, &unprefixed));
+    }
+
+    #[test]
+    fn test_multisig_transaction_serialization() {
+        let keys = keys(3);
+        let policy = policy(2, &keys);
+        let (submitter, submitter_key) = gen_keypair();
+        let withdrawal = Withdrawal::new(&submitter, 100);
+        let statement = MultisigStatement::for_message::<TxWithdraw>(
+            &submitter,
+            &policy,
+            1,
+            &withdrawal.hash(),
+        );
+        let signatures = sign(&statement, &keys[1..]);
+        let tx = TxWithdraw::new(
+            &submitter,
+            1,
+            withdrawal.clone(),
+            signatures,
+            &submitter_key,
+        );
+        assert!(tx.verify_signature(&submitter));
+        assert!(policy.verify(&statement, &tx.signatures()));
+
+        let raw = RawMessage::from_vec(tx.raw().as_ref().to_vec());
+        let decoded = TxWithdraw::from_raw(raw).unwrap();
+        assert_eq!(decoded, tx);
+        assert_eq!(decoded.withdrawal(), withdrawal);
+
+        let json = serde_json::to_string(&tx).unwrap();
+        let from_json: TxWithdraw = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json, tx);
+        assert!(policy.verify(&statement, &from_json.signatures()));
+    }
+
+    #[test]
+    fn test_signature_set() {
+        let keys = keys(3);
+        let policy = policy(2, &keys);
+        let statement = statement(&policy, 1);
+        let signatures = sign(&statement, &keys);
+
+        let mut set = SignatureSet::new(statement.clone(), policy.clone()).unwrap();
+        assert_eq!(set.add(signatures[0].clone()), Ok(true));
+        assert_eq!(set.add(signatures[0].clone()), Ok(false));
+        assert!(!set.is_complete());
+
+        let (pk, sk) = gen_keypair();
+        assert_eq!(
+            set.add(PartialSignature::sign(&statement, &pk, &sk)),
+            Err(MultisigError::UnknownSigner(pk))
+        );
+        let forged = PartialSignature::new(&keys[1].0, signatures[0].signature());
+        assert_eq!(
+            set.add(forged),
+            Err(MultisigError::InvalidSignature(keys[1].0))
+        );
+
+        assert_eq!(set.add(signatures[2].clone()), Ok(true));
+        assert!(set.is_complete());
+        assert!(policy.verify(&statement, set.signatures()));
+
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(serde_json::from_str::<SignatureSet>(&json).unwrap(), set);
+
+        let invalid_policy = ThresholdPolicy::new(0, vec![]);
+        assert_eq!(
+            SignatureSet::new(statement.clone(), invalid_policy),
+            Err(MultisigError::InvalidPolicy)
+        );
+        let other_policy = ThresholdPolicy::new(1, vec![keys[0].0]);
+        assert_eq!(
+            SignatureSet::new(statement, other_policy),
+            Err(MultisigError::PolicyMismatch)
+        );
+    }
+}
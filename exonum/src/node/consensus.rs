@@ -14,7 +14,7 @@
 
 use std::{collections::HashSet, error::Error};
 
-use blockchain::{Schema, Transaction};
+use blockchain::{check_transaction_metadata, Schema, Transaction};
 use crypto::{CryptoHash, Hash, PublicKey};
 use events::InternalRequest;
 use helpers::{Height, Round, ValidatorId};
@@ -126,16 +126,17 @@ impl NodeHandler {
         let snapshot = self.blockchain.snapshot();
         let schema = Schema::new(snapshot);
         //TODO: Remove this match after errors refactor. (ECR-979)
-        let has_unknown_txs =
-            match self.state
-                .add_propose(msg, &schema.transactions(), &schema.transactions_pool())
-            {
-                Ok(state) => state.has_unknown_txs(),
-                Err(err) => {
-                    warn!("{}, msg={:?}", err, msg);
-                    return;
-                }
-            };
+        let has_unknown_txs = match self.state.add_propose(
+            msg,
+            &schema.transactions_locations(),
+            &schema.transactions_pool(),
+        ) {
+            Ok(state) => state.has_unknown_txs(),
+            Err(err) => {
+                warn!("{}, msg={:?}", err, msg);
+                return;
+            }
+        };
 
         let hash = msg.hash();
 
@@ -228,7 +229,11 @@ impl NodeHandler {
             let snapshot = self.blockchain.snapshot();
             let schema = Schema::new(snapshot);
             let has_unknown_txs = self.state
-                .create_incomplete_block(msg, &schema.transactions(), &schema.transactions_pool())
+                .create_incomplete_block(
+                    msg,
+                    &schema.transactions_locations(),
+                    &schema.transactions_pool(),
+                )
                 .has_unknown_txs();
 
             let known_nodes = self.remove_request(&RequestData::Block(block.height()));
@@ -535,19 +540,37 @@ impl NodeHandler {
 
     /// Checks if the transaction is new and adds it to the pool. This may trigger an expedited
     /// `Propose` timeout on this node if transaction count in the pool goes over the threshold.
-    fn handle_tx_inner(&mut self, msg: RawTransaction) -> Result<(), String> {
-        let hash = msg.hash();
+    ///
+    /// Expired transactions and transactions with outdated nonces are not added to the pool
+    /// unless a propose or a block is waiting for them.
+    fn handle_tx_inner(&mut self, tx: &dyn Transaction) -> Result<(), String> {
+        let hash = tx.hash();
 
         let snapshot = self.blockchain.snapshot();
-        if Schema::new(&snapshot).transactions().contains(&hash) {
+        let schema = Schema::new(&snapshot);
+        // Bodies of committed transactions may be pruned, but their locations are kept.
+        if schema.transactions_locations().contains(&hash) || schema.transactions().contains(&hash)
+        {
             let err = format!("Received already processed transaction, hash {:?}", hash);
             return Err(err);
         }
+        // The result of a transaction awaited by a propose or a block is determined
+        // when the block is executed.
+        if !self.state.is_transaction_awaited(&hash) {
+            check_transaction_metadata(tx, schema.height().next(), &schema)
+                .map_err(|e| format!("Received invalid transaction, hash {:?}: {}", hash, e))?;
+        }
 
         let mut fork = self.blockchain.fork();
         {
             let mut schema = Schema::new(&mut fork);
-            schema.add_transaction_into_pool(msg);
+            schema.add_transaction_into_pool(tx.raw().clone());
+            if let Some(valid_until) = tx.valid_until() {
+                schema.set_transaction_expiry(&hash, valid_until);
+            }
+            if let Some((sender, nonce)) = tx.nonce() {
+                schema.set_transaction_nonce(&hash, sender, nonce);
+            }
         }
         self.blockchain
             .merge(fork.into_patch())
@@ -591,7 +614,7 @@ impl NodeHandler {
 
         // We don't care about result, because situation when transaction received twice
         // is normal for internal messages (transaction may be received from 2+ nodes).
-        let _ = self.handle_tx_inner(msg);
+        let _ = self.handle_tx_inner(&*tx);
     }
 
     /// Handles raw transactions.
@@ -628,7 +651,7 @@ impl NodeHandler {
     #[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
     pub fn handle_incoming_tx(&mut self, msg: Box<dyn Transaction>) {
         trace!("Handle incoming transaction");
-        match self.handle_tx_inner(msg.raw().clone()) {
+        match self.handle_tx_inner(&*msg) {
             Ok(_) => self.broadcast(msg.raw()),
             Err(e) => error!("{}", e),
         }
@@ -719,7 +742,9 @@ impl NodeHandler {
             let round = self.state.round();
             let max_count = ::std::cmp::min(u64::from(self.txs_block_limit()), pool_len);
 
-            let txs: Vec<Hash> = pool.iter().take(max_count as usize).collect();
+            // The lower nonces of a sender are proposed before the higher ones; they are
+            // looked up in the index of the pool, so only the proposed part is scanned.
+            let txs = schema.order_by_nonce(pool.iter().take(max_count as usize));
             let propose = Propose::new(
                 validator_id,
                 self.state.height(),
@@ -34,8 +34,8 @@ use tokio_core::reactor::Core;
 use toml::Value;
 
 use std::{
-    collections::{BTreeMap, HashSet}, fmt, io, net::{SocketAddr, ToSocketAddrs}, sync::Arc, thread,
-    time::{Duration, SystemTime},
+    collections::{BTreeMap, HashSet}, fmt, io, net::{SocketAddr, ToSocketAddrs}, path::PathBuf,
+    sync::Arc, thread, time::{Duration, SystemTime},
 };
 
 use serde::de::{self, Deserialize, Deserializer};
@@ -176,6 +176,11 @@ pub struct NodeApiConfig {
     ///
     /// [cors]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
     pub private_allow_origin: Option<AllowOrigin>,
+    /// Directory in which backups are created with the `v1/backup` endpoint of
+    /// the private API. The endpoint accepts only paths inside this directory.
+    ///
+    /// Defaults to `None`, meaning that backups cannot be created via the API.
+    pub backup_dir: Option<PathBuf>,
 }
 
 impl Default for NodeApiConfig {
@@ -187,6 +192,7 @@ impl Default for NodeApiConfig {
             private_api_address: None,
             public_allow_origin: None,
             private_allow_origin: None,
+            backup_dir: None,
         }
     }
 }
@@ -886,6 +892,8 @@ impl Node {
             node_cfg.service_secret_key.clone(),
             ApiSender::new(channel.api_requests.0.clone()),
         );
+        blockchain.set_keep_blocks(node_cfg.database.keep_blocks);
+        blockchain.set_keep_history(node_cfg.database.keep_history);
         blockchain.initialize(node_cfg.genesis.clone()).unwrap();
 
         let peers = node_cfg.connect_list.addresses();
@@ -906,7 +914,8 @@ impl Node {
             peer_discovery: peers,
         };
 
-        let api_state = SharedNodeState::new(node_cfg.api.state_update_timeout as u64);
+        let mut api_state = SharedNodeState::new(node_cfg.api.state_update_timeout as u64);
+        api_state.backup_dir = node_cfg.api.backup_dir.clone();
         let system_state = Box::new(DefaultSystemState(node_cfg.listen_address));
         let network_config = config.network;
         let handler = NodeHandler::new(
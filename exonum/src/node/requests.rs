@@ -139,6 +139,11 @@ impl NodeHandler {
     }
 
     /// Handles `BlockRequest` message. For details see the message documentation.
+    ///
+    /// Requests for the blocks pruned from the storage are not answered, since the block
+    /// cannot be sent without its transactions and precommits. Pruned nodes thus cannot
+    /// serve the synchronization of the peers lagging behind the pruned height; such peers
+    /// need an archival node among their peers.
     pub fn handle_request_block(&mut self, msg: &BlockRequest) {
         trace!(
             "Handle block request with height:{}, our height: {}",
@@ -153,6 +158,14 @@ impl NodeHandler {
         let schema = Schema::new(&snapshot);
 
         let height = msg.height();
+        if schema.is_pruned(height) {
+            warn!(
+                "Cannot serve block request from {:?}: block at height {} has been pruned",
+                msg.from(),
+                height
+            );
+            return;
+        }
         let block_hash = schema.block_hash_by_height(height).unwrap();
 
         let block = schema.blocks().get(&block_hash).unwrap();
@@ -23,12 +23,10 @@ use std::{
     sync::{Arc, RwLock}, time::{Duration, SystemTime},
 };
 
-use blockchain::{ConsensusConfig, StoredConfiguration, ValidatorKeys};
+use blockchain::{ConsensusConfig, StoredConfiguration, TxLocation, ValidatorKeys};
 use crypto::{CryptoHash, Hash, PublicKey, SecretKey};
 use helpers::{Height, Milliseconds, Round, ValidatorId};
-use messages::{
-    BlockResponse, Connect, ConsensusMessage, Message, Precommit, Prevote, Propose, RawMessage,
-};
+use messages::{BlockResponse, Connect, ConsensusMessage, Message, Precommit, Prevote, Propose};
 use node::{connect_list::ConnectList, ConnectInfo};
 use storage::{KeySetIndex, MapIndex, Patch, Snapshot};
 
@@ -806,6 +804,17 @@ impl State {
         self.queued.push(msg);
     }
 
+    /// Returns `true` if a propose or an incomplete block of the current height is waiting
+    /// for the transaction with the given hash.
+    pub fn is_transaction_awaited(&self, tx_hash: &Hash) -> bool {
+        self.proposes
+            .values()
+            .any(|propose_state| propose_state.unknown_txs.contains(tx_hash))
+            || self.incomplete_block
+                .as_ref()
+                .map_or(false, |block| block.unknown_txs.contains(tx_hash))
+    }
+
     /// Checks whether some proposes are waiting for this transaction.
     /// Returns a list of proposes that don't contain unknown transactions.
     ///
@@ -891,10 +900,13 @@ impl State {
     }
 
     /// Adds propose from other node. Returns `ProposeState` if it is a new propose.
+    ///
+    /// Committed transactions are found by their locations, which are kept when
+    /// the transaction bodies are pruned.
     pub fn add_propose<S: AsRef<dyn Snapshot>>(
         &mut self,
         msg: &Propose,
-        transactions: &MapIndex<S, Hash, RawMessage>,
+        transactions_locations: &MapIndex<S, Hash, TxLocation>,
         transaction_pool: &KeySetIndex<S, Hash>,
     ) -> Result<&ProposeState, failure::Error> {
         let propose_hash = msg.hash();
@@ -903,14 +915,12 @@ impl State {
             Entry::Vacant(e) => {
                 let mut unknown_txs = HashSet::new();
                 for hash in msg.transactions() {
-                    if transactions.get(hash).is_some() {
-                        if !transaction_pool.contains(hash) {
-                            bail!(
-                                "Received propose with already \
-                                 committed transaction"
-                            )
-                        }
-                    } else {
+                    if transactions_locations.contains(hash) {
+                        bail!(
+                            "Received propose with already \
+                             committed transaction"
+                        )
+                    } else if !transaction_pool.contains(hash) {
                         unknown_txs.insert(*hash);
                     }
                 }
@@ -962,21 +972,19 @@ impl State {
     pub fn create_incomplete_block<S: AsRef<dyn Snapshot>>(
         &mut self,
         msg: &BlockResponse,
-        txs: &MapIndex<S, Hash, RawMessage>,
+        txs_locations: &MapIndex<S, Hash, TxLocation>,
         txs_pool: &KeySetIndex<S, Hash>,
     ) -> &IncompleteBlock {
         assert!(self.incomplete_block().is_none());
 
         let mut unknown_txs = HashSet::new();
         for hash in msg.transactions() {
-            if txs.get(hash).is_some() {
-                if !txs_pool.contains(hash) {
-                    panic!(
-                        "Received block with already \
-                         committed transaction"
-                    )
-                }
-            } else {
+            if txs_locations.contains(hash) {
+                panic!(
+                    "Received block with already \
+                     committed transaction"
+                )
+            } else if !txs_pool.contains(hash) {
                 unknown_txs.insert(*hash);
             }
         }
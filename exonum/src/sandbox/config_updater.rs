@@ -73,4 +73,8 @@ impl Service for ConfigUpdateService {
         let tx = ConfigUpdaterTransactions::tx_from_raw(raw)?;
         Ok(tx.into())
     }
+
+    fn storage_namespaces(&self) -> Vec<String> {
+        vec![self.service_name().to_owned(), "core".to_owned()]
+    }
 }
@@ -18,6 +18,7 @@ use bit_vec::BitVec;
 
 use std::time::Duration;
 
+use blockchain::Schema;
 use crypto::{gen_keypair, CryptoHash, Hash};
 use helpers::{Height, Milliseconds, Round, ValidatorId};
 use messages::{
@@ -728,3 +729,27 @@ fn expedited_propose_on_transaction_pressure() {
     sandbox.broadcast(&propose);
     sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
 }
+
+/// Idea of the test is to check that a committed transaction is not accepted into the pool
+/// again after its body has been pruned from the storage.
+#[test]
+fn pruned_transaction_is_not_accepted_again() {
+    let sandbox = timestamping_sandbox();
+    let sandbox_state = SandboxState::new();
+
+    let tx = gen_timestamping_tx();
+    add_one_height_with_transactions(&sandbox, &sandbox_state, &[tx.raw().clone()]);
+    add_one_height(&sandbox, &sandbox_state);
+
+    {
+        let mut blockchain = sandbox.blockchain_mut();
+        let mut fork = blockchain.fork();
+        assert_eq!(Schema::new(&mut fork).prune_blocks(1), Some(Height(1)));
+        blockchain.merge(fork.into_patch()).unwrap();
+    }
+    let snapshot = sandbox.blockchain_ref().snapshot();
+    assert!(!Schema::new(&snapshot).transactions().contains(&tx.hash()));
+
+    sandbox.recv(&tx);
+    sandbox.assert_pool_len(0);
+}
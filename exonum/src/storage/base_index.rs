@@ -39,7 +39,6 @@ use storage::indexes_metadata::{self, IndexType, INDEXES_METADATA_TABLE_NAME};
 #[derive(Debug)]
 pub struct BaseIndex<T> {
     name: String,
-    is_family: bool,
     index_id: Option<Vec<u8>>,
     is_mutable: bool,
     index_type: IndexType,
@@ -48,16 +47,20 @@ pub struct BaseIndex<T> {
 
 /// An iterator over the entries of a `BaseIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`BaseIndex`]. See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`], [`iter_rev_from`]
+/// or [`iter_rev_range`] method on [`BaseIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.BaseIndex.html#method.iter
 /// [`iter_from`]: struct.BaseIndex.html#method.iter_from
+/// [`iter_rev`]: struct.BaseIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.BaseIndex.html#method.iter_rev_from
+/// [`iter_rev_range`]: struct.BaseIndex.html#method.iter_rev_range
 /// [`BaseIndex`]: struct.BaseIndex.html
 pub struct BaseIndexIter<'a, K, V> {
     base_iter: Iter<'a>,
     base_prefix_len: usize,
     index_id: Vec<u8>,
+    min_key: Option<Vec<u8>>,
     ended: bool,
     _k: PhantomData<K>,
     _v: PhantomData<V>,
@@ -88,7 +91,6 @@ where
 
         Self {
             name: index_name.as_ref().to_string(),
-            is_family,
             index_id: None,
             is_mutable: false,
             index_type,
@@ -123,7 +125,6 @@ where
 
         Self {
             name: family_name.as_ref().to_string(),
-            is_family,
             index_id: {
                 let mut buf = vec![0; index_id.size()];
                 index_id.write(&mut buf);
@@ -135,10 +136,14 @@ where
         }
     }
 
+    /// Returns the storage view of the index.
+    pub(crate) fn snapshot(&self) -> &(dyn Snapshot + 'static) {
+        self.view.as_ref()
+    }
+
     pub(crate) fn indexes_metadata(view: T) -> Self {
         Self {
             name: INDEXES_METADATA_TABLE_NAME.to_string(),
-            is_family: false,
             index_id: None,
             is_mutable: true,
             index_type: IndexType::Map,
@@ -187,7 +192,7 @@ where
     /// for iteration.
     pub fn iter<P, K, V>(&self, subprefix: &P) -> BaseIndexIter<K, V>
     where
-        P: StorageKey,
+        P: StorageKey + ?Sized,
         K: StorageKey,
         V: StorageValue,
     {
@@ -196,6 +201,7 @@ where
             base_iter: self.view.as_ref().iter(&self.name, &iter_prefix),
             base_prefix_len: self.index_id.as_ref().map_or(0, |p| p.len()),
             index_id: iter_prefix,
+            min_key: None,
             ended: false,
             _k: PhantomData,
             _v: PhantomData,
@@ -218,6 +224,89 @@ where
             base_iter: self.view.as_ref().iter(&self.name, &iter_from),
             base_prefix_len: self.index_id.as_ref().map_or(0, |p| p.len()),
             index_id: iter_prefix,
+            min_key: None,
+            ended: false,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the entries of the index in descending order. The iterator
+    /// element type is *any* key-value pair. An argument `subprefix` allows specifying a subset
+    /// of keys for iteration.
+    pub fn iter_rev<P, K, V>(&self, subprefix: &P) -> BaseIndexIter<K, V>
+    where
+        P: StorageKey,
+        K: StorageKey,
+        V: StorageValue,
+    {
+        let iter_prefix = self.prefixed_key(subprefix);
+        let iter_to = prefix_successor(&iter_prefix);
+        self.rev_iter(iter_prefix, iter_to, None)
+    }
+
+    /// Returns an iterator over the entries of the index in descending order starting from the
+    /// specified key (inclusive). The iterator element type is *any* key-value pair.
+    /// An argument `subprefix` allows specifying a subset of iteration.
+    pub fn iter_rev_from<P, F, K, V>(&self, subprefix: &P, from: &F) -> BaseIndexIter<K, V>
+    where
+        P: StorageKey,
+        F: StorageKey + ?Sized,
+        K: StorageKey,
+        V: StorageValue,
+    {
+        let iter_prefix = self.prefixed_key(subprefix);
+        let iter_to = self.key_successor(from);
+        self.rev_iter(iter_prefix, Some(iter_to), None)
+    }
+
+    /// Returns an iterator over the entries of the index in descending order starting from the
+    /// specified key (inclusive) or from the last entry if `from` is `None`, and ending at
+    /// the `lower` key (inclusive). The iterator element type is *any* key-value pair.
+    /// An argument `subprefix` allows specifying a subset of iteration.
+    pub fn iter_rev_range<P, F, L, K, V>(
+        &self,
+        subprefix: &P,
+        from: Option<&F>,
+        lower: &L,
+    ) -> BaseIndexIter<K, V>
+    where
+        P: StorageKey,
+        F: StorageKey + ?Sized,
+        L: StorageKey + ?Sized,
+        K: StorageKey,
+        V: StorageValue,
+    {
+        let iter_prefix = self.prefixed_key(subprefix);
+        let iter_to = match from {
+            Some(from) => Some(self.key_successor(from)),
+            None => prefix_successor(&iter_prefix),
+        };
+        let min_key = self.prefixed_key(lower);
+        self.rev_iter(iter_prefix, iter_to, Some(min_key))
+    }
+
+    /// Returns the smallest prefixed key that is greater than the given one.
+    fn key_successor<K: StorageKey + ?Sized>(&self, key: &K) -> Vec<u8> {
+        let mut key = self.prefixed_key(key);
+        key.push(0);
+        key
+    }
+
+    fn rev_iter<K, V>(
+        &self,
+        iter_prefix: Vec<u8>,
+        iter_to: Option<Vec<u8>>,
+        min_key: Option<Vec<u8>>,
+    ) -> BaseIndexIter<K, V> {
+        BaseIndexIter {
+            base_iter: self
+                .view
+                .as_ref()
+                .iter_rev(&self.name, iter_to.as_ref().map(Vec::as_slice)),
+            base_prefix_len: self.index_id.as_ref().map_or(0, |p| p.len()),
+            index_id: iter_prefix,
+            min_key,
             ended: false,
             _k: PhantomData,
             _v: PhantomData,
@@ -231,13 +320,18 @@ impl<'a> BaseIndex<&'a mut Fork> {
             indexes_metadata::set_index_type(
                 &self.name,
                 self.index_type,
-                self.is_family,
+                self.index_id.as_ref().map(Vec::as_slice),
                 &mut self.view,
             );
             self.is_mutable = true;
         }
     }
 
+    /// Returns the fork the index is based on.
+    pub(crate) fn fork(&mut self) -> &mut Fork {
+        self.view
+    }
+
     /// Inserts the key-value pair into the index. Both key and value may be of *any* types.
     pub fn put<K, V>(&mut self, key: &K, value: V)
     where
@@ -271,6 +365,11 @@ impl<'a> BaseIndex<&'a mut Fork> {
         self.set_index_type();
         self.view
             .remove_by_prefix(&self.name, self.index_id.as_ref());
+        if let Some(ref index_id) = self.index_id {
+            indexes_metadata::remove_family_member(&self.name, index_id, &mut self.view);
+            // The index is recorded as a family member again on its next change.
+            self.is_mutable = false;
+        }
     }
 }
 
@@ -286,7 +385,10 @@ where
             return None;
         }
         if let Some((k, v)) = self.base_iter.next() {
-            if k.starts_with(&self.index_id) {
+            let is_above_min = self.min_key
+                .as_ref()
+                .map_or(true, |min_key| k >= min_key.as_slice());
+            if k.starts_with(&self.index_id) && is_above_min {
                 return Some((
                     K::read(&k[self.base_prefix_len..]),
                     V::from_bytes(Cow::Borrowed(v)),
@@ -304,6 +406,19 @@ impl<'a, K, V> ::std::fmt::Debug for BaseIndexIter<'a, K, V> {
     }
 }
 
+/// Returns the smallest key that is greater than all keys starting with the given prefix,
+/// or `None` if there is no such key.
+fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut key = prefix.to_vec();
+    while let Some(byte) = key.pop() {
+        if byte < 0xFF {
+            key.push(byte + 1);
+            return Some(key);
+        }
+    }
+    None
+}
+
 /// A function that validates an index name. Allowable characters in name: ASCII characters, digits
 /// and underscores.
 fn is_valid_name<S: AsRef<str>>(name: S) -> bool {
@@ -342,6 +457,14 @@ mod tests {
         assert!(!is_valid_name("1in!dex_Namez"));
     }
 
+    #[test]
+    fn test_prefix_successor() {
+        assert_eq!(prefix_successor(&[]), None);
+        assert_eq!(prefix_successor(&[0xFF, 0xFF]), None);
+        assert_eq!(prefix_successor(&[1, 2]), Some(vec![1, 3]));
+        assert_eq!(prefix_successor(&[1, 0xFF]), Some(vec![2]));
+    }
+
     #[test]
     fn check_valid_name() {
         assert_valid_name("valid_name");
@@ -0,0 +1,311 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A read cache of committed entries for a database.
+
+use std::{
+    collections::{BTreeMap, HashMap}, path::Path, sync::{Arc, Mutex},
+};
+
+use super::{Database, Iter, Patch, Result, Snapshot, TableStats};
+
+// Approximate memory overhead of a cached entry in addition to its key and value.
+const ENTRY_OVERHEAD: usize = 64;
+
+/// A database decorator caching the entries read from the underlying database.
+///
+/// The cache keeps the most recently read committed entries, including the absent ones,
+/// keyed by the column family name and the key. Reads of the snapshots and forks
+/// created by the database are served from the cache if possible, which speeds up
+/// repeated reads of the same entries, such as the upper branches of `ProofMapIndex`.
+///
+/// When a patch is merged, the changed entries are evicted from the cache. Snapshots
+/// created before the merge stop using the cache, so they always read the data from
+/// the state they have been created for. Iterators are not cached.
+///
+/// The cache is enabled for the database opened with [`open_database`] if `cache_size`
+/// is specified in `DbOptions`.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{CachedDB, Database, MemoryDB};
+///
+/// let db = CachedDB::new(Box::new(MemoryDB::new()), 1 << 20);
+/// let mut fork = db.fork();
+/// fork.put("table", vec![1], vec![2]);
+/// db.merge(fork.into_patch()).unwrap();
+///
+/// let snapshot = db.snapshot();
+/// assert_eq!(snapshot.get("table", &[1]), Some(vec![2]));
+/// assert_eq!(snapshot.get("table", &[1]), Some(vec![2]));
+/// let stats = db.cache_stats().unwrap();
+/// assert_eq!((stats.hits, stats.misses), (1, 1));
+/// ```
+///
+/// [`open_database`]: fn.open_database.html
+pub struct CachedDB {
+    db: Box<dyn Database>,
+    cache: Arc<Mutex<Cache>>,
+}
+
+/// Statistics of the read cache of a [`CachedDB`], which are returned by
+/// [`Database::cache_stats`].
+///
+/// [`CachedDB`]: struct.CachedDB.html
+/// [`Database::cache_stats`]: trait.Database.html#method.cache_stats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Number of reads served from the cache.
+    pub hits: u64,
+    /// Number of reads passed to the underlying database.
+    pub misses: u64,
+    /// Number of cached entries.
+    pub entries: u64,
+    /// Approximate size of the cached entries in bytes.
+    pub size: u64,
+}
+
+impl CacheStats {
+    /// Returns the share of the reads served from the cache, or `0.0` if there
+    /// were no reads.
+    pub fn hit_rate(&self) -> f64 {
+        let reads = self.hits + self.misses;
+        if reads == 0 {
+            0.0
+        } else {
+            self.hits as f64 / reads as f64
+        }
+    }
+}
+
+/// A snapshot of a `CachedDB`.
+struct CachedSnapshot {
+    snapshot: Box<dyn Snapshot>,
+    cache: Arc<Mutex<Cache>>,
+    // Generation of the cache at which the snapshot has been created.
+    generation: u64,
+}
+
+type CacheKey = (String, Vec<u8>);
+
+struct CacheEntry {
+    value: Option<Vec<u8>>,
+    // Position of the entry in the `recent` queue.
+    tick: u64,
+}
+
+// A least recently used cache of committed entries.
+struct Cache {
+    entries: HashMap<CacheKey, CacheEntry>,
+    // Keys of the entries in the order of their use.
+    recent: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    // Incremented on each merge; entries are read from and added to the cache only
+    // by the snapshots of the current generation.
+    generation: u64,
+    size: usize,
+    max_size: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl CachedDB {
+    /// Creates a cache of at most `max_size` bytes over the given database.
+    pub fn new(db: Box<dyn Database>, max_size: usize) -> Self {
+        Self {
+            db,
+            cache: Arc::new(Mutex::new(Cache::new(max_size))),
+        }
+    }
+
+    fn stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        CacheStats {
+            hits: cache.hits,
+            misses: cache.misses,
+            entries: cache.entries.len() as u64,
+            size: cache.size as u64,
+        }
+    }
+
+    fn merge_with(&self, patch: Patch, sync: bool) -> Result<()> {
+        let keys: Vec<CacheKey> = patch
+            .iter()
+            .flat_map(|(name, changes)| {
+                changes
+                    .iter()
+                    .map(move |(key, _)| (name.clone(), key.clone()))
+            })
+            .collect();
+
+        // The lock is held during the merge, so that snapshots of the previous
+        // generation cannot add the old values of the merged entries to the cache.
+        let mut cache = self.cache.lock().unwrap();
+        let result = if sync {
+            self.db.merge_sync(patch)
+        } else {
+            self.db.merge(patch)
+        };
+        for key in keys {
+            cache.remove(&key);
+        }
+        cache.generation += 1;
+        result
+    }
+}
+
+impl Database for CachedDB {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        let cache = self.cache.lock().unwrap();
+        Box::new(CachedSnapshot {
+            snapshot: self.db.snapshot(),
+            cache: Arc::clone(&self.cache),
+            generation: cache.generation,
+        })
+    }
+
+    fn snapshot_at(&self, version: u64) -> Option<Box<dyn Snapshot>> {
+        self.db.snapshot_at(version)
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        self.merge_with(patch, false)
+    }
+
+    fn merge_sync(&self, patch: Patch) -> Result<()> {
+        self.merge_with(patch, true)
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> Result<Box<dyn Snapshot>> {
+        self.db.create_checkpoint(path)
+    }
+
+    fn table_stats(&self, name: &str) -> TableStats {
+        self.db.table_stats(name)
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(self.stats())
+    }
+}
+
+impl Snapshot for CachedSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let cache_key = (name.to_owned(), key.to_vec());
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if cache.generation == self.generation {
+                if let Some(value) = cache.get(&cache_key) {
+                    return value;
+                }
+            }
+        }
+
+        let value = self.snapshot.get(name, key);
+        let mut cache = self.cache.lock().unwrap();
+        if cache.generation == self.generation {
+            cache.insert(cache_key, value.clone());
+        }
+        value
+    }
+
+    fn contains(&self, name: &str, key: &[u8]) -> bool {
+        self.get(name, key).is_some()
+    }
+
+    fn iter(&self, name: &str, from: &[u8]) -> Iter {
+        self.snapshot.iter(name, from)
+    }
+
+    fn iter_rev(&self, name: &str, to: Option<&[u8]>) -> Iter {
+        self.snapshot.iter_rev(name, to)
+    }
+}
+
+impl Cache {
+    fn new(max_size: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recent: BTreeMap::new(),
+            tick: 0,
+            generation: 0,
+            size: 0,
+            max_size,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    // Returns the cached value, which is `Some(None)` for a cached absent entry.
+    fn get(&mut self, key: &CacheKey) -> Option<Option<Vec<u8>>> {
+        let tick = self.tick;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                let key = self.recent.remove(&entry.tick).unwrap();
+                entry.tick = tick;
+                self.recent.insert(tick, key);
+                self.tick += 1;
+                self.hits += 1;
+                Some(entry.value.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: CacheKey, value: Option<Vec<u8>>) {
+        self.remove(&key);
+        let size = entry_size(&key, &value);
+        if size > self.max_size {
+            return;
+        }
+        while self.size + size > self.max_size {
+            let oldest_tick = *self.recent.keys().next().unwrap();
+            let oldest = self.recent[&oldest_tick].clone();
+            self.remove(&oldest);
+        }
+
+        let tick = self.tick;
+        self.tick += 1;
+        self.size += size;
+        self.recent.insert(tick, key.clone());
+        self.entries.insert(key, CacheEntry { value, tick });
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recent.remove(&entry.tick);
+            self.size -= entry_size(key, &entry.value);
+        }
+    }
+}
+
+fn entry_size(key: &CacheKey, value: &Option<Vec<u8>>) -> usize {
+    key.0.len() + key.1.len() + value.as_ref().map_or(0, Vec::len) + ENTRY_OVERHEAD
+}
+
+impl From<CachedDB> for Arc<dyn Database> {
+    fn from(db: CachedDB) -> Self {
+        Self::from(Box::new(db) as Box<dyn Database>)
+    }
+}
+
+impl ::std::fmt::Debug for CachedDB {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "CachedDB({:?})", self.stats())
+    }
+}
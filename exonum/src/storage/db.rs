@@ -12,20 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use byteorder::{ByteOrder, LittleEndian};
+
 use std::{
+    borrow::Cow,
     cmp::Ordering::{Equal, Greater, Less},
     collections::{
-        btree_map::{BTreeMap, IntoIter as BtmIntoIter, Iter as BtmIter, Range},
+        btree_map::{BTreeMap, IntoIter as BtmIntoIter, Iter as BtmIter},
         hash_map::{Entry as HmEntry, IntoIter as HmIntoIter, Iter as HmIter},
-        Bound::{Included, Unbounded}, HashMap,
+        Bound::{Excluded, Included, Unbounded}, HashMap,
     },
-    iter::{Iterator as StdIterator, Peekable},
+    iter::{Iterator as StdIterator, Peekable}, mem, path::Path,
 };
 
-use super::Result;
+use super::{
+    history, indexes_metadata::INDEXES_METADATA_TABLE_NAME, CacheStats, Error, Result, TableStats,
+};
+use crypto::{self, CryptoHash, Hash};
 
 /// Map containing changes with a corresponding key.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Changes {
     data: BTreeMap<Vec<u8>, Change>,
 }
@@ -74,7 +80,12 @@ impl IntoIterator for Changes {
 /// This set can contain changes from multiple tables. When a block is added to
 /// the blockchain, changes are first collected into a patch and then applied to
 /// the storage.
-#[derive(Debug, Clone)]
+///
+/// A patch can be serialized with [`to_bytes`] to be applied to another database, e.g.,
+/// to a read replica. The hash of a patch is the hash of its serialized form.
+///
+/// [`to_bytes`]: #method.to_bytes
+#[derive(Debug, Clone, PartialEq)]
 pub struct Patch {
     changes: HashMap<String, Changes>,
 }
@@ -123,6 +134,132 @@ impl Patch {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Serializes the patch into a compact binary form.
+    ///
+    /// Column families are written in the lexicographic order of their names, so equal
+    /// patches always produce equal byte sequences. The format is stable: the number of
+    /// column families (`u32`) is followed by the length-prefixed name of each family,
+    /// the number of its changes (`u32`) and the changes in the ascending order of keys.
+    /// A change is a length-prefixed key followed by `0` for a deletion, or `1` and
+    /// a length-prefixed value for an insertion. Integers, including length prefixes
+    /// (`u32`), are encoded in little-endian.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{Database, MemoryDB, Patch, Snapshot};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// fork.put("table", vec![1], vec![2]);
+    /// let bytes = fork.into_patch().to_bytes();
+    ///
+    /// let replica = MemoryDB::new();
+    /// replica.merge(Patch::from_bytes(&bytes).unwrap()).unwrap();
+    /// assert_eq!(replica.snapshot().get("table", &[1]), Some(vec![2]));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut names = self.changes.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut buf = Vec::new();
+        write_u32(&mut buf, names.len() as u32);
+        for name in names {
+            let changes = &self.changes[name];
+            write_bytes(&mut buf, name.as_bytes());
+            write_u32(&mut buf, changes.data.len() as u32);
+            for (key, change) in &changes.data {
+                write_bytes(&mut buf, key);
+                match *change {
+                    Change::Put(ref value) => {
+                        buf.push(1);
+                        write_bytes(&mut buf, value);
+                    }
+                    Change::Delete => buf.push(0),
+                }
+            }
+        }
+        buf
+    }
+
+    /// Deserializes the patch from the binary form produced by [`to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes do not represent a serialized patch.
+    ///
+    /// [`to_bytes`]: #method.to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = PatchReader { bytes, pos: 0 };
+        let mut patch = Self::new();
+        for _ in 0..reader.read_u32()? {
+            let name = String::from_utf8(reader.read_bytes()?.to_vec())
+                .map_err(|_| Error::new("Invalid column family name in the patch"))?;
+            let mut changes = Changes::new();
+            for _ in 0..reader.read_u32()? {
+                let key = reader.read_bytes()?.to_vec();
+                let change = match reader.read_u8()? {
+                    0 => Change::Delete,
+                    1 => Change::Put(reader.read_bytes()?.to_vec()),
+                    tag => return Err(Error::new(format!("Invalid change tag {}", tag))),
+                };
+                changes.data.insert(key, change);
+            }
+            patch.insert_changes(name, changes);
+        }
+        if reader.pos != bytes.len() {
+            return Err(Error::new("Unexpected trailing bytes in the patch"));
+        }
+        Ok(patch)
+    }
+}
+
+impl CryptoHash for Patch {
+    fn hash(&self) -> Hash {
+        crypto::hash(&self.to_bytes())
+    }
+}
+
+fn write_u32(buf: &mut Vec<u8>, value: u32) {
+    let mut bytes = [0; 4];
+    LittleEndian::write_u32(&mut bytes, value);
+    buf.extend_from_slice(&bytes);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(buf, bytes.len() as u32);
+    buf.extend_from_slice(bytes);
+}
+
+/// Cursor over a serialized `Patch`.
+struct PatchReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() - self.pos < len {
+            return Err(Error::new("Unexpected end of the serialized patch"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        self.read_slice(1).map(|bytes| bytes[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        self.read_slice(4).map(LittleEndian::read_u32)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_u32()? as usize;
+        self.read_slice(len)
+    }
 }
 
 /// Iterator over the `Patch` data.
@@ -169,6 +306,11 @@ pub enum Change {
 /// [`remove_by_prefix`]) are applied to a fork, the subsequent reads act as if the changes
 /// are applied to the database; in reality, these changes are accumulated in memory.
 ///
+/// The fork passed to `Transaction::execute` and `Service::before_commit` only allows changing
+/// the indexes within the storage namespaces of the service, which are returned by
+/// `Service::storage_namespaces`; changing other indexes results in a panic. The indexes of
+/// other services can be read through the read-only view returned by `fork.as_ref()`.
+///
 /// To apply changes to the database, you need to convert a `Fork` into a [`Patch`] using
 /// [`into_patch`] and then atomically [`merge`] it into the database. If two
 /// conflicting forks are merged into a database, this can lead to an inconsistent state. If you
@@ -177,7 +319,8 @@ pub enum Change {
 ///
 /// `Fork` also supports checkpoints ([`checkpoint`], [`commit`] and
 /// [`rollback`] methods), which allows rolling back some of the latest changes (e.g., after
-/// a runtime error).
+/// a runtime error). Nested savepoints ([`savepoint`], [`rollback_to`] and [`release`]
+/// methods) allow rolling back a part of the changes made after a checkpoint.
 ///
 /// `Fork` implements the [`Snapshot`] trait and provides methods for both reading and
 /// writing data. Thus, `&mut Fork` is used as a storage view for creating
@@ -196,18 +339,33 @@ pub enum Change {
 /// [`checkpoint`]: #method.checkpoint
 /// [`commit`]: #method.commit
 /// [`rollback`]: #method.rollback
+/// [`savepoint`]: #method.savepoint
+/// [`rollback_to`]: #method.rollback_to
+/// [`release`]: #method.release
 
 // FIXME: make &mut Fork "unwind safe". (ECR-176)
 pub struct Fork {
     snapshot: Box<dyn Snapshot>,
     patch: Patch,
     changelog: Vec<(String, Vec<u8>, Option<Change>)>,
-    logged: bool,
+    // Active savepoints along with the length of the changelog at their creation,
+    // from the outermost to the innermost one.
+    savepoints: Vec<(SavepointId, usize)>,
+    next_savepoint_id: u64,
+    // Namespaces of the indexes which can be changed, or `None` if changes are not restricted.
+    write_scope: Option<Vec<String>>,
 }
 
-struct ForkIter<'a> {
+/// An identifier of a savepoint created by [`Fork::savepoint`].
+///
+/// [`Fork::savepoint`]: struct.Fork.html#method.savepoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SavepointId(u64);
+
+struct ForkIter<'a, T: StdIterator> {
     snapshot: Iter<'a>,
-    changes: Option<Peekable<Range<'a, Vec<u8>, Change>>>,
+    changes: Option<Peekable<T>>,
+    reversed: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -253,12 +411,22 @@ pub trait Database: Send + Sync + 'static {
 
     /// Creates a new fork of the database from its current state.
     fn fork(&self) -> Fork {
-        Fork {
-            snapshot: self.snapshot(),
-            patch: Patch::new(),
-            changelog: Vec::new(),
-            logged: false,
-        }
+        Fork::new(self.snapshot())
+    }
+
+    /// Creates a read-only snapshot of the database as it was right after the given
+    /// version had been recorded with [`Fork::save_version`].
+    ///
+    /// Returns `None` if the requested version is newer than the latest recorded one,
+    /// or if its history is not available in the database.
+    ///
+    /// The default implementation reconstructs the state by applying the recorded
+    /// reverse changes on top of the current snapshot, so the cost of the call grows
+    /// with the number of changes made after the requested version.
+    ///
+    /// [`Fork::save_version`]: struct.Fork.html#method.save_version
+    fn snapshot_at(&self, version: u64) -> Option<Box<dyn Snapshot>> {
+        history::snapshot_at(self.snapshot(), version)
     }
 
     /// Atomically applies a sequence of patch changes to the database.
@@ -284,6 +452,57 @@ pub trait Database: Send + Sync + 'static {
     /// will be returned. In case of an error, the method guarantees no changes are applied to
     /// the database.
     fn merge_sync(&self, patch: Patch) -> Result<()>;
+
+    /// Creates a consistent copy of the database in the given directory. The database
+    /// remains available for reads and writes while the copy is being created.
+    ///
+    /// Returns a snapshot of the created copy, which can be used, e.g., to verify
+    /// the copied data.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation returns an error, which means that the database does
+    /// not support checkpoints. An error is also returned if the copy cannot be created,
+    /// for example, if the directory already exists.
+    fn create_checkpoint(&self, _path: &Path) -> Result<Box<dyn Snapshot>> {
+        Err(Error::new("Checkpoints are not supported by this database"))
+    }
+
+    /// Returns the number of keys and the size of data in the column family with
+    /// the given name.
+    ///
+    /// The default implementation computes exact figures by iterating over a snapshot
+    /// of the database. Implementors may return approximate figures if the exact ones
+    /// are expensive to compute.
+    fn table_stats(&self, name: &str) -> TableStats {
+        TableStats::from_snapshot(&*self.snapshot(), name)
+    }
+
+    /// Returns the statistics of the read cache of the database, or `None` if the database
+    /// does not cache reads. The default implementation returns `None`.
+    ///
+    /// See [`CachedDB`] for details.
+    ///
+    /// [`CachedDB`]: struct.CachedDB.html
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+
+    /// Deserializes a patch produced by [`Patch::to_bytes`] and atomically applies it
+    /// to the database. Returns the hash of the applied patch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes do not represent a serialized patch, or if the
+    /// patch cannot be merged.
+    ///
+    /// [`Patch::to_bytes`]: struct.Patch.html#method.to_bytes
+    fn merge_serialized(&self, bytes: &[u8]) -> Result<Hash> {
+        let patch = Patch::from_bytes(bytes)?;
+        let hash = patch.hash();
+        self.merge(patch)?;
+        Ok(hash)
+    }
 }
 
 /// A read-only snapshot of a storage backend.
@@ -309,6 +528,11 @@ pub trait Snapshot: 'static {
     /// Returns an iterator over the entries of the snapshot in ascending order starting from
     /// the specified key. The iterator element type is `(&[u8], &[u8])`.
     fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a>;
+
+    /// Returns an iterator over the entries of the snapshot in descending order starting from
+    /// the greatest key that is less than `to`, or from the last key if `to` is `None`.
+    /// The iterator element type is `(&[u8], &[u8])`.
+    fn iter_rev<'a>(&'a self, name: &str, to: Option<&[u8]>) -> Iter<'a>;
 }
 
 /// A trait that defines a streaming iterator over storage view entries. Unlike
@@ -357,67 +581,219 @@ impl Snapshot for Fork {
         Box::new(ForkIter {
             snapshot: self.snapshot.iter(name, from),
             changes,
+            reversed: false,
+        })
+    }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Option<&[u8]>) -> Iter<'a> {
+        let range = match to {
+            Some(to) => (Unbounded, Excluded(to)),
+            None => (Unbounded, Unbounded),
+        };
+        let changes = match self.patch.changes(name) {
+            Some(changes) => Some(changes.data.range::<[u8], _>(range).rev().peekable()),
+            None => None,
+        };
+
+        Box::new(ForkIter {
+            snapshot: self.snapshot.iter_rev(name, to),
+            changes,
+            reversed: true,
         })
     }
 }
 
 impl Fork {
+    /// Creates a new fork on top of the given snapshot.
+    pub(crate) fn new(snapshot: Box<dyn Snapshot>) -> Self {
+        Self {
+            snapshot,
+            patch: Patch::new(),
+            changelog: Vec::new(),
+            savepoints: Vec::new(),
+            next_savepoint_id: 0,
+            write_scope: None,
+        }
+    }
+
+    /// Restricts the changes of the fork to the indexes within the given namespaces, or removes
+    /// the restriction if `None` is passed. Returns the previous restriction.
+    pub(crate) fn set_write_scope(&mut self, scope: Option<Vec<String>>) -> Option<Vec<String>> {
+        mem::replace(&mut self.write_scope, scope)
+    }
+
+    // Panics if the key (or the keys starting with it) of the column family with the given
+    // name cannot be changed in the fork. Keys of the indexes metadata can be changed only
+    // for the indexes within the namespaces of the scope.
+    fn check_write_scope(&self, name: &str, key: &[u8]) {
+        if let Some(ref namespaces) = self.write_scope {
+            let index_name = if name == INDEXES_METADATA_TABLE_NAME {
+                // Metadata of the members of a family is keyed by the family name followed
+                // by a zero byte and the member ID.
+                let name_len = key.iter().position(|&byte| byte == 0).unwrap_or(key.len());
+                String::from_utf8_lossy(&key[..name_len])
+            } else {
+                Cow::Borrowed(name)
+            };
+            if !namespaces
+                .iter()
+                .any(|namespace| is_in_namespace(&index_name, namespace))
+            {
+                panic!(
+                    "Index `{}` cannot be changed outside of its namespace; changes are \
+                     allowed only within the namespaces {:?}",
+                    index_name, namespaces
+                );
+            }
+        }
+    }
+
+    // Returns `true` if changes are recorded into the changelog.
+    fn logged(&self) -> bool {
+        !self.savepoints.is_empty()
+    }
+
+    // Returns the position of the savepoint with the given ID in the stack.
+    fn savepoint_position(&self, id: SavepointId) -> Option<usize> {
+        self.savepoints
+            .iter()
+            .position(|&(savepoint_id, _)| savepoint_id == id)
+    }
+
+    // Reverts the changes recorded after the changelog had the given length.
+    fn revert_changelog(&mut self, len: usize) {
+        for (name, k, c) in self.changelog.drain(len..).rev() {
+            if let Some(changes) = self.patch.changes_mut(&name) {
+                match c {
+                    Some(change) => changes.data.insert(k, change),
+                    None => changes.data.remove(&k),
+                };
+            }
+        }
+    }
+
     /// Creates a new checkpoint.
     ///
     /// In Exonum checkpoints are created before applying each transaction to
-    /// the database.
+    /// the database. A checkpoint is the outermost savepoint; nested savepoints can
+    /// be created on top of it with [`savepoint`](#method.savepoint).
     ///
     /// # Panics
     ///
-    /// Panics if another checkpoint was created before and has not been committed or rolled back.
+    /// Panics if another checkpoint or savepoint was created before and has not been
+    /// committed, released or rolled back.
     pub fn checkpoint(&mut self) {
-        if self.logged {
+        if self.logged() {
             panic!("call checkpoint before rollback or commit");
         }
-        self.logged = true;
+        self.savepoint();
     }
 
-    /// Finalizes all changes after the latest checkpoint.
+    /// Finalizes all changes after the latest checkpoint, releasing all savepoints
+    /// created after it.
     ///
     /// # Panics
     ///
     /// Panics if there is no active checkpoint, or the latest checkpoint
     /// is already committed or rolled back.
     pub fn commit(&mut self) {
-        if !self.logged {
+        if !self.logged() {
             panic!("call commit before checkpoint");
         }
-        self.changelog.clear();
-        self.logged = false;
+        let id = self.savepoints[0].0;
+        self.release(id);
     }
 
-    /// Rolls back all changes after the latest checkpoint.
+    /// Rolls back all changes after the latest checkpoint, including the changes
+    /// made after the savepoints created after it.
     ///
     /// # Panics
     ///
     /// Panics if there is no active checkpoint, or the latest checkpoint
     /// is already committed or rolled back.
     pub fn rollback(&mut self) {
-        if !self.logged {
+        if !self.logged() {
             panic!("call rollback before checkpoint");
         }
-        for (name, k, c) in self.changelog.drain(..).rev() {
-            if let Some(changes) = self.patch.changes_mut(&name) {
-                match c {
-                    Some(change) => changes.data.insert(k, change),
-                    None => changes.data.remove(&k),
-                };
-            }
+        let id = self.savepoints[0].0;
+        self.rollback_to(id);
+    }
+
+    /// Creates a new savepoint nested into the active ones.
+    ///
+    /// The changes made after the savepoint can be rolled back with [`rollback_to`]
+    /// or kept with [`release`], independently of the outer savepoints. For example,
+    /// a transaction executed within a checkpoint may try an operation and discard only
+    /// the changes made by this operation.
+    ///
+    /// [`rollback_to`]: #method.rollback_to
+    /// [`release`]: #method.release
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{Database, MemoryDB, Snapshot};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// fork.checkpoint();
+    /// fork.put("table", vec![1], vec![1]);
+    ///
+    /// let savepoint = fork.savepoint();
+    /// fork.put("table", vec![2], vec![2]);
+    /// fork.rollback_to(savepoint);
+    ///
+    /// fork.commit();
+    /// assert_eq!(fork.get("table", &[1]), Some(vec![1]));
+    /// assert_eq!(fork.get("table", &[2]), None);
+    /// ```
+    pub fn savepoint(&mut self) -> SavepointId {
+        let id = SavepointId(self.next_savepoint_id);
+        self.next_savepoint_id += 1;
+        self.savepoints.push((id, self.changelog.len()));
+        id
+    }
+
+    /// Rolls back all changes made after the given savepoint. The savepoint and all
+    /// savepoints nested into it are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the savepoint is not active, i.e., it has already been rolled back or
+    /// released, or an outer savepoint has been rolled back or released.
+    pub fn rollback_to(&mut self, id: SavepointId) {
+        let position = self.savepoint_position(id)
+            .unwrap_or_else(|| panic!("call rollback_to with inactive savepoint {:?}", id));
+        let changelog_len = self.savepoints[position].1;
+        self.savepoints.truncate(position);
+        self.revert_changelog(changelog_len);
+    }
+
+    /// Keeps all changes made after the given savepoint. The savepoint and all
+    /// savepoints nested into it are removed; the changes can still be rolled back
+    /// with an outer savepoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the savepoint is not active, i.e., it has already been rolled back or
+    /// released, or an outer savepoint has been rolled back or released.
+    pub fn release(&mut self, id: SavepointId) {
+        let position = self.savepoint_position(id)
+            .unwrap_or_else(|| panic!("call release with inactive savepoint {:?}", id));
+        self.savepoints.truncate(position);
+        if !self.logged() {
+            self.changelog.clear();
         }
-        self.logged = false;
     }
 
     /// Inserts a key-value pair into the fork.
     pub fn put(&mut self, name: &str, key: Vec<u8>, value: Vec<u8>) {
+        self.check_write_scope(name, &key);
+        let logged = self.logged();
         let changes = self.patch
             .changes_entry(name.to_string())
             .or_insert_with(Changes::new);
-        if self.logged {
+        if logged {
             self.changelog.push((
                 name.to_string(),
                 key.clone(),
@@ -430,10 +806,12 @@ impl Fork {
 
     /// Removes a key from the fork.
     pub fn remove(&mut self, name: &str, key: Vec<u8>) {
+        self.check_write_scope(name, &key);
+        let logged = self.logged();
         let changes = self.patch
             .changes_entry(name.to_string())
             .or_insert_with(Changes::new);
-        if self.logged {
+        if logged {
             self.changelog.push((
                 name.to_string(),
                 key.clone(),
@@ -447,6 +825,8 @@ impl Fork {
     /// Removes all keys starting with the specified prefix from the column family
     /// with the given `name`.
     pub fn remove_by_prefix(&mut self, name: &str, prefix: Option<&Vec<u8>>) {
+        self.check_write_scope(name, prefix.map_or(&[], |prefix| prefix.as_slice()));
+        let logged = self.logged();
         let changes = self.patch
             .changes_entry(name.to_string())
             .or_insert_with(Changes::new);
@@ -459,7 +839,14 @@ impl Fork {
                 .take_while(|k| k.starts_with(prefix))
                 .collect::<Vec<_>>();
             for k in keys {
-                changes.data.remove(&k);
+                let change = changes.data.remove(&k);
+                if logged {
+                    self.changelog.push((name.to_string(), k, change));
+                }
+            }
+        } else if logged {
+            for (k, change) in mem::replace(&mut changes.data, BTreeMap::new()) {
+                self.changelog.push((name.to_string(), k, Some(change)));
             }
         } else {
             changes.data.clear();
@@ -469,12 +856,59 @@ impl Fork {
             .iter(name, prefix.map_or(&[], |k| k.as_slice()));
         while let Some((k, ..)) = iter.next() {
             let change = changes.data.insert(k.to_vec(), Change::Delete);
-            if self.logged {
+            if logged {
                 self.changelog.push((name.to_string(), k.to_vec(), change));
             }
         }
     }
 
+    /// Records the changes accumulated in this fork as the given version of the database
+    /// state, so that the state can later be retrieved with [`snapshot_at`].
+    ///
+    /// The method stores the values overwritten by the fork and should be called
+    /// right before converting the fork into a patch; changes made after the call are
+    /// not reflected in the history. Versions are expected to be recorded sequentially;
+    /// if `version` does not directly follow the latest recorded version, the history
+    /// is restarted from `version`.
+    ///
+    /// [`snapshot_at`]: trait.Database.html#method.snapshot_at
+    pub fn save_version(&mut self, version: u64) {
+        let undo = self.undo_patch();
+        history::save_version(self, version, &undo);
+    }
+
+    /// Removes the history of the versions preceding the given one, so that they can
+    /// no longer be retrieved with [`snapshot_at`]. The latest recorded version is
+    /// always kept.
+    ///
+    /// [`snapshot_at`]: trait.Database.html#method.snapshot_at
+    pub fn prune_history(&mut self, version: u64) {
+        history::prune(self, version);
+    }
+
+    /// Returns a patch reverting the changes accumulated in this fork.
+    fn undo_patch(&self) -> Patch {
+        let mut undo = Patch::new();
+        for (name, changes) in self.patch.iter() {
+            if history::is_history_table(name) {
+                continue;
+            }
+            let data = changes
+                .data
+                .keys()
+                .map(|key| {
+                    let change = match self.snapshot.get(name, key) {
+                        Some(value) => Change::Put(value),
+                        None => Change::Delete,
+                    };
+                    (key.clone(), change)
+                })
+                .collect();
+            undo.insert_changes(name.clone(), Changes { data });
+        }
+        undo
+    }
+
     /// Converts the fork into `Patch` consuming the fork instance.
     pub fn into_patch(self) -> Patch {
         self.patch
@@ -492,10 +926,10 @@ impl Fork {
     ///
     /// # Panics
     ///
-    /// Panics if a checkpoint has been created before and has not been committed
-    /// or rolled back yet.
+    /// Panics if a checkpoint or a savepoint has been created before and has not been
+    /// committed, released or rolled back yet.
     pub fn merge(&mut self, patch: Patch) {
-        if self.logged {
+        if self.logged() {
             panic!("call merge before commit or rollback");
         }
 
@@ -511,6 +945,13 @@ impl Fork {
     }
 }
 
+/// Returns `true` if the index with the given name belongs to the namespace, i.e., the name
+/// is equal to the namespace or starts with the namespace followed by a dot.
+pub(crate) fn is_in_namespace(name: &str, namespace: &str) -> bool {
+    name.starts_with(namespace)
+        && (name.len() == namespace.len() || name[namespace.len()..].starts_with('.'))
+}
+
 impl AsRef<dyn Snapshot> for dyn Snapshot + 'static {
     fn as_ref(&self) -> &dyn Snapshot {
         self
@@ -529,23 +970,34 @@ impl ::std::fmt::Debug for Fork {
     }
 }
 
-impl<'a> ForkIter<'a> {
+impl<'a, T> ForkIter<'a, T>
+where
+    T: StdIterator<Item = (&'a Vec<u8>, &'a Change)>,
+{
     fn step(&mut self) -> NextIterValue {
         if let Some(ref mut changes) = self.changes {
             match changes.peek() {
                 Some(&(k, change)) => match self.snapshot.peek() {
-                    Some((key, ..)) => match *change {
-                        Change::Put(..) => match k[..].cmp(key) {
-                            Equal => NextIterValue::Replaced,
-                            Less => NextIterValue::Inserted,
-                            Greater => NextIterValue::Stored,
-                        },
-                        Change::Delete => match k[..].cmp(key) {
-                            Equal => NextIterValue::Deleted,
-                            Less => NextIterValue::MissDeleted,
-                            Greater => NextIterValue::Stored,
-                        },
-                    },
+                    Some((key, ..)) => {
+                        // In the reversed iterator, greater keys come first.
+                        let ordering = if self.reversed {
+                            key.cmp(&k[..])
+                        } else {
+                            k[..].cmp(key)
+                        };
+                        match *change {
+                            Change::Put(..) => match ordering {
+                                Equal => NextIterValue::Replaced,
+                                Less => NextIterValue::Inserted,
+                                Greater => NextIterValue::Stored,
+                            },
+                            Change::Delete => match ordering {
+                                Equal => NextIterValue::Deleted,
+                                Less => NextIterValue::MissDeleted,
+                                Greater => NextIterValue::Stored,
+                            },
+                        }
+                    }
                     None => match *change {
                         Change::Put(..) => NextIterValue::Inserted,
                         Change::Delete => NextIterValue::MissDeleted,
@@ -565,7 +1017,10 @@ impl<'a> ForkIter<'a> {
     }
 }
 
-impl<'a> Iterator for ForkIter<'a> {
+impl<'a, T> Iterator for ForkIter<'a, T>
+where
+    T: StdIterator<Item = (&'a Vec<u8>, &'a Change)>,
+{
     fn next(&mut self) -> Option<(&[u8], &[u8])> {
         loop {
             match self.step() {
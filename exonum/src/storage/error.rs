@@ -18,6 +18,8 @@
 
 //! An implementation of `Error` type.
 
+use std::io;
+
 /// The error type for I/O operations with storage.
 ///
 /// These errors result in a panic. Storage errors are fatal as in the case of
@@ -38,3 +40,9 @@ impl Error {
         }
     }
 }
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::new(err.to_string())
+    }
+}
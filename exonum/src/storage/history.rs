@@ -0,0 +1,142 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioned history of the database state.
+//!
+//! For every recorded version the history keeps a reverse patch, i.e., the values
+//! overwritten by the changes of this version. A snapshot of an older version is
+//! reconstructed by applying the reverse patches of all newer versions on top of
+//! the current state, from the newest to the oldest one.
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use super::{Fork, Patch, Snapshot};
+
+/// Name of the column family storing the history of the database state.
+pub const STATE_HISTORY_TABLE_NAME: &str = "__STATE_HISTORY__";
+
+// Key of the recorded versions range. Reverse patches are stored under
+// 8-byte big-endian version numbers, so the empty key never clashes with them.
+const HISTORY_RANGE_KEY: &[u8] = &[];
+
+/// Inclusive range of versions available in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryRange {
+    /// The oldest version that can be retrieved.
+    pub first: u64,
+    /// The latest recorded version.
+    pub last: u64,
+}
+
+impl HistoryRange {
+    fn to_bytes(self) -> Vec<u8> {
+        let mut buf = vec![0; 16];
+        LittleEndian::write_u64(&mut buf[0..8], self.first);
+        LittleEndian::write_u64(&mut buf[8..16], self.last);
+        buf
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), 16, "Storage history range is corrupted");
+        Self {
+            first: LittleEndian::read_u64(&bytes[0..8]),
+            last: LittleEndian::read_u64(&bytes[8..16]),
+        }
+    }
+
+    /// Returns `true` if the given version can be retrieved from the history.
+    pub fn contains(&self, version: u64) -> bool {
+        self.first <= version && version <= self.last
+    }
+}
+
+pub fn is_history_table(name: &str) -> bool {
+    name == STATE_HISTORY_TABLE_NAME
+}
+
+fn version_key(version: u64) -> Vec<u8> {
+    let mut key = vec![0; 8];
+    BigEndian::write_u64(&mut key, version);
+    key
+}
+
+/// Returns the range of versions recorded in the given snapshot, if any.
+pub fn history_range(snapshot: &dyn Snapshot) -> Option<HistoryRange> {
+    snapshot
+        .get(STATE_HISTORY_TABLE_NAME, HISTORY_RANGE_KEY)
+        .map(|bytes| HistoryRange::from_bytes(&bytes))
+}
+
+pub fn save_version(fork: &mut Fork, version: u64, undo: &Patch) {
+    let range = match history_range(&*fork) {
+        Some(ref range) if range.last.checked_add(1) == Some(version) => HistoryRange {
+            first: range.first,
+            last: version,
+        },
+        _ => HistoryRange {
+            first: version,
+            last: version,
+        },
+    };
+    fork.put(STATE_HISTORY_TABLE_NAME, version_key(version), undo.to_bytes());
+    fork.put(
+        STATE_HISTORY_TABLE_NAME,
+        HISTORY_RANGE_KEY.to_vec(),
+        range.to_bytes(),
+    );
+}
+
+pub fn snapshot_at(snapshot: Box<dyn Snapshot>, version: u64) -> Option<Box<dyn Snapshot>> {
+    let range = history_range(&*snapshot)?;
+    if !range.contains(version) {
+        return None;
+    }
+
+    let mut undo_patches = Vec::new();
+    for v in (version + 1..=range.last).rev() {
+        let bytes = snapshot.get(STATE_HISTORY_TABLE_NAME, &version_key(v))?;
+        let undo = Patch::from_bytes(&bytes)
+            .unwrap_or_else(|e| panic!("Storage history of version {} is corrupted: {}", v, e));
+        undo_patches.push(undo);
+    }
+
+    let mut fork = Fork::new(snapshot);
+    for undo in undo_patches {
+        fork.merge(undo);
+    }
+    Some(Box::new(fork))
+}
+
+pub fn prune(fork: &mut Fork, first: u64) {
+    let range = match history_range(&*fork) {
+        Some(range) => range,
+        None => return,
+    };
+    let first = ::std::cmp::min(first, range.last);
+    if first <= range.first {
+        return;
+    }
+    for version in range.first..first {
+        fork.remove(STATE_HISTORY_TABLE_NAME, version_key(version));
+    }
+    let range = HistoryRange {
+        first,
+        last: range.last,
+    };
+    fork.put(
+        STATE_HISTORY_TABLE_NAME,
+        HISTORY_RANGE_KEY.to_vec(),
+        range.to_bytes(),
+    );
+}
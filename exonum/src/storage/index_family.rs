@@ -0,0 +1,333 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of access to the members of an index family.
+
+use std::marker::PhantomData;
+
+use super::{
+    indexes_metadata::{self, INDEXES_METADATA_TABLE_NAME}, Fork, Iter, Snapshot, StorageKey,
+};
+
+/// A view of an index family, i.e., the indices created with the `new_in_family` constructor
+/// under the same family name.
+///
+/// `IndexFamily` allows enumerating IDs of the family members and clearing the whole family.
+/// A member is recorded when its index is changed for the first time and is forgotten
+/// when the index is cleared.
+///
+/// IDs are returned in the ascending order of their serialized form. Since the data of family
+/// members is stored under the prefix of the serialized ID, the IDs should have a fixed size
+/// (for example, `PublicKey` or `Hash`), so that an ID is never a prefix of another ID.
+#[derive(Debug)]
+pub struct IndexFamily<T> {
+    name: String,
+    view: T,
+}
+
+/// An iterator over the IDs of an `IndexFamily` members.
+///
+/// This struct is created by the [`ids`] or [`ids_from`] method on [`IndexFamily`].
+/// See its documentation for details.
+///
+/// [`ids`]: struct.IndexFamily.html#method.ids
+/// [`ids_from`]: struct.IndexFamily.html#method.ids_from
+/// [`IndexFamily`]: struct.IndexFamily.html
+pub struct IndexFamilyIds<'a, K> {
+    base_iter: Iter<'a>,
+    prefix: Vec<u8>,
+    ended: bool,
+    _k: PhantomData<K>,
+}
+
+impl<T> IndexFamily<T>
+where
+    T: AsRef<dyn Snapshot>,
+{
+    /// Creates a new view of the index family based on the family name and storage view.
+    ///
+    /// Storage view can be specified as [`&Snapshot`] or [`&mut Fork`]. In the first case, only
+    /// immutable methods are available. In the second case, both immutable and mutable methods are
+    /// available.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `family_name` refers to an ordinary index.
+    ///
+    /// [`&Snapshot`]: ../trait.Snapshot.html
+    /// [`&mut Fork`]: ../struct.Fork.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, IndexFamily};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let family = IndexFamily::new("family", &snapshot);
+    /// ```
+    pub fn new<S: AsRef<str>>(family_name: S, view: T) -> Self {
+        indexes_metadata::assert_index_family(family_name.as_ref(), view.as_ref());
+        Self {
+            name: family_name.as_ref().to_string(),
+            view,
+        }
+    }
+
+    /// Returns `true` if the family contains a member with the specified ID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, IndexFamily, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// ListIndex::new_in_family("family", &1_u64, &mut fork).push(10_u8);
+    ///
+    /// let family = IndexFamily::new("family", &fork);
+    /// assert!(family.contains(&1_u64));
+    /// assert!(!family.contains(&2_u64));
+    /// ```
+    pub fn contains<K>(&self, index_id: &K) -> bool
+    where
+        K: StorageKey + ?Sized,
+    {
+        let mut buffer = vec![0; index_id.size()];
+        index_id.write(&mut buffer);
+        indexes_metadata::is_family_member(&self.name, &buffer, self.view.as_ref())
+    }
+
+    /// Returns an iterator over the IDs of the family members in ascending order.
+    /// The iterator element type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, IndexFamily, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// ListIndex::new_in_family("family", &2_u64, &mut fork).push(10_u8);
+    /// ListIndex::new_in_family("family", &1_u64, &mut fork).push(20_u8);
+    ///
+    /// let family = IndexFamily::new("family", &fork);
+    /// assert_eq!(family.ids::<u64>().collect::<Vec<_>>(), vec![1, 2]);
+    /// ```
+    pub fn ids<K: StorageKey>(&self) -> IndexFamilyIds<K> {
+        let prefix = indexes_metadata::family_members_prefix(&self.name);
+        IndexFamilyIds {
+            base_iter: self.view
+                .as_ref()
+                .iter(INDEXES_METADATA_TABLE_NAME, &prefix),
+            prefix,
+            ended: false,
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the IDs of the family members in ascending order starting
+    /// from the specified ID. The iterator element type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, IndexFamily, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// for id in 0..5_u64 {
+    ///     ListIndex::new_in_family("family", &id, &mut fork).push(10_u8);
+    /// }
+    ///
+    /// let family = IndexFamily::new("family", &fork);
+    /// assert_eq!(family.ids_from(&3_u64).collect::<Vec<_>>(), vec![3, 4]);
+    /// ```
+    pub fn ids_from<K: StorageKey>(&self, from: &K) -> IndexFamilyIds<K> {
+        let prefix = indexes_metadata::family_members_prefix(&self.name);
+        let mut iter_from = prefix.clone();
+        let prefix_len = prefix.len();
+        iter_from.resize(prefix_len + from.size(), 0);
+        from.write(&mut iter_from[prefix_len..]);
+        IndexFamilyIds {
+            base_iter: self.view
+                .as_ref()
+                .iter(INDEXES_METADATA_TABLE_NAME, &iter_from),
+            prefix,
+            ended: false,
+            _k: PhantomData,
+        }
+    }
+}
+
+impl<'a> IndexFamily<&'a mut Fork> {
+    /// Clears the whole index family, removing the data of all its members.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of entries
+    /// in the family.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, IndexFamily, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// ListIndex::new_in_family("family", &1_u64, &mut fork).push(10_u8);
+    ///
+    /// IndexFamily::new("family", &mut fork).clear();
+    /// assert!(ListIndex::<_, u8>::new_in_family("family", &1_u64, &fork).is_empty());
+    /// assert_eq!(IndexFamily::new("family", &fork).ids::<u64>().count(), 0);
+    /// ```
+    pub fn clear(&mut self) {
+        indexes_metadata::clear_family(&self.name, self.view);
+    }
+}
+
+impl<'a, K> Iterator for IndexFamilyIds<'a, K>
+where
+    K: StorageKey,
+{
+    type Item = K::Owned;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+        if let Some((k, _)) = self.base_iter.next() {
+            if k.starts_with(&self.prefix) {
+                return Some(K::read(&k[self.prefix.len()..]));
+            }
+        }
+        self.ended = true;
+        None
+    }
+}
+
+impl<'a, K> ::std::fmt::Debug for IndexFamilyIds<'a, K> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "IndexFamilyIds(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::{gen_keypair, PublicKey};
+    use storage::{Database, ListIndex, MapIndex, MemoryDB, ProofMapIndex};
+
+    const FAMILY_NAME: &str = "family";
+
+    #[test]
+    fn family_members() {
+        let db = MemoryDB::new();
+        let mut keys: Vec<PublicKey> = (0..5).map(|_| gen_keypair().0).collect();
+
+        let mut fork = db.fork();
+        for (i, key) in keys.iter().enumerate() {
+            let mut index = ListIndex::new_in_family(FAMILY_NAME, key, &mut fork);
+            index.push(i as u64);
+        }
+        // Reading an index does not make it a member.
+        let absent_key = gen_keypair().0;
+        assert!(ListIndex::<_, u64>::new_in_family(FAMILY_NAME, &absent_key, &fork).is_empty());
+        db.merge(fork.into_patch()).unwrap();
+
+        keys.sort();
+        let snapshot = db.snapshot();
+        let family = IndexFamily::new(FAMILY_NAME, &snapshot);
+        assert_eq!(family.ids::<PublicKey>().collect::<Vec<_>>(), keys);
+        assert_eq!(
+            family.ids_from(&keys[2]).collect::<Vec<_>>(),
+            keys[2..].to_vec()
+        );
+        assert!(keys.iter().all(|key| family.contains(key)));
+        assert!(!family.contains(&absent_key));
+    }
+
+    #[test]
+    fn clear_family_member() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        for id in 0..3_u8 {
+            let mut index = MapIndex::new_in_family(FAMILY_NAME, &id, &mut fork);
+            index.put(&1_u8, 2_u8);
+        }
+
+        {
+            let mut index: MapIndex<_, u8, u8> =
+                MapIndex::new_in_family(FAMILY_NAME, &1_u8, &mut fork);
+            index.clear();
+            index.put(&3, 4);
+            index.clear();
+        }
+        let family = IndexFamily::new(FAMILY_NAME, &fork);
+        assert_eq!(family.ids::<u8>().collect::<Vec<_>>(), vec![0, 2]);
+
+        {
+            let mut index = MapIndex::new_in_family(FAMILY_NAME, &1_u8, &mut fork);
+            index.clear();
+            index.put(&3_u8, 4_u8);
+        }
+        let family = IndexFamily::new(FAMILY_NAME, &fork);
+        assert_eq!(family.ids::<u8>().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn clear_whole_family() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        for id in 0..3_u8 {
+            let mut index = ProofMapIndex::new_in_family(FAMILY_NAME, &id, &mut fork);
+            index.put(&PublicKey::zero(), 1_u8);
+        }
+        MapIndex::new_in_family("other_family", &0_u8, &mut fork).put(&1_u8, 2_u8);
+        db.merge(fork.into_patch()).unwrap();
+
+        let mut fork = db.fork();
+        IndexFamily::new(FAMILY_NAME, &mut fork).clear();
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.iter(FAMILY_NAME, &[]).next(), None);
+        assert_eq!(
+            IndexFamily::new(FAMILY_NAME, &snapshot).ids::<u8>().count(),
+            0
+        );
+
+        let other = IndexFamily::new("other_family", &snapshot);
+        assert_eq!(other.ids::<u8>().collect::<Vec<_>>(), vec![0]);
+        let index: MapIndex<_, u8, u8> = MapIndex::new_in_family("other_family", &0_u8, &snapshot);
+        assert_eq!(index.get(&1), Some(2));
+
+        // The type of the family is retained.
+        let mut fork = db.fork();
+        let mut index = ProofMapIndex::new_in_family(FAMILY_NAME, &0_u8, &mut fork);
+        index.put(&PublicKey::zero(), 2_u8);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Attempt to access index family 'index' while it's an ordinary index"
+    )]
+    fn ordinary_index_as_family() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        ListIndex::new("index", &mut fork).push(1_u8);
+        IndexFamily::new("index", &fork);
+    }
+}
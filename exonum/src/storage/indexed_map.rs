@@ -0,0 +1,363 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a map with secondary indexes.
+//!
+//! `IndexedMap` wraps a [`MapIndex`] or a [`ProofMapIndex`] and maintains secondary indexes
+//! for its values. Each secondary index maps a key extracted from the value to the set
+//! of the primary keys of the entries with that value.
+//!
+//! [`MapIndex`]: ../map_index/struct.MapIndex.html
+//! [`ProofMapIndex`]: ../proof_map_index/struct.ProofMapIndex.html
+
+use std::fmt;
+
+use super::{Fork, IndexFamily, KeySetIndex, Snapshot, StorageKey, StorageValue};
+use crypto::{self, Hash};
+
+/// A primary index of an [`IndexedMap`].
+///
+/// The trait is implemented for [`MapIndex`] and [`ProofMapIndex`] with keys implementing
+/// [`StorageKey`] and `Clone`.
+///
+/// [`IndexedMap`]: struct.IndexedMap.html
+/// [`MapIndex`]: ../map_index/struct.MapIndex.html
+/// [`ProofMapIndex`]: ../proof_map_index/struct.ProofMapIndex.html
+/// [`StorageKey`]: ../trait.StorageKey.html
+pub trait PrimaryIndex {
+    /// Type of the keys of the index.
+    type Key: StorageKey + Clone;
+    /// Type of the values of the index.
+    type Value: StorageValue;
+
+    /// Returns the storage view the index is based on.
+    fn snapshot(&self) -> &(dyn Snapshot + 'static);
+
+    /// Returns a value corresponding to the key.
+    fn get(&self, key: &Self::Key) -> Option<Self::Value>;
+}
+
+/// A primary index of an [`IndexedMap`] based on a fork.
+///
+/// [`IndexedMap`]: struct.IndexedMap.html
+pub trait PrimaryIndexMut: PrimaryIndex {
+    /// Returns the fork the index is based on.
+    fn fork(&mut self) -> &mut Fork;
+
+    /// Inserts a key-value pair into the index.
+    fn put(&mut self, key: &Self::Key, value: Self::Value);
+
+    /// Removes a key from the index.
+    fn remove(&mut self, key: &Self::Key);
+
+    /// Clears the index, removing all entries.
+    fn clear(&mut self);
+}
+
+/// A map maintaining secondary indexes of its values.
+///
+/// Secondary indexes are declared with the [`with_index`] method by the name and the function
+/// extracting a secondary key from a value. An index is stored as an index family of
+/// [`KeySetIndex`]es with the given name; the set for a secondary key contains the primary keys
+/// of the entries with that secondary key. The ID of a family member is the hash
+/// of the secondary key, so secondary keys may have variable size.
+///
+/// The secondary indexes are updated by the [`put`], [`remove`] and [`clear`] methods.
+/// Changes made directly to the primary index are not reflected in the secondary indexes.
+/// The secondary indexes should be declared in the same way whenever the map is created.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{Database, IndexedMap, MapIndex, MemoryDB};
+///
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// {
+///     let mut map = IndexedMap::new(MapIndex::new("users", &mut fork))
+///         .with_index("users.by_city", |city: &String| city.clone());
+///     map.put(&1_u64, "Paris".to_owned());
+///     map.put(&2_u64, "London".to_owned());
+///     map.put(&3_u64, "Paris".to_owned());
+///     map.put(&3_u64, "Berlin".to_owned());
+/// }
+///
+/// let map = IndexedMap::new(MapIndex::<_, u64, String>::new("users", &fork))
+///     .with_index("users.by_city", |city: &String| city.clone());
+/// assert_eq!(map.find_keys("users.by_city", &"Paris".to_owned()), vec![1]);
+/// assert_eq!(map.find("users.by_city", &"Berlin".to_owned()), vec![(3, "Berlin".to_owned())]);
+/// ```
+///
+/// [`with_index`]: #method.with_index
+/// [`KeySetIndex`]: ../key_set_index/struct.KeySetIndex.html
+/// [`put`]: #method.put
+/// [`remove`]: #method.remove
+/// [`clear`]: #method.clear
+pub struct IndexedMap<I: PrimaryIndex> {
+    primary: I,
+    secondary: Vec<SecondaryIndex<I::Value>>,
+}
+
+struct SecondaryIndex<V> {
+    name: String,
+    extractor: Box<dyn Fn(&V) -> Vec<u8>>,
+}
+
+impl<I: PrimaryIndex> IndexedMap<I> {
+    /// Creates a map based on the primary index without secondary indexes.
+    pub fn new(primary: I) -> Self {
+        Self {
+            primary,
+            secondary: Vec::new(),
+        }
+    }
+
+    /// Declares a secondary index with the given name. The secondary key of a value
+    /// is computed by the `extractor` function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a secondary index with the same name has already been declared.
+    pub fn with_index<S, F, Q>(mut self, name: S, extractor: F) -> Self
+    where
+        S: AsRef<str>,
+        F: Fn(&I::Value) -> Q + 'static,
+        Q: StorageKey,
+    {
+        let name = name.as_ref().to_owned();
+        assert!(
+            self.secondary.iter().all(|index| index.name != name),
+            "Secondary index {} is declared twice",
+            name
+        );
+        self.secondary.push(SecondaryIndex {
+            name,
+            extractor: Box::new(move |value| key_bytes(&extractor(value))),
+        });
+        self
+    }
+
+    /// Returns the primary index of the map.
+    pub fn primary(&self) -> &I {
+        &self.primary
+    }
+
+    /// Returns a value corresponding to the primary key.
+    pub fn get(&self, key: &I::Key) -> Option<I::Value> {
+        self.primary.get(key)
+    }
+
+    /// Returns the primary keys of the entries with the given key in the secondary index,
+    /// in ascending order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index has not been declared.
+    pub fn find_keys<Q>(&self, index_name: &str, secondary_key: &Q) -> Vec<I::Key>
+    where
+        Q: StorageKey + ?Sized,
+    {
+        self.assert_declared(index_name);
+        let id = member_id(&key_bytes(secondary_key));
+        let keys: KeySetIndex<_, I::Key> =
+            KeySetIndex::new_in_family(index_name, &id, self.primary.snapshot());
+        keys.iter().collect()
+    }
+
+    /// Returns the entries with the given key in the secondary index, in ascending order
+    /// of the primary keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secondary index has not been declared.
+    pub fn find<Q>(&self, index_name: &str, secondary_key: &Q) -> Vec<(I::Key, I::Value)>
+    where
+        Q: StorageKey + ?Sized,
+    {
+        self.find_keys(index_name, secondary_key)
+            .into_iter()
+            .filter_map(|key| self.primary.get(&key).map(|value| (key, value)))
+            .collect()
+    }
+
+    fn assert_declared(&self, name: &str) {
+        assert!(
+            self.secondary.iter().any(|index| index.name == name),
+            "Secondary index {} is not declared",
+            name
+        );
+    }
+}
+
+impl<I: PrimaryIndexMut> IndexedMap<I> {
+    /// Inserts a key-value pair into the map, updating the secondary indexes.
+    pub fn put(&mut self, key: &I::Key, value: I::Value) {
+        let old_value = self.primary.get(key);
+        for index in &self.secondary {
+            let new_id = (index.extractor)(&value);
+            let old_id = old_value.as_ref().map(|old| (index.extractor)(old));
+            if old_id.as_ref() == Some(&new_id) {
+                continue;
+            }
+
+            let fork = self.primary.fork();
+            if let Some(old_id) = old_id {
+                let mut keys: KeySetIndex<_, I::Key> =
+                    KeySetIndex::new_in_family(&index.name, &member_id(&old_id), &mut *fork);
+                keys.remove(key);
+            }
+            let mut keys = KeySetIndex::new_in_family(&index.name, &member_id(&new_id), fork);
+            keys.insert(key.clone());
+        }
+        self.primary.put(key, value);
+    }
+
+    /// Removes a key from the map, updating the secondary indexes.
+    pub fn remove(&mut self, key: &I::Key) {
+        let old_value = match self.primary.get(key) {
+            Some(value) => value,
+            None => return,
+        };
+        for index in &self.secondary {
+            let old_id = (index.extractor)(&old_value);
+            let mut keys: KeySetIndex<_, I::Key> =
+                KeySetIndex::new_in_family(&index.name, &member_id(&old_id), self.primary.fork());
+            keys.remove(key);
+        }
+        self.primary.remove(key);
+    }
+
+    /// Clears the map, removing all entries from the primary and the secondary indexes.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of entries
+    /// in the map.
+    pub fn clear(&mut self) {
+        for index in &self.secondary {
+            IndexFamily::new(&index.name, self.primary.fork()).clear();
+        }
+        self.primary.clear();
+    }
+}
+
+impl<I> fmt::Debug for IndexedMap<I>
+where
+    I: PrimaryIndex + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self
+            .secondary
+            .iter()
+            .map(|index| index.name.as_str())
+            .collect();
+        f.debug_struct("IndexedMap")
+            .field("primary", &self.primary)
+            .field("secondary", &names)
+            .finish()
+    }
+}
+
+fn key_bytes<K: StorageKey + ?Sized>(key: &K) -> Vec<u8> {
+    let mut buffer = vec![0; key.size()];
+    key.write(&mut buffer);
+    buffer
+}
+
+// The hash of the serialized secondary key is used as the ID of the family member,
+// so that an ID is never a prefix of another ID.
+fn member_id(secondary_key: &[u8]) -> Hash {
+    crypto::hash(secondary_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Database, MapIndex, MemoryDB, ProofMapIndex};
+    use super::*;
+
+    const INDEX_NAME: &str = "test_index_name";
+    const BY_LEN: &str = "test_index_name.by_len";
+    const BY_FIRST: &str = "test_index_name.by_first";
+
+    #[test]
+    fn secondary_indexes_follow_changes() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        {
+            let mut map = IndexedMap::new(MapIndex::new(INDEX_NAME, &mut fork))
+                .with_index(BY_LEN, |value: &String| value.len() as u64)
+                .with_index(BY_FIRST, |value: &String| value[..1].to_owned());
+
+            map.put(&1_u64, "apple".to_owned());
+            map.put(&2_u64, "apricot".to_owned());
+            map.put(&3_u64, "pear".to_owned());
+            assert_eq!(map.find_keys(BY_LEN, &5_u64), vec![1]);
+            assert_eq!(map.find_keys(BY_FIRST, "a"), vec![1, 2]);
+            assert_eq!(map.find(BY_FIRST, "p"), vec![(3, "pear".to_owned())]);
+
+            map.put(&1_u64, "plum".to_owned());
+            assert!(map.find_keys(BY_LEN, &5_u64).is_empty());
+            assert_eq!(map.find_keys(BY_LEN, &4_u64), vec![1, 3]);
+            assert_eq!(map.find_keys(BY_FIRST, "a"), vec![2]);
+            assert_eq!(map.find_keys(BY_FIRST, "p"), vec![1, 3]);
+
+            map.remove(&3_u64);
+            map.remove(&4_u64);
+            assert_eq!(map.get(&3_u64), None);
+            assert_eq!(map.find_keys(BY_LEN, &4_u64), vec![1]);
+            assert_eq!(map.find_keys(BY_FIRST, "p"), vec![1]);
+
+            map.clear();
+            assert_eq!(map.primary().iter().count(), 0);
+            assert!(map.find_keys(BY_LEN, &7_u64).is_empty());
+            assert!(map.find_keys(BY_FIRST, "p").is_empty());
+        }
+        assert_eq!(IndexFamily::new(BY_FIRST, &fork).ids::<Hash>().count(), 0);
+    }
+
+    #[test]
+    fn proof_map_primary() {
+        let db = MemoryDB::new();
+        let mut fork = db.fork();
+        let keys: Vec<Hash> = (0..4_u8).map(|i| crypto::hash(&[i])).collect();
+        {
+            let mut map = IndexedMap::new(ProofMapIndex::new(INDEX_NAME, &mut fork))
+                .with_index(BY_LEN, |value: &String| value.len() as u64);
+            for (i, key) in keys.iter().enumerate() {
+                map.put(key, "x".repeat(i % 2 + 1));
+            }
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let map = IndexedMap::new(ProofMapIndex::<_, Hash, String>::new(INDEX_NAME, &snapshot))
+            .with_index(BY_LEN, |value: &String| value.len() as u64);
+        let mut expected = vec![keys[1], keys[3]];
+        expected.sort();
+        assert_eq!(map.find_keys(BY_LEN, &2_u64), expected);
+
+        let proof = map.primary().get_proof(keys[0]).check().unwrap();
+        assert_eq!(proof.merkle_root(), map.primary().merkle_root());
+    }
+
+    #[test]
+    #[should_panic(expected = "Secondary index test_index_name.by_len is not declared")]
+    fn undeclared_index() {
+        let db = MemoryDB::new();
+        let snapshot = db.snapshot();
+        let map = IndexedMap::new(MapIndex::<_, u64, String>::new(INDEX_NAME, &snapshot));
+        map.find_keys(BY_LEN, &1_u64);
+    }
+}
@@ -22,7 +22,7 @@ use crypto::{self, CryptoHash, Hash};
 use encoding::{
     serialize::{json, WriteBufferWrapper}, CheckedOffset, Error as EncodingError, Field, Offset,
 };
-use storage::{base_index::BaseIndex, Fork, Snapshot, StorageValue};
+use storage::{base_index::BaseIndex, history, Fork, Snapshot, StorageValue};
 
 pub const INDEXES_METADATA_TABLE_NAME: &str = "__INDEXES_METADATA__";
 
@@ -31,6 +31,9 @@ pub const INDEXES_METADATA_TABLE_NAME: &str = "__INDEXES_METADATA__";
 // upon the introduction of breaking changes to the storage.
 const CORE_STORAGE_METADATA: StorageMetadata = StorageMetadata { version: 0 };
 const CORE_STORAGE_METADATA_KEY: &str = "__STORAGE_METADATA__";
+// Data versions of services are recorded as members of this reserved family
+// with the service names as IDs.
+const SERVICE_DATA_VERSIONS: &str = "__SERVICE_DATA_VERSIONS__";
 
 encoding_struct! {
     struct IndexMetadata {
@@ -39,16 +42,25 @@ encoding_struct! {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Type of an index recorded in the indexes metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum IndexType {
+    /// [`Entry`](struct.Entry.html).
     Entry,
+    /// [`KeySetIndex`](key_set_index/struct.KeySetIndex.html).
     KeySet,
+    /// [`ListIndex`](list_index/struct.ListIndex.html).
     List,
+    /// [`SparseListIndex`](sparse_list_index/struct.SparseListIndex.html).
     SparseList,
+    /// [`MapIndex`](map_index/struct.MapIndex.html).
     Map,
+    /// [`ProofListIndex`](proof_list_index/struct.ProofListIndex.html).
     ProofList,
+    /// [`ProofMapIndex`](proof_map_index/struct.ProofMapIndex.html).
     ProofMap,
+    /// [`ValueSetIndex`](value_set_index/struct.ValueSetIndex.html).
     ValueSet,
 }
 
@@ -225,14 +237,133 @@ impl fmt::Display for StorageMetadata {
     }
 }
 
-pub fn set_index_type(name: &str, index_type: IndexType, is_family: bool, view: &mut Fork) {
-    if name == INDEXES_METADATA_TABLE_NAME || name == CORE_STORAGE_METADATA_KEY {
+fn assert_not_internal(name: &str) {
+    if name == INDEXES_METADATA_TABLE_NAME
+        || name == CORE_STORAGE_METADATA_KEY
+        || name == SERVICE_DATA_VERSIONS
+        || history::is_history_table(name)
+    {
         panic!("Attempt to access an internal storage infrastructure");
     }
+}
+
+pub fn set_index_type(
+    name: &str,
+    index_type: IndexType,
+    index_id: Option<&[u8]>,
+    view: &mut Fork,
+) {
+    assert_not_internal(name);
     let mut metadata = BaseIndex::indexes_metadata(view);
     if metadata.get::<_, IndexMetadata>(name).is_none() {
-        metadata.put(&name.to_owned(), IndexMetadata::new(index_type, is_family));
+        metadata.put(
+            &name.to_owned(),
+            IndexMetadata::new(index_type, index_id.is_some()),
+        );
     }
+    if let Some(index_id) = index_id {
+        let key = family_member_key(name, index_id);
+        if !metadata.contains(&key) {
+            metadata.put(&key, ());
+        }
+    }
+}
+
+// Members of index families are recorded under the family name followed by a zero byte
+// and the index ID. Index names cannot contain zero bytes, so these keys never clash
+// with the names of indexes.
+pub fn family_members_prefix(family_name: &str) -> Vec<u8> {
+    let mut prefix = family_name.as_bytes().to_vec();
+    prefix.push(0);
+    prefix
+}
+
+fn family_member_key(family_name: &str, index_id: &[u8]) -> Vec<u8> {
+    let mut key = family_members_prefix(family_name);
+    key.extend_from_slice(index_id);
+    key
+}
+
+pub fn assert_index_family(family_name: &str, view: &dyn Snapshot) {
+    let metadata = BaseIndex::indexes_metadata(view);
+    if let Some(value) = metadata.get::<_, IndexMetadata>(family_name) {
+        assert!(
+            value.is_family(),
+            "Attempt to access index family '{}' while it's an ordinary index",
+            family_name
+        );
+    }
+}
+
+pub fn is_family_member(family_name: &str, index_id: &[u8], view: &dyn Snapshot) -> bool {
+    view.contains(
+        INDEXES_METADATA_TABLE_NAME,
+        &family_member_key(family_name, index_id),
+    )
+}
+
+pub fn remove_family_member(family_name: &str, index_id: &[u8], view: &mut Fork) {
+    view.remove(
+        INDEXES_METADATA_TABLE_NAME,
+        family_member_key(family_name, index_id),
+    );
+}
+
+/// Returns the data version of the service recorded in the metadata.
+pub fn service_data_version(service_name: &str, view: &dyn Snapshot) -> Option<u32> {
+    let metadata = BaseIndex::indexes_metadata(view);
+    metadata.get(&family_member_key(
+        SERVICE_DATA_VERSIONS,
+        service_name.as_bytes(),
+    ))
+}
+
+/// Records the data version of the service in the metadata.
+pub fn set_service_data_version(service_name: &str, version: u32, view: &mut Fork) {
+    let mut metadata = BaseIndex::indexes_metadata(view);
+    metadata.put(
+        &family_member_key(SERVICE_DATA_VERSIONS, service_name.as_bytes()),
+        version,
+    );
+}
+
+/// Returns names, types and family flags of all indexes recorded in the metadata,
+/// ordered by names.
+pub fn indexes(view: &dyn Snapshot) -> Vec<(String, IndexType, bool)> {
+    let mut indexes = Vec::new();
+    let mut iter = view.iter(INDEXES_METADATA_TABLE_NAME, &[]);
+    while let Some((key, value)) = iter.next() {
+        // Skip the storage version and records about family members.
+        if key == CORE_STORAGE_METADATA_KEY.as_bytes() || key.contains(&0) {
+            continue;
+        }
+        let name = String::from_utf8(key.to_vec()).expect("Index name is not valid UTF-8");
+        let metadata = IndexMetadata::from_bytes(Cow::Borrowed(value));
+        indexes.push((name, metadata.index_type(), metadata.is_family()));
+    }
+    indexes
+}
+
+/// Returns names of all column families holding the data of the storage, i.e., the internal
+/// tables followed by the indexes recorded in the metadata.
+pub fn table_names(view: &dyn Snapshot) -> Vec<String> {
+    let mut names = vec![
+        INDEXES_METADATA_TABLE_NAME.to_owned(),
+        history::STATE_HISTORY_TABLE_NAME.to_owned(),
+    ];
+    names.extend(indexes(view).into_iter().map(|(name, ..)| name));
+    names
+}
+
+/// Removes all data of the index family and records about its members. The type
+/// of the family is retained.
+pub fn clear_family(family_name: &str, view: &mut Fork) {
+    assert_not_internal(family_name);
+    view.remove_by_prefix(family_name, None);
+    view.remove_by_prefix(
+        INDEXES_METADATA_TABLE_NAME,
+        Some(&family_members_prefix(family_name)),
+    );
 }
 
 #[cfg(test)]
@@ -242,7 +373,9 @@ mod tests {
         CORE_STORAGE_METADATA_KEY, INDEXES_METADATA_TABLE_NAME,
     };
     use crypto::{Hash, PublicKey};
-    use storage::{base_index::BaseIndex, Database, Fork, MapIndex, MemoryDB, ProofMapIndex};
+    use storage::{
+        base_index::BaseIndex, Database, Fork, ListIndex, MapIndex, MemoryDB, ProofMapIndex,
+    };
 
     #[test]
     fn index_metadata_roundtrip() {
@@ -390,6 +523,43 @@ mod tests {
         index.put(&Hash::zero(), 43);
     }
 
+    #[test]
+    fn metadata_within_write_scope() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        fork.set_write_scope(Some(vec!["owner".to_owned()]));
+
+        ListIndex::new("owner.values", &mut fork).push(1_u64);
+        MapIndex::new_in_family("owner.family", &1_u8, &mut fork).put(&Hash::zero(), 2_u64);
+
+        fork.set_write_scope(None);
+        let metadata = BaseIndex::indexes_metadata(&fork);
+        assert!(metadata.contains("owner.values"));
+        assert!(metadata.contains("owner.family"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Index `other.values` cannot be changed outside of its namespace")]
+    fn metadata_outside_write_scope() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        fork.set_write_scope(Some(vec!["owner".to_owned()]));
+
+        let key = b"other.values".to_vec();
+        fork.put(INDEXES_METADATA_TABLE_NAME, key, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index `other.family` cannot be changed outside of its namespace")]
+    fn family_metadata_outside_write_scope() {
+        let database = MemoryDB::new();
+        let mut fork = database.fork();
+        fork.set_write_scope(Some(vec!["owner".to_owned()]));
+
+        let key = b"other.family\x00\x01".to_vec();
+        fork.remove(INDEXES_METADATA_TABLE_NAME, key);
+    }
+
     #[test]
     fn test_storage_version_write_current() {
         let database = MemoryDB::new();
@@ -38,11 +38,13 @@ pub struct KeySetIndex<T, K> {
 
 /// Returns an iterator over the items of a `KeySetIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`KeySetIndex`]. See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`] or
+/// [`iter_rev_from`] method on [`KeySetIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.KeySetIndex.html#method.iter
 /// [`iter_from`]: struct.KeySetIndex.html#method.iter_from
+/// [`iter_rev`]: struct.KeySetIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.KeySetIndex.html#method.iter_rev_from
 /// [`KeySetIndex`]: struct.KeySetIndex.html
 #[derive(Debug)]
 pub struct KeySetIndexIter<'a, K> {
@@ -180,6 +182,52 @@ where
             base_iter: self.base.iter_from(&(), from),
         }
     }
+
+    /// Returns an iterator visiting all elements in descending order. The iterator element
+    /// type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, KeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: KeySetIndex<_, u8> = KeySetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter_rev() {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn iter_rev(&self) -> KeySetIndexIter<K> {
+        KeySetIndexIter {
+            base_iter: self.base.iter_rev(&()),
+        }
+    }
+
+    /// Returns an iterator visiting all elements in descending order starting from the specified
+    /// value (inclusive). The iterator element type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, KeySetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: KeySetIndex<_, u8> = KeySetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter_rev_from(&2) {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn iter_rev_from(&self, from: &K) -> KeySetIndexIter<K> {
+        KeySetIndexIter {
+            base_iter: self.base.iter_rev_from(&(), from),
+        }
+    }
 }
 
 impl<'a, K> KeySetIndex<&'a mut Fork, K>
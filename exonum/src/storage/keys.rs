@@ -88,6 +88,15 @@ pub trait StorageKey: ToOwned {
     fn read(buffer: &[u8]) -> Self::Owned;
 }
 
+/// A `StorageKey` with the serialized size that does not depend on the key value.
+///
+/// All components of a tuple key except for the last one must implement this trait,
+/// so that the components can be separated when the key is deserialized.
+pub trait FixedSizeKey: StorageKey {
+    /// Size of the serialized key in bytes.
+    const SIZE: usize;
+}
+
 /// No-op implementation.
 impl StorageKey for () {
     fn size(&self) -> usize {
@@ -103,6 +112,10 @@ impl StorageKey for () {
     }
 }
 
+impl FixedSizeKey for () {
+    const SIZE: usize = 0;
+}
+
 impl StorageKey for u8 {
     fn size(&self) -> usize {
         1
@@ -117,6 +130,10 @@ impl StorageKey for u8 {
     }
 }
 
+impl FixedSizeKey for u8 {
+    const SIZE: usize = 1;
+}
+
 /// Uses encoding with the values mapped to `u8`
 /// by adding the corresponding constant (`128`) to the value.
 impl StorageKey for i8 {
@@ -133,6 +150,10 @@ impl StorageKey for i8 {
     }
 }
 
+impl FixedSizeKey for i8 {
+    const SIZE: usize = 1;
+}
+
 // spell-checker:ignore utype, itype, vals, ints
 
 macro_rules! storage_key_for_ints {
@@ -168,6 +189,14 @@ macro_rules! storage_key_for_ints {
                     as $itype
             }
         }
+
+        impl FixedSizeKey for $utype {
+            const SIZE: usize = $size;
+        }
+
+        impl FixedSizeKey for $itype {
+            const SIZE: usize = $size;
+        }
     };
 }
 
@@ -190,6 +219,10 @@ macro_rules! storage_key_for_crypto_types {
                 $type::from_slice(buffer).unwrap()
             }
         }
+
+        impl FixedSizeKey for $type {
+            const SIZE: usize = $size;
+        }
     };
 }
 
@@ -277,6 +310,10 @@ impl StorageKey for DateTime<Utc> {
     }
 }
 
+impl FixedSizeKey for DateTime<Utc> {
+    const SIZE: usize = 12;
+}
+
 impl StorageKey for Uuid {
     fn size(&self) -> usize {
         16
@@ -291,6 +328,10 @@ impl StorageKey for Uuid {
     }
 }
 
+impl FixedSizeKey for Uuid {
+    const SIZE: usize = 16;
+}
+
 impl StorageKey for Decimal {
     fn size(&self) -> usize {
         16
@@ -307,6 +348,61 @@ impl StorageKey for Decimal {
     }
 }
 
+impl FixedSizeKey for Decimal {
+    const SIZE: usize = 16;
+}
+
+// spell-checker:ignore vars
+
+macro_rules! storage_key_for_tuples {
+    ($($prefix:ident $prefix_var:ident),+ => $last:ident $last_var:ident) => {
+        /// Serializes the components one after another, so the keys are ordered
+        /// lexicographically by their components. All components except for the last one
+        /// must have a fixed size.
+        impl<$($prefix,)+ $last> StorageKey for ($($prefix,)+ $last)
+        where
+            $($prefix: FixedSizeKey + Clone + ToOwned<Owned = $prefix>,)+
+            $last: StorageKey + Clone + ToOwned<Owned = $last>,
+        {
+            fn size(&self) -> usize {
+                let ($(ref $prefix_var,)+ ref $last_var) = *self;
+                $($prefix_var.size() +)+ $last_var.size()
+            }
+
+            fn write(&self, buffer: &mut [u8]) {
+                let ($(ref $prefix_var,)+ ref $last_var) = *self;
+                let mut offset = 0;
+                $(
+                    $prefix_var.write(&mut buffer[offset..offset + $prefix::SIZE]);
+                    offset += $prefix::SIZE;
+                )+
+                $last_var.write(&mut buffer[offset..]);
+            }
+
+            fn read(buffer: &[u8]) -> Self::Owned {
+                let mut offset = 0;
+                $(
+                    let $prefix_var = $prefix::read(&buffer[offset..offset + $prefix::SIZE]);
+                    offset += $prefix::SIZE;
+                )+
+                ($($prefix_var,)+ $last::read(&buffer[offset..]))
+            }
+        }
+
+        impl<$($prefix,)+ $last> FixedSizeKey for ($($prefix,)+ $last)
+        where
+            $($prefix: FixedSizeKey + Clone + ToOwned<Owned = $prefix>,)+
+            $last: FixedSizeKey + Clone + ToOwned<Owned = $last>,
+        {
+            const SIZE: usize = $($prefix::SIZE +)+ $last::SIZE;
+        }
+    };
+}
+
+storage_key_for_tuples!{A a => B b}
+storage_key_for_tuples!{A a, B b => C c}
+storage_key_for_tuples!{A a, B b, C c => D d}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -627,6 +723,77 @@ mod tests {
         assert_round_trip_eq(&decimals);
     }
 
+    #[test]
+    fn tuple_round_trip() {
+        let pub_key = PublicKey::from_hex(
+            "1e38d80b8a9786648a471b11a9624a9519215743df7321938d70bac73dae3b84",
+        ).unwrap();
+
+        assert_round_trip_eq(&[(1_u8, -2_i64), (0, 0)]);
+        assert_round_trip_eq(&[(pub_key, 5_u64, "key".to_owned())]);
+        assert_round_trip_eq(&[(Uuid::nil(), -1_i16, 7_u32, vec![1_u8, 2, 3])]);
+        assert_round_trip_eq(&[((1_u16, 2_u8), String::new())]);
+        assert_eq!(<(u8, u64, (u32, i16)) as FixedSizeKey>::SIZE, 15);
+    }
+
+    #[test]
+    fn tuple_ordering() {
+        use rand::{thread_rng, Rng};
+
+        let mut rng = thread_rng();
+        let mut keys: Vec<(u8, i32, u16)> = rng.gen_iter().take(FUZZ_SAMPLES).collect();
+        keys.sort();
+        keys.dedup();
+
+        let (mut x_buffer, mut y_buffer) = ([0_u8; 7], [0_u8; 7]);
+        for w in keys.windows(2) {
+            w[0].write(&mut x_buffer);
+            w[1].write(&mut y_buffer);
+            assert!(x_buffer < y_buffer);
+        }
+
+        let mut keys = vec![(1_u32, "b".to_owned()), (0, "c".to_owned()), (1, "a".to_owned())];
+        let mut buffers: Vec<Vec<u8>> = keys.iter()
+            .map(|key| {
+                let mut buffer = get_buffer(key);
+                key.write(&mut buffer);
+                buffer
+            })
+            .collect();
+        keys.sort();
+        buffers.sort();
+        let sorted_keys: Vec<_> = buffers
+            .iter()
+            .map(|buffer| <(u32, String)>::read(buffer))
+            .collect();
+        assert_eq!(sorted_keys, keys);
+    }
+
+    #[test]
+    fn tuple_key_in_index() {
+        use storage::{Database, MapIndex, MemoryDB};
+
+        let db: Box<dyn Database> = Box::new(MemoryDB::new());
+        let mut fork = db.fork();
+        let mut index: MapIndex<_, (u64, i32), u8> = MapIndex::new("test_index", &mut fork);
+        index.put(&(2, -1), 1);
+        index.put(&(1, 5), 2);
+        index.put(&(2, 3), 3);
+        index.put(&(1, -8), 4);
+
+        assert_eq!(index.get(&(2, 3)), Some(3));
+        assert_eq!(index.values().collect::<Vec<_>>(), vec![4, 2, 1, 3]);
+        assert_eq!(
+            index.iter_from(&(1, 0)).collect::<Vec<_>>(),
+            vec![((1, 5), 2), ((2, -1), 1), ((2, 3), 3)]
+        );
+        assert_eq!(
+            index.iter_prefix(&1_u64).collect::<Vec<_>>(),
+            vec![((1, -8), 4), ((1, 5), 2)]
+        );
+        assert_eq!(index.iter_prefix(&3_u64).count(), 0);
+    }
+
     fn assert_round_trip_eq<T>(values: &[T])
     where
         T: StorageKey + PartialEq<<T as ToOwned>::Owned> + Debug,
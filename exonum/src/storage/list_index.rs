@@ -42,11 +42,13 @@ pub struct ListIndex<T, V> {
 
 /// Returns an iterator over the items of a `ListIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`ListIndex`]. See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`] or
+/// [`iter_rev_from`] method on [`ListIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.ListIndex.html#method.iter
 /// [`iter_from`]: struct.ListIndex.html#method.iter_from
+/// [`iter_rev`]: struct.ListIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.ListIndex.html#method.iter_rev_from
 /// [`ListIndex`]: struct.ListIndex.html
 #[derive(Debug)]
 pub struct ListIndexIter<'a, V> {
@@ -258,6 +260,53 @@ where
             base_iter: self.base.iter_from(&(), &from),
         }
     }
+
+    /// Returns an iterator over the list in reverse order. The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    ///
+    /// let values: Vec<_> = index.iter_rev().collect();
+    /// assert_eq!(values, vec![5, 4, 3, 2, 1]);
+    /// ```
+    pub fn iter_rev(&self) -> ListIndexIter<V> {
+        ListIndexIter {
+            base_iter: self.base.iter_rev_range(&(), None::<&u64>, &0_u64),
+        }
+    }
+
+    /// Returns an iterator over the list in reverse order starting from the specified position
+    /// (inclusive). The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    ///
+    /// let values: Vec<_> = index.iter_rev_from(3).collect();
+    /// assert_eq!(values, vec![4, 3, 2, 1]);
+    /// ```
+    pub fn iter_rev_from(&self, from: u64) -> ListIndexIter<V> {
+        ListIndexIter {
+            base_iter: self.base.iter_rev_range(&(), Some(&from), &0_u64),
+        }
+    }
 }
 
 impl<'a, V> ListIndex<&'a mut Fork, V>
@@ -523,6 +572,14 @@ mod tests {
             list_index.iter_from(3).collect::<Vec<u8>>(),
             Vec::<u8>::new()
         );
+
+        assert_eq!(list_index.iter_rev().collect::<Vec<u8>>(), vec![3, 2, 1]);
+        assert_eq!(
+            list_index.iter_rev_from(5).collect::<Vec<u8>>(),
+            vec![3, 2, 1]
+        );
+        assert_eq!(list_index.iter_rev_from(1).collect::<Vec<u8>>(), vec![2, 1]);
+        assert_eq!(list_index.iter_rev_from(0).collect::<Vec<u8>>(), vec![1]);
     }
 
     mod memorydb_tests {
@@ -577,6 +634,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rocksdb")]
     mod rocksdb_tests {
         use std::path::Path;
         use storage::{Database, DbOptions, ListIndex, RocksDB};
@@ -0,0 +1,836 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of `LogDB` database.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::{
+    collections::{BTreeMap, HashMap}, fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write}, ops::Bound, path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use super::{db::Change, Database, DbOptions, Iter, Iterator, Patch, Result, Snapshot, TableStats};
+use crypto::{self, Hash, HASH_SIZE};
+
+// Names of the log file and the temporary file used during compaction.
+const LOG_FILE_NAME: &str = "data.log";
+const COMPACTION_FILE_NAME: &str = "data.log.compact";
+
+// Header of the log file: magic bytes followed by the format version.
+const LOG_MAGIC: &[u8] = b"EXONUM-LOGDB";
+const LOG_FORMAT_VERSION: u32 = 2;
+const LOG_HEADER_SIZE: u64 = 16;
+
+// Size of a record header: the length of the payload, the checksum of the length
+// and the hash of the payload.
+const RECORD_HEADER_SIZE: usize = 4 + LENGTH_CHECKSUM_SIZE + HASH_SIZE;
+const LENGTH_CHECKSUM_SIZE: usize = 4;
+
+// Tags of the changes in a record payload.
+const DELETE_TAG: u8 = 0;
+const PUT_TAG: u8 = 1;
+
+// The log is compacted when it is larger than this size and the live data takes less
+// than a half of it.
+const COMPACTION_MIN_SIZE: u64 = 64 * 1024 * 1024;
+// Approximate maximum payload size of a record written during compaction.
+const COMPACTION_RECORD_SIZE: usize = 4 * 1024 * 1024;
+
+/// Database implementation that stores data in an append-only log file.
+///
+/// Each merged patch is appended to the log as a single record protected by a hash,
+/// while an in-memory index maps the keys of each column family to the positions of
+/// their values in the log. Values are read from the log on demand. `LogDB` does not have
+/// native dependencies, which makes it suitable for embedded deployments.
+///
+/// When the database is opened, the index is rebuilt by reading the log. The last record
+/// of the log is discarded if it has not been completely written, e.g., because the process
+/// crashed during a merge, so that either all changes of a patch are applied or none of them.
+/// The length of each record is protected by a checksum, so a record is taken for
+/// an incomplete one only if it actually ends beyond the end of the log. A corrupted record
+/// followed by other records is reported as an error, and the log is left intact. Changes merged with [`merge`] may be lost if the machine crashes before
+/// the operating system writes them to disk; [`merge_sync`] waits until the record is written.
+///
+/// The index is not copied for snapshots. Instead, each merge creates a new version of
+/// the index, and a snapshot sees the values of the version it has been created at.
+/// The previous values of a key are kept while there are snapshots that can see them.
+///
+/// Overwritten and removed values remain in the log until it is compacted. The log is
+/// compacted automatically when it becomes larger than 64 MB and the live data takes less
+/// than a half of its size; compaction can also be started manually with [`compact`].
+///
+/// [`merge`]: ../trait.Database.html#tymethod.merge
+/// [`merge_sync`]: ../trait.Database.html#tymethod.merge_sync
+/// [`compact`]: #method.compact
+pub struct LogDB {
+    path: PathBuf,
+    state: RwLock<LogState>,
+}
+
+/// A snapshot of a `LogDB`.
+pub struct LogDBSnapshot {
+    index: Arc<VersionedIndex>,
+    version: u64,
+    file: Arc<Mutex<File>>,
+}
+
+/// An iterator over the entries of a `LogDB`.
+///
+/// The iterator does not copy the index. Instead, each step looks up the entry following
+/// the previously returned key, so the index is locked only for the time of the lookup.
+struct LogDBIter<'a> {
+    snapshot: &'a LogDBSnapshot,
+    name: String,
+    reverse: bool,
+    // Bound of the keys that have not been visited yet; `None` if the iteration is over.
+    bound: Option<Bound<Vec<u8>>>,
+    // The entry found by `peek`, which is returned by the next call to `next`.
+    peeked: Option<Option<(Vec<u8>, Vec<u8>)>>,
+    // The entry returned by the latest call to `next`.
+    current: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+// Values of a key from the oldest version to the latest one; `None` marks a removed key.
+type Versions = Vec<(u64, Option<ValueRef>)>;
+
+type Index = HashMap<String, BTreeMap<Vec<u8>, Versions>>;
+
+// The index shared by the database and its snapshots.
+struct VersionedIndex {
+    tables: RwLock<Index>,
+    // Numbers of live snapshots by their versions.
+    snapshots: Mutex<BTreeMap<u64, usize>>,
+}
+
+// Position of a value in the log.
+#[derive(Debug, Clone, Copy)]
+struct ValueRef {
+    offset: u64,
+    len: u32,
+}
+
+struct LogState {
+    index: Arc<VersionedIndex>,
+    // Version of the index created by the latest merge.
+    version: u64,
+    // Keys that have several versions, which are removed once no snapshot can see them.
+    outdated: Vec<(String, Vec<u8>)>,
+    file: Arc<Mutex<File>>,
+    // Length of the valid part of the log.
+    len: u64,
+    // Total size of the changes that define the current data, i.e., the size of
+    // the log after compaction.
+    live_size: u64,
+}
+
+impl LogDB {
+    /// Opens a database stored in the specified directory with the specified options.
+    ///
+    /// If the database does not exist at the indicated path and the option
+    /// `create_if_missing` is switched on in `DbOptions`, a new database will
+    /// be created at the indicated path. Other options of `DbOptions` do not apply to
+    /// this database.
+    pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let log_path = path.join(LOG_FILE_NAME);
+        if !log_path.exists() {
+            if !options.create_if_missing {
+                return Err(super::Error::new(format!(
+                    "Database does not exist at {}",
+                    path.display()
+                )));
+            }
+            fs::create_dir_all(&path)?;
+            let mut file = File::create(&log_path)?;
+            write_log_header(&mut file)?;
+            file.sync_all()?;
+            sync_dir(&path);
+        }
+        // Compaction could be interrupted before replacing the log.
+        let compaction_path = path.join(COMPACTION_FILE_NAME);
+        if compaction_path.exists() {
+            fs::remove_file(&compaction_path)?;
+        }
+
+        let file = OpenOptions::new().read(true).write(true).open(&log_path)?;
+        let file_len = file.metadata()?.len();
+        let (index, len, live_size) = read_log(&file, file_len)?;
+        if file_len > len {
+            warn!(
+                "Discarding an incomplete record at the end of the log {}",
+                log_path.display()
+            );
+            file.set_len(len)?;
+            file.sync_all()?;
+        }
+
+        Ok(Self {
+            path,
+            state: RwLock::new(LogState {
+                index: Arc::new(VersionedIndex::new(index)),
+                version: 0,
+                outdated: Vec::new(),
+                file: Arc::new(Mutex::new(file)),
+                len,
+                live_size,
+            }),
+        })
+    }
+
+    /// Rewrites the log so that it contains only the current values, reclaiming the space
+    /// taken by overwritten and removed values. Existing snapshots remain valid.
+    pub fn compact(&self) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        self.compact_log(&mut state)
+    }
+
+    fn do_merge(&self, patch: Patch, sync: bool) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+
+        let mut payload = Vec::new();
+        let mut changes = Vec::new();
+        for (name, table_changes) in patch {
+            for (key, change) in table_changes {
+                let value_ref = write_change(&mut payload, &name, &key, &change);
+                changes.push((name.clone(), key, value_ref));
+            }
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let record_offset = state.len;
+        let record = make_record(&payload);
+        {
+            let mut file = state.file.lock().unwrap();
+            let result = append_record(&mut file, record_offset, &record, sync);
+            if let Err(e) = result {
+                // Discard a partially written record.
+                let _ = file.set_len(record_offset);
+                return Err(e.into());
+            }
+        }
+        state.len += record.len() as u64;
+
+        let value_offset = record_offset + RECORD_HEADER_SIZE as u64;
+        state.version += 1;
+        let version = state.version;
+        let oldest_version = state.index.oldest_snapshot().unwrap_or(version);
+        let mut live_size = state.live_size;
+        let mut outdated = Vec::new();
+        {
+            let index = Arc::clone(&state.index);
+            let mut tables = index.tables.write().unwrap();
+            for (name, key, value_ref) in changes {
+                let value_ref = value_ref.map(|value_ref| ValueRef {
+                    offset: value_offset + value_ref.offset,
+                    len: value_ref.len,
+                });
+                if let Some(value_ref) = value_ref {
+                    live_size += change_size(&name, &key, Some(value_ref.len));
+                }
+                let table = tables.entry(name.clone()).or_insert_with(BTreeMap::new);
+                let (previous, tracked) = {
+                    let versions = table.entry(key.clone()).or_insert_with(Vec::new);
+                    let previous = latest_value(versions);
+                    let tracked = versions.len() > 1;
+                    versions.push((version, value_ref));
+                    (previous, tracked)
+                };
+                if let Some(previous) = previous {
+                    live_size -= change_size(&name, &key, Some(previous.len));
+                }
+                // Keys with several versions are already in the list of outdated keys.
+                if remove_outdated_versions(table, &key, oldest_version) && !tracked {
+                    outdated.push((name, key));
+                }
+            }
+
+            // Versions of the keys that have not been changed by this merge are removed
+            // once the snapshots that could see them are dropped.
+            state
+                .outdated
+                .retain(|&(ref name, ref key)| match tables.get_mut(name) {
+                    Some(table) => remove_outdated_versions(table, key, oldest_version),
+                    None => false,
+                });
+            state.outdated.extend(outdated);
+        }
+        state.live_size = live_size;
+
+        if state.len > COMPACTION_MIN_SIZE && state.len > 2 * state.live_size {
+            self.compact_log(&mut state)?;
+        }
+        Ok(())
+    }
+
+    fn compact_log(&self, state: &mut LogState) -> Result<()> {
+        let compaction_path = self.path.join(COMPACTION_FILE_NAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&compaction_path)?;
+        write_log_header(&mut file)?;
+
+        let old_index = Arc::clone(&state.index);
+        let tables = old_index.tables.read().unwrap();
+        let mut index = Index::new();
+        let mut len = LOG_HEADER_SIZE;
+        let mut live_size = 0;
+        let mut payload = Vec::new();
+        let mut refs = Vec::new();
+        {
+            let mut names: Vec<_> = tables.keys().collect();
+            names.sort();
+            let mut source = state.file.lock().unwrap();
+            for name in names {
+                for (key, versions) in &tables[name] {
+                    let value_ref = match latest_value(versions) {
+                        Some(value_ref) => value_ref,
+                        None => continue,
+                    };
+                    let value = read_value(&mut source, value_ref)?;
+                    let change = Change::Put(value);
+                    let value_ref = write_change(&mut payload, name, key, &change).unwrap();
+                    refs.push((name, key, value_ref));
+                    if payload.len() >= COMPACTION_RECORD_SIZE {
+                        live_size += payload.len() as u64;
+                        len = write_compacted(&mut file, len, &payload, &mut refs, &mut index)?;
+                        payload.clear();
+                    }
+                }
+            }
+        }
+        if !payload.is_empty() {
+            live_size += payload.len() as u64;
+            len = write_compacted(&mut file, len, &payload, &mut refs, &mut index)?;
+        }
+        file.sync_all()?;
+        fs::rename(&compaction_path, self.path.join(LOG_FILE_NAME))?;
+        sync_dir(&self.path);
+
+        info!(
+            "Compacted the log {} from {} to {} bytes",
+            self.path.display(),
+            state.len,
+            len
+        );
+        // Snapshots keep the previous index and the handle of the previous log,
+        // which remains readable.
+        state.index = Arc::new(VersionedIndex::new(index));
+        state.outdated.clear();
+        state.file = Arc::new(Mutex::new(file));
+        state.len = len;
+        state.live_size = live_size;
+        Ok(())
+    }
+}
+
+impl Database for LogDB {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        let state = self.state.read().unwrap();
+        state.index.acquire(state.version);
+        Box::new(LogDBSnapshot {
+            index: Arc::clone(&state.index),
+            version: state.version,
+            file: Arc::clone(&state.file),
+        })
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        self.do_merge(patch, false)
+    }
+
+    fn merge_sync(&self, patch: Patch) -> Result<()> {
+        self.do_merge(patch, true)
+    }
+
+    /// Copies the log into the given directory.
+    fn create_checkpoint(&self, path: &Path) -> Result<Box<dyn Snapshot>> {
+        if path.exists() {
+            return Err(super::Error::new(format!(
+                "Checkpoint directory {} already exists",
+                path.display()
+            )));
+        }
+        {
+            // Merges are blocked while the log is being copied.
+            let state = self.state.write().unwrap();
+            let mut source = state.file.lock().unwrap();
+            fs::create_dir_all(path)?;
+            let mut target = File::create(path.join(LOG_FILE_NAME))?;
+            source.seek(SeekFrom::Start(0))?;
+            io::copy(&mut (&mut *source).take(state.len), &mut target)?;
+            target.sync_all()?;
+        }
+        let options = DbOptions {
+            create_if_missing: false,
+            ..DbOptions::default()
+        };
+        Ok(Self::open(path, &options)?.snapshot())
+    }
+
+    /// Returns exact figures computed from the in-memory index without reading the log.
+    fn table_stats(&self, name: &str) -> TableStats {
+        let mut stats = TableStats::default();
+        let state = self.state.read().unwrap();
+        let tables = state.index.tables.read().unwrap();
+        if let Some(table) = tables.get(name) {
+            for (key, versions) in table {
+                if let Some(value_ref) = latest_value(versions) {
+                    stats.key_count += 1;
+                    stats.size += (key.len() + value_ref.len as usize) as u64;
+                }
+            }
+        }
+        stats
+    }
+}
+
+impl LogDBSnapshot {
+    fn value_ref(&self, name: &str, key: &[u8]) -> Option<ValueRef> {
+        let tables = self.index.tables.read().unwrap();
+        visible_value(tables.get(name)?.get(key)?, self.version)
+    }
+
+    fn make_iter(&self, name: &str, reverse: bool, bound: Bound<Vec<u8>>) -> Iter {
+        Box::new(LogDBIter {
+            snapshot: self,
+            name: name.to_owned(),
+            reverse,
+            bound: Some(bound),
+            peeked: None,
+            current: None,
+        })
+    }
+}
+
+impl Snapshot for LogDBSnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let value_ref = self.value_ref(name, key)?;
+        let mut file = self.file.lock().unwrap();
+        match read_value(&mut file, value_ref) {
+            Ok(value) => Some(value),
+            Err(e) => panic!(e),
+        }
+    }
+
+    fn contains(&self, name: &str, key: &[u8]) -> bool {
+        self.value_ref(name, key).is_some()
+    }
+
+    fn iter(&self, name: &str, from: &[u8]) -> Iter {
+        self.make_iter(name, false, Bound::Included(from.to_vec()))
+    }
+
+    fn iter_rev(&self, name: &str, to: Option<&[u8]>) -> Iter {
+        let bound = to.map_or(Bound::Unbounded, |to| Bound::Excluded(to.to_vec()));
+        self.make_iter(name, true, bound)
+    }
+}
+
+impl Drop for LogDBSnapshot {
+    fn drop(&mut self) {
+        self.index.release(self.version);
+    }
+}
+
+impl VersionedIndex {
+    fn new(tables: Index) -> Self {
+        Self {
+            tables: RwLock::new(tables),
+            snapshots: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    // Registers a snapshot of the given version.
+    fn acquire(&self, version: u64) {
+        *self.snapshots.lock().unwrap().entry(version).or_insert(0) += 1;
+    }
+
+    // Unregisters a dropped snapshot of the given version.
+    fn release(&self, version: u64) {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        let remove = match snapshots.get_mut(&version) {
+            Some(count) => {
+                *count -= 1;
+                *count == 0
+            }
+            None => false,
+        };
+        if remove {
+            snapshots.remove(&version);
+        }
+    }
+
+    // Returns the version of the oldest live snapshot.
+    fn oldest_snapshot(&self) -> Option<u64> {
+        self.snapshots.lock().unwrap().keys().next().cloned()
+    }
+}
+
+impl<'a> LogDBIter<'a> {
+    // Finds the next entry visible to the snapshot and reads its value from the log.
+    fn read_next(&mut self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let (key, value_ref) = {
+            let bound = self.bound.take()?;
+            let bound_ref = match bound {
+                Bound::Included(ref key) => Bound::Included(key.as_slice()),
+                Bound::Excluded(ref key) => Bound::Excluded(key.as_slice()),
+                Bound::Unbounded => Bound::Unbounded,
+            };
+            let tables = self.snapshot.index.tables.read().unwrap();
+            let table = tables.get(&self.name)?;
+            let version = self.snapshot.version;
+            let visible = |(key, versions): (&Vec<u8>, &Versions)| {
+                visible_value(versions, version).map(|value_ref| (key.clone(), value_ref))
+            };
+            if self.reverse {
+                table
+                    .range::<[u8], _>((Bound::Unbounded, bound_ref))
+                    .rev()
+                    .filter_map(visible)
+                    .next()?
+            } else {
+                table
+                    .range::<[u8], _>((bound_ref, Bound::Unbounded))
+                    .filter_map(visible)
+                    .next()?
+            }
+        };
+        self.bound = Some(Bound::Excluded(key.clone()));
+
+        let mut file = self.snapshot.file.lock().unwrap();
+        match read_value(&mut file, value_ref) {
+            Ok(value) => Some((key, value)),
+            Err(e) => panic!(e),
+        }
+    }
+}
+
+impl<'a> Iterator for LogDBIter<'a> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        self.current = match self.peeked.take() {
+            Some(entry) => entry,
+            None => self.read_next(),
+        };
+        self.current
+            .as_ref()
+            .map(|&(ref key, ref value)| (key.as_slice(), value.as_slice()))
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_next());
+        }
+        self.peeked
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .map(|&(ref key, ref value)| (key.as_slice(), value.as_slice()))
+    }
+}
+
+impl From<LogDB> for Arc<dyn Database> {
+    fn from(db: LogDB) -> Self {
+        Self::from(Box::new(db) as Box<dyn Database>)
+    }
+}
+
+impl ::std::fmt::Debug for LogDB {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "LogDB({})", self.path.display())
+    }
+}
+
+impl ::std::fmt::Debug for LogDBSnapshot {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "LogDBSnapshot(..)")
+    }
+}
+
+fn write_log_header(file: &mut File) -> io::Result<()> {
+    let mut header = [0; LOG_HEADER_SIZE as usize];
+    header[..LOG_MAGIC.len()].copy_from_slice(LOG_MAGIC);
+    LittleEndian::write_u32(&mut header[LOG_MAGIC.len()..], LOG_FORMAT_VERSION);
+    file.write_all(&header)
+}
+
+// Flushes the directory entries, so that a created or renamed file survives a crash.
+// Directories cannot be synchronized on some platforms, so errors are ignored.
+fn sync_dir(path: &Path) {
+    if let Ok(dir) = File::open(path) {
+        let _ = dir.sync_all();
+    }
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    let mut len = [0; 4];
+    LittleEndian::write_u32(&mut len, bytes.len() as u32);
+    buf.extend_from_slice(&len);
+    buf.extend_from_slice(bytes);
+}
+
+// Writes the change into the record payload. For an insertion, returns the position
+// of the value relative to the payload.
+fn write_change(
+    payload: &mut Vec<u8>,
+    name: &str,
+    key: &[u8],
+    change: &Change,
+) -> Option<ValueRef> {
+    match *change {
+        Change::Put(ref value) => {
+            payload.push(PUT_TAG);
+            write_bytes(payload, name.as_bytes());
+            write_bytes(payload, key);
+            write_bytes(payload, value);
+            Some(ValueRef {
+                offset: (payload.len() - value.len()) as u64,
+                len: value.len() as u32,
+            })
+        }
+        Change::Delete => {
+            payload.push(DELETE_TAG);
+            write_bytes(payload, name.as_bytes());
+            write_bytes(payload, key);
+            None
+        }
+    }
+}
+
+// Returns the size of a change in the record payload.
+fn change_size(name: &str, key: &[u8], value_len: Option<u32>) -> u64 {
+    let size = 1 + 4 + name.len() + 4 + key.len();
+    (size + value_len.map_or(0, |len| 4 + len as usize)) as u64
+}
+
+fn make_record(payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+    let mut len = [0; 4];
+    LittleEndian::write_u32(&mut len, payload.len() as u32);
+    record.extend_from_slice(&len);
+    record.extend_from_slice(&length_checksum(&len));
+    record.extend_from_slice(crypto::hash(payload).as_ref());
+    record.extend_from_slice(payload);
+    record
+}
+
+// Returns the checksum protecting the length of a record payload.
+fn length_checksum(len: &[u8]) -> [u8; LENGTH_CHECKSUM_SIZE] {
+    let mut checksum = [0; LENGTH_CHECKSUM_SIZE];
+    checksum.copy_from_slice(&crypto::hash(len).as_ref()[..LENGTH_CHECKSUM_SIZE]);
+    checksum
+}
+
+fn append_record(file: &mut File, offset: u64, record: &[u8], sync: bool) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(record)?;
+    if sync {
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+// Writes a record with the compacted changes and adds their values to the index.
+// Returns the length of the log after the record.
+fn write_compacted(
+    file: &mut File,
+    offset: u64,
+    payload: &[u8],
+    refs: &mut Vec<(&String, &Vec<u8>, ValueRef)>,
+    index: &mut Index,
+) -> io::Result<u64> {
+    let record = make_record(payload);
+    append_record(file, offset, &record, false)?;
+    let value_offset = offset + RECORD_HEADER_SIZE as u64;
+    for (name, key, value_ref) in refs.drain(..) {
+        let value_ref = ValueRef {
+            offset: value_offset + value_ref.offset,
+            len: value_ref.len,
+        };
+        index
+            .entry(name.clone())
+            .or_insert_with(BTreeMap::new)
+            .insert(key.clone(), vec![(0, Some(value_ref))]);
+    }
+    Ok(offset + record.len() as u64)
+}
+
+// Returns the latest value of a key.
+fn latest_value(versions: &Versions) -> Option<ValueRef> {
+    versions.last().and_then(|&(_, value_ref)| value_ref)
+}
+
+// Returns the value of a key visible to the snapshot of the given version.
+fn visible_value(versions: &Versions, version: u64) -> Option<ValueRef> {
+    versions
+        .iter()
+        .rev()
+        .find(|&&(value_version, _)| value_version <= version)
+        .and_then(|&(_, value_ref)| value_ref)
+}
+
+// Removes the versions of a key that are not visible to the snapshots not older than
+// `oldest_version`, and removes the key if it has been removed in all of them.
+// Returns `true` if the key still has several versions.
+fn remove_outdated_versions(
+    table: &mut BTreeMap<Vec<u8>, Versions>,
+    key: &[u8],
+    oldest_version: u64,
+) -> bool {
+    let (outdated, removed) = match table.get_mut(key) {
+        Some(versions) => {
+            let visible = versions
+                .iter()
+                .rposition(|&(version, _)| version <= oldest_version)
+                .unwrap_or(0);
+            versions.drain(..visible);
+            (
+                versions.len() > 1,
+                versions.len() == 1 && versions[0].1.is_none(),
+            )
+        }
+        None => (false, false),
+    };
+    if removed {
+        table.remove(key);
+    }
+    outdated
+}
+
+fn read_value(file: &mut File, value_ref: ValueRef) -> io::Result<Vec<u8>> {
+    let mut value = vec![0; value_ref.len as usize];
+    file.seek(SeekFrom::Start(value_ref.offset))?;
+    file.read_exact(&mut value)?;
+    Ok(value)
+}
+
+// Reads the log of the given length and builds the index. Returns the index, the length
+// of the valid part of the log and the size of the live data.
+//
+// An incomplete record or a record with a corrupted payload at the end of the log ends
+// its valid part. A record with a corrupted length or a corrupted record followed by
+// other data results in an error.
+fn read_log(file: &File, file_len: u64) -> Result<(Index, u64, u64)> {
+    let mut reader = BufReader::new(file);
+    let mut header = [0; LOG_HEADER_SIZE as usize];
+    reader.read_exact(&mut header)?;
+    if &header[..LOG_MAGIC.len()] != LOG_MAGIC {
+        return Err(super::Error::new("File is not a LogDB log"));
+    }
+    let version = LittleEndian::read_u32(&header[LOG_MAGIC.len()..]);
+    if version != LOG_FORMAT_VERSION {
+        return Err(super::Error::new(format!(
+            "Unsupported log format version {}",
+            version
+        )));
+    }
+
+    let mut index = Index::new();
+    let mut len = LOG_HEADER_SIZE;
+    let mut live_size = 0;
+    loop {
+        let mut record_header = Vec::new();
+        (&mut reader)
+            .take(RECORD_HEADER_SIZE as u64)
+            .read_to_end(&mut record_header)?;
+        if record_header.len() != RECORD_HEADER_SIZE {
+            break;
+        }
+        let (len_bytes, rest) = record_header.split_at(4);
+        let (checksum, payload_hash) = rest.split_at(LENGTH_CHECKSUM_SIZE);
+        // A short payload is taken for an incomplete write only if its length is intact.
+        if checksum != &length_checksum(len_bytes)[..] {
+            return Err(super::Error::new(format!(
+                "Length of the log record at offset {} is corrupted",
+                len
+            )));
+        }
+        let payload_len = LittleEndian::read_u32(len_bytes);
+        let payload_hash = Hash::from_slice(payload_hash).unwrap();
+        let mut payload = Vec::new();
+        (&mut reader)
+            .take(u64::from(payload_len))
+            .read_to_end(&mut payload)?;
+        if payload.len() != payload_len as usize {
+            break;
+        }
+        let value_offset = len + RECORD_HEADER_SIZE as u64;
+        if crypto::hash(&payload) != payload_hash {
+            if value_offset + u64::from(payload_len) == file_len {
+                break;
+            }
+            return Err(super::Error::new(format!(
+                "Log record at offset {} is corrupted",
+                len
+            )));
+        }
+
+        read_payload(&payload, value_offset, &mut index, &mut live_size)?;
+        len = value_offset + u64::from(payload_len);
+    }
+    Ok((index, len, live_size))
+}
+
+// Applies the changes of a record payload to the index.
+fn read_payload(payload: &[u8], offset: u64, index: &mut Index, live_size: &mut u64) -> Result<()> {
+    let corrupted = || super::Error::new("Log record is corrupted");
+    let mut pos = 0;
+    let read_bytes = |pos: &mut usize| -> Result<(usize, usize)> {
+        if payload.len() - *pos < 4 {
+            return Err(corrupted());
+        }
+        let len = LittleEndian::read_u32(&payload[*pos..*pos + 4]) as usize;
+        let start = *pos + 4;
+        if payload.len() - start < len {
+            return Err(corrupted());
+        }
+        *pos = start + len;
+        Ok((start, len))
+    };
+
+    while pos < payload.len() {
+        let tag = payload[pos];
+        pos += 1;
+        let (name_start, name_len) = read_bytes(&mut pos)?;
+        let name = ::std::str::from_utf8(&payload[name_start..name_start + name_len])
+            .map_err(|_| corrupted())?;
+        let (key_start, key_len) = read_bytes(&mut pos)?;
+        let key = &payload[key_start..key_start + key_len];
+
+        let table = index.entry(name.to_owned()).or_insert_with(BTreeMap::new);
+        let previous = match tag {
+            PUT_TAG => {
+                let (value_start, value_len) = read_bytes(&mut pos)?;
+                let value_ref = ValueRef {
+                    offset: offset + value_start as u64,
+                    len: value_len as u32,
+                };
+                *live_size += change_size(name, key, Some(value_ref.len));
+                table.insert(key.to_vec(), vec![(0, Some(value_ref))])
+            }
+            DELETE_TAG => table.remove(key),
+            _ => return Err(corrupted()),
+        };
+        if let Some(previous) = previous.as_ref().and_then(latest_value) {
+            *live_size -= change_size(name, key, Some(previous.len));
+        }
+    }
+    Ok(())
+}
@@ -21,8 +21,8 @@
 use std::{borrow::Borrow, marker::PhantomData};
 
 use super::{
-    base_index::{BaseIndex, BaseIndexIter}, indexes_metadata::IndexType, Fork, Snapshot,
-    StorageKey, StorageValue,
+    base_index::{BaseIndex, BaseIndexIter}, indexed_map::{PrimaryIndex, PrimaryIndexMut},
+    indexes_metadata::IndexType, Fork, Snapshot, StorageKey, StorageValue,
 };
 
 /// A map of keys and values. Access to the elements of this map is obtained using the keys.
@@ -41,11 +41,14 @@ pub struct MapIndex<T, K, V> {
 
 /// Returns an iterator over the entries of a `MapIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`MapIndex`]. See its documentation for additional details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_prefix`], [`iter_rev`] or
+/// [`iter_rev_from`] method on [`MapIndex`]. See its documentation for additional details.
 ///
 /// [`iter`]: struct.MapIndex.html#method.iter
 /// [`iter_from`]: struct.MapIndex.html#method.iter_from
+/// [`iter_prefix`]: struct.MapIndex.html#method.iter_prefix
+/// [`iter_rev`]: struct.MapIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.MapIndex.html#method.iter_rev_from
 /// [`MapIndex`]: struct.MapIndex.html
 #[derive(Debug)]
 pub struct MapIndexIter<'a, K, V> {
@@ -54,11 +57,13 @@ pub struct MapIndexIter<'a, K, V> {
 
 /// Returns an iterator over the keys of a `MapIndex`.
 ///
-/// This struct is created by the [`keys`] or
-/// [`keys_from`] method on [`MapIndex`]. See its documentation for additional details.
+/// This struct is created by the [`keys`], [`keys_from`], [`keys_rev`] or
+/// [`keys_rev_from`] method on [`MapIndex`]. See its documentation for additional details.
 ///
 /// [`keys`]: struct.MapIndex.html#method.keys
 /// [`keys_from`]: struct.MapIndex.html#method.keys_from
+/// [`keys_rev`]: struct.MapIndex.html#method.keys_rev
+/// [`keys_rev_from`]: struct.MapIndex.html#method.keys_rev_from
 /// [`MapIndex`]: struct.MapIndex.html
 #[derive(Debug)]
 pub struct MapIndexKeys<'a, K> {
@@ -67,11 +72,13 @@ pub struct MapIndexKeys<'a, K> {
 
 /// Returns an iterator over the values of a `MapIndex`.
 ///
-/// This struct is created by the [`values`] or
-/// [`values_from`] method on [`MapIndex`]. See its documentation for additional details.
+/// This struct is created by the [`values`], [`values_from`], [`values_rev`] or
+/// [`values_rev_from`] method on [`MapIndex`]. See its documentation for additional details.
 ///
 /// [`values`]: struct.MapIndex.html#method.values
 /// [`values_from`]: struct.MapIndex.html#method.values_from
+/// [`values_rev`]: struct.MapIndex.html#method.values_rev
+/// [`values_rev_from`]: struct.MapIndex.html#method.values_rev_from
 /// [`MapIndex`]: struct.MapIndex.html
 #[derive(Debug)]
 pub struct MapIndexValues<'a, V> {
@@ -340,6 +347,200 @@ where
             base_iter: self.base.iter_from(&(), from),
         }
     }
+
+    /// Returns an iterator over the entries of the map in ascending order, which keys start
+    /// with the specified prefix. The iterator element type is (K, V).
+    ///
+    /// The prefix is compared with the serialized keys, so it is mostly useful for composite
+    /// keys, such as tuples: the prefix made of the first components of a tuple selects all
+    /// entries with these components.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index: MapIndex<_, (u8, u64), u8> = MapIndex::new(name, &mut fork);
+    /// index.put(&(1, 2), 10);
+    /// index.put(&(2, 1), 20);
+    /// index.put(&(1, 3), 30);
+    ///
+    /// let entries: Vec<_> = index.iter_prefix(&1_u8).collect();
+    /// assert_eq!(entries, vec![((1, 2), 10), ((1, 3), 30)]);
+    /// ```
+    pub fn iter_prefix<P>(&self, prefix: &P) -> MapIndexIter<K, V>
+    where
+        P: StorageKey + ?Sized,
+    {
+        MapIndexIter {
+            base_iter: self.base.iter(prefix),
+        }
+    }
+
+    /// Returns an iterator over the entries of the map in descending order. The iterator element
+    /// type is (K, V).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// index.put(&1, 10);
+    /// index.put(&2, 20);
+    /// index.put(&3, 30);
+    ///
+    /// let entries: Vec<_> = index.iter_rev().collect();
+    /// assert_eq!(entries, vec![(3, 30), (2, 20), (1, 10)]);
+    /// ```
+    pub fn iter_rev(&self) -> MapIndexIter<K, V> {
+        MapIndexIter {
+            base_iter: self.base.iter_rev(&()),
+        }
+    }
+
+    /// Returns an iterator over the keys of a map in descending order. The iterator element
+    /// type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// index.put(&1, 10);
+    /// index.put(&2, 20);
+    /// index.put(&3, 30);
+    ///
+    /// let keys: Vec<_> = index.keys_rev().collect();
+    /// assert_eq!(keys, vec![3, 2, 1]);
+    /// ```
+    pub fn keys_rev(&self) -> MapIndexKeys<K> {
+        MapIndexKeys {
+            base_iter: self.base.iter_rev(&()),
+        }
+    }
+
+    /// Returns an iterator over the values of a map in descending order of keys. The iterator
+    /// element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// index.put(&1, 10);
+    /// index.put(&2, 20);
+    /// index.put(&3, 30);
+    ///
+    /// let values: Vec<_> = index.values_rev().collect();
+    /// assert_eq!(values, vec![30, 20, 10]);
+    /// ```
+    pub fn values_rev(&self) -> MapIndexValues<V> {
+        MapIndexValues {
+            base_iter: self.base.iter_rev(&()),
+        }
+    }
+
+    /// Returns an iterator over the entries of a map in descending order starting from the
+    /// specified key (inclusive). The iterator element type is (K, V).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// index.put(&1, 10);
+    /// index.put(&2, 20);
+    /// index.put(&3, 30);
+    ///
+    /// let entries: Vec<_> = index.iter_rev_from(&2).collect();
+    /// assert_eq!(entries, vec![(2, 20), (1, 10)]);
+    /// ```
+    pub fn iter_rev_from<Q>(&self, from: &Q) -> MapIndexIter<K, V>
+    where
+        K: Borrow<Q>,
+        Q: StorageKey + ?Sized,
+    {
+        MapIndexIter {
+            base_iter: self.base.iter_rev_from(&(), from),
+        }
+    }
+
+    /// Returns an iterator over the keys of a map in descending order starting from the
+    /// specified key (inclusive). The iterator element type is K.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// index.put(&1, 10);
+    /// index.put(&2, 20);
+    /// index.put(&3, 30);
+    ///
+    /// let keys: Vec<_> = index.keys_rev_from(&2).collect();
+    /// assert_eq!(keys, vec![2, 1]);
+    /// ```
+    pub fn keys_rev_from<Q>(&self, from: &Q) -> MapIndexKeys<K>
+    where
+        K: Borrow<Q>,
+        Q: StorageKey + ?Sized,
+    {
+        MapIndexKeys {
+            base_iter: self.base.iter_rev_from(&(), from),
+        }
+    }
+
+    /// Returns an iterator over the values of a map in descending order of keys starting from
+    /// the specified key (inclusive). The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, MapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = MapIndex::new(name, &mut fork);
+    /// index.put(&1, 10);
+    /// index.put(&2, 20);
+    /// index.put(&3, 30);
+    ///
+    /// let values: Vec<_> = index.values_rev_from(&2).collect();
+    /// assert_eq!(values, vec![20, 10]);
+    /// ```
+    pub fn values_rev_from<Q>(&self, from: &Q) -> MapIndexValues<V>
+    where
+        K: Borrow<Q>,
+        Q: StorageKey + ?Sized,
+    {
+        MapIndexValues {
+            base_iter: self.base.iter_rev_from(&(), from),
+        }
+    }
 }
 
 impl<'a, K, V> MapIndex<&'a mut Fork, K, V>
@@ -431,6 +632,46 @@ where
     }
 }
 
+impl<T, K, V> PrimaryIndex for MapIndex<T, K, V>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey + Clone,
+    V: StorageValue,
+{
+    type Key = K;
+    type Value = V;
+
+    fn snapshot(&self) -> &(dyn Snapshot + 'static) {
+        self.base.snapshot()
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+}
+
+impl<'a, K, V> PrimaryIndexMut for MapIndex<&'a mut Fork, K, V>
+where
+    K: StorageKey + Clone,
+    V: StorageValue,
+{
+    fn fork(&mut self) -> &mut Fork {
+        self.base.fork()
+    }
+
+    fn put(&mut self, key: &K, value: V) {
+        self.put(key, value)
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+}
+
 impl<'a, K, V> Iterator for MapIndexIter<'a, K, V>
 where
     K: StorageKey,
@@ -563,6 +804,30 @@ mod tests {
             Vec::<u8>::new()
         );
 
+        assert_eq!(
+            map_index.iter_rev().collect::<Vec<(u8, u8)>>(),
+            vec![(3, 3), (2, 2), (1, 1)]
+        );
+        assert_eq!(
+            map_index.iter_rev_from(&4).collect::<Vec<(u8, u8)>>(),
+            vec![(3, 3), (2, 2), (1, 1)]
+        );
+        assert_eq!(
+            map_index.iter_rev_from(&2).collect::<Vec<(u8, u8)>>(),
+            vec![(2, 2), (1, 1)]
+        );
+        assert_eq!(
+            map_index.iter_rev_from(&0).collect::<Vec<(u8, u8)>>(),
+            Vec::<(u8, u8)>::new()
+        );
+        assert_eq!(map_index.keys_rev().collect::<Vec<u8>>(), vec![3, 2, 1]);
+        assert_eq!(map_index.keys_rev_from(&2).collect::<Vec<u8>>(), vec![2, 1]);
+        assert_eq!(map_index.values_rev().collect::<Vec<u8>>(), vec![3, 2, 1]);
+        assert_eq!(
+            map_index.values_rev_from(&2).collect::<Vec<u8>>(),
+            vec![2, 1]
+        );
+
         map_index.remove(&1u8);
         assert_eq!(
             map_index.iter_from(&0_u8).collect::<Vec<(u8, u8)>>(),
@@ -596,6 +861,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rocksdb")]
     mod rocksdb_tests {
         use std::path::Path;
         use storage::Database;
@@ -14,18 +14,30 @@
 
 //! An implementation of `MemoryDB` database.
 
+use byteorder::{ByteOrder, LittleEndian};
+
 use std::{
-    clone::Clone, collections::{BTreeMap, HashMap}, sync::{Arc, RwLock},
+    clone::Clone, collections::{BTreeMap, HashMap}, fs::{self, File}, io::{Read, Write},
+    path::Path, sync::{Arc, RwLock},
 };
 
-use super::{db::Change, Database, Iter, Iterator, Patch, Result, Snapshot};
+use super::{db::Change, Database, Error, Iter, Iterator, Patch, Result, Snapshot, TableStats};
+use crypto::{self, Hash, HASH_SIZE};
 
 type DB = HashMap<String, BTreeMap<Vec<u8>, Vec<u8>>>;
 
+// Header of a saved database: magic bytes followed by the format version.
+const FILE_MAGIC: &[u8] = b"EXONUM-MEMDB";
+const FILE_FORMAT_VERSION: u32 = 1;
+
 /// Database implementation that stores all the data in RAM.
 ///
 /// This database is only used for testing and experimenting; is not designed to
-/// operate under load in production.
+/// operate under load in production. The contents of the database can be saved to a file
+/// with [`save_to`] and restored with [`load_from`].
+///
+/// [`save_to`]: #method.save_to
+/// [`load_from`]: #method.load_from
 #[derive(Default, Debug)]
 pub struct MemoryDB {
     map: RwLock<DB>,
@@ -44,6 +56,149 @@ impl MemoryDB {
             map: RwLock::new(HashMap::new()),
         }
     }
+
+    /// Saves the contents of the database to the file at the given path.
+    ///
+    /// The file starts with a header containing the format version and ends with
+    /// the hash of the preceding data, which is checked by [`load_from`]. The data is
+    /// written into a temporary file first, which then replaces the target file,
+    /// so a previously saved state is not damaged if saving fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate exonum;
+    /// # extern crate tempdir;
+    /// use exonum::storage::{Database, MemoryDB};
+    /// # use tempdir::TempDir;
+    ///
+    /// # fn main() {
+    /// # let dir = TempDir::new("exonum_memorydb_save").unwrap();
+    /// # let path = dir.path().join("db.bin");
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// fork.put("table", vec![1], vec![2]);
+    /// db.merge(fork.into_patch()).unwrap();
+    ///
+    /// db.save_to(&path).unwrap();
+    /// let loaded = MemoryDB::load_from(&path).unwrap();
+    /// assert_eq!(loaded.snapshot().get("table", &[1]), Some(vec![2]));
+    /// # }
+    /// ```
+    ///
+    /// [`load_from`]: #method.load_from
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut data = Vec::new();
+        data.extend_from_slice(FILE_MAGIC);
+        write_u32(&mut data, FILE_FORMAT_VERSION);
+        {
+            let map = self.map.read().unwrap();
+            let mut names: Vec<_> = map.keys().collect();
+            names.sort();
+            write_u32(&mut data, names.len() as u32);
+            for name in names {
+                let table = &map[name];
+                write_bytes(&mut data, name.as_bytes());
+                write_u32(&mut data, table.len() as u32);
+                for (key, value) in table {
+                    write_bytes(&mut data, key);
+                    write_bytes(&mut data, value);
+                }
+            }
+        }
+        let hash = crypto::hash(&data);
+        data.extend_from_slice(hash.as_ref());
+
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(&data)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Loads a database saved with [`save_to`] from the file at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, has an unsupported format version,
+    /// or is corrupted.
+    ///
+    /// [`save_to`]: #method.save_to
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+
+        let header_len = FILE_MAGIC.len() + 4;
+        if data.len() < header_len + HASH_SIZE || &data[..FILE_MAGIC.len()] != FILE_MAGIC {
+            return Err(Error::new("File is not a saved MemoryDB"));
+        }
+        let version = LittleEndian::read_u32(&data[FILE_MAGIC.len()..header_len]);
+        if version != FILE_FORMAT_VERSION {
+            return Err(Error::new(format!(
+                "Unsupported MemoryDB format version {}",
+                version
+            )));
+        }
+        let (data, hash) = data.split_at(data.len() - HASH_SIZE);
+        if crypto::hash(data) != Hash::from_slice(hash).unwrap() {
+            return Err(Error::new("Saved MemoryDB is corrupted"));
+        }
+
+        let mut reader = &data[header_len..];
+        let mut map = DB::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let name = String::from_utf8(read_bytes(&mut reader)?)
+                .map_err(|_| Error::new("Saved MemoryDB is corrupted"))?;
+            let mut table = BTreeMap::new();
+            for _ in 0..read_u32(&mut reader)? {
+                let key = read_bytes(&mut reader)?;
+                let value = read_bytes(&mut reader)?;
+                table.insert(key, value);
+            }
+            map.insert(name, table);
+        }
+        if !reader.is_empty() {
+            return Err(Error::new("Saved MemoryDB is corrupted"));
+        }
+
+        Ok(Self {
+            map: RwLock::new(map),
+        })
+    }
+}
+
+fn write_u32(data: &mut Vec<u8>, value: u32) {
+    let mut buf = [0; 4];
+    LittleEndian::write_u32(&mut buf, value);
+    data.extend_from_slice(&buf);
+}
+
+fn write_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    write_u32(data, bytes.len() as u32);
+    data.extend_from_slice(bytes);
+}
+
+fn read_u32(reader: &mut &[u8]) -> Result<u32> {
+    if reader.len() < 4 {
+        return Err(Error::new("Saved MemoryDB is corrupted"));
+    }
+    let value = LittleEndian::read_u32(&reader[..4]);
+    *reader = &reader[4..];
+    Ok(value)
+}
+
+fn read_bytes(reader: &mut &[u8]) -> Result<Vec<u8>> {
+    let len = read_u32(reader)? as usize;
+    if reader.len() < len {
+        return Err(Error::new("Saved MemoryDB is corrupted"));
+    }
+    let bytes = reader[..len].to_vec();
+    *reader = &reader[len..];
+    Ok(bytes)
 }
 
 impl Database for MemoryDB {
@@ -77,6 +232,16 @@ impl Database for MemoryDB {
     fn merge_sync(&self, patch: Patch) -> Result<()> {
         self.merge(patch)
     }
+
+    fn table_stats(&self, name: &str) -> TableStats {
+        let mut stats = TableStats::default();
+        if let Some(table) = self.map.read().unwrap().get(name) {
+            for (key, value) in table {
+                stats.add_entry(key, value);
+            }
+        }
+        stats
+    }
 }
 
 impl Snapshot for MemoryDB {
@@ -109,6 +274,21 @@ impl Snapshot for MemoryDB {
 
         Box::new(MemoryDBIter { data, index: 0 })
     }
+
+    fn iter_rev(&self, name: &str, to: Option<&[u8]>) -> Iter {
+        let map_guard = self.map.read().unwrap();
+        let data = match map_guard.get(name) {
+            Some(table) => table
+                .iter()
+                .rev()
+                .skip_while(|&(k, _)| to.map_or(false, |to| k.as_slice() >= to))
+                .map(|(k, v)| (k.to_vec(), v.to_vec()))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Box::new(MemoryDBIter { data, index: 0 })
+    }
 }
 
 impl Iterator for MemoryDBIter {
@@ -164,3 +344,53 @@ fn test_memorydb_snapshot() {
     let snapshot = db.snapshot();
     assert!(snapshot.contains(idx_name, vec![2, 3, 4].as_slice()));
 }
+
+#[test]
+fn test_memorydb_save_load() {
+    use tempdir::TempDir;
+
+    let dir = TempDir::new("exonum_memorydb_save_load").unwrap();
+    let path = dir.path().join("db.bin");
+    let db = MemoryDB::new();
+    {
+        let mut fork = db.fork();
+        fork.put("first", vec![1, 2, 3], vec![123]);
+        fork.put("first", vec![], vec![]);
+        fork.put("second", vec![2], vec![2; 1000]);
+        db.merge(fork.into_patch()).unwrap();
+    }
+    db.save_to(&path).unwrap();
+
+    let loaded = MemoryDB::load_from(&path).unwrap();
+    assert_eq!(*loaded.map.read().unwrap(), *db.map.read().unwrap());
+
+    // A saved file is replaced.
+    MemoryDB::new().save_to(&path).unwrap();
+    let loaded = MemoryDB::load_from(&path).unwrap();
+    assert!(loaded.map.read().unwrap().is_empty());
+}
+
+#[test]
+fn test_memorydb_load_corrupted() {
+    use tempdir::TempDir;
+
+    let dir = TempDir::new("exonum_memorydb_load_corrupted").unwrap();
+    let path = dir.path().join("db.bin");
+    let db = MemoryDB::new();
+    {
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+    }
+    db.save_to(&path).unwrap();
+
+    let mut data = fs::read(&path).unwrap();
+    let len = data.len();
+    data[len / 2] ^= 1;
+    fs::write(&path, &data).unwrap();
+    assert!(MemoryDB::load_from(&path).is_err());
+
+    fs::write(&path, &data[..len - 1]).unwrap();
+    assert!(MemoryDB::load_from(&path).is_err());
+    assert!(MemoryDB::load_from(dir.path().join("absent.bin")).is_err());
+}
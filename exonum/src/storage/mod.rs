@@ -24,7 +24,9 @@
 //! that is, the Exonum process has exclusive access to the DB during blockchain operation.
 //! You can interact with the `Database` from multiple threads by cloning its instance.
 //!
-//! Exonum provides two database types: [`RocksDB`] and [`MemoryDB`].
+//! Exonum provides three database types: [`RocksDB`], [`LogDB`] and [`MemoryDB`].
+//! `RocksDB` is available with the `rocksdb` feature, which is enabled by default;
+//! `LogDB` has no native dependencies and is always available.
 //!
 //! # Snapshot and Fork
 //!
@@ -42,11 +44,16 @@
 //! as a [`Patch`]. A patch can be atomically [`merge`]d into a database. Different threads
 //! may call `merge` concurrently.
 //!
+//! A fork may also record its changes as a new version of the database state with
+//! [`save_version`]. Older states can then be read with the [`snapshot_at`][3] method
+//! of the `Database`, which returns an ordinary read-only `Snapshot`.
+//!
 //! # `StorageKey` and `StorageValue` traits
 //!
 //! If you need to use your own data types as keys or values in the storage, you need to implement
 //! the [`StorageKey`] or [`StorageValue`] traits respectively. These traits have already been
-//! implemented for most standard types.
+//! implemented for most standard types. `StorageKey` is also implemented for tuples of up to
+//! 4 keys, all of which except for the last one must implement [`FixedSizeKey`].
 //!
 //! # Indices
 //!
@@ -58,6 +65,8 @@
 //! On the other hand, multiple indices can be stored in the same column family, provided
 //! that their key spaces do not intersect. Isolation is commonly achieved with the help
 //! of column families; see `new_in_family` constructor in the built-in index types.
+//! Members of such an index family can be enumerated or cleared all at once with
+//! [`IndexFamily`].
 //!
 //! Merkelized indices can generate cryptographic proofs about inclusion
 //! of entries. Having such a proof, an external client may verify locally that the received data
@@ -78,17 +87,24 @@
 //!   proofs of existence and is implemented as a binary Merkle Patricia tree.
 //! - [`KeySetIndex`] and [`ValueSetIndex`] is a set of items, similar to [`BTreeSet`] and
 //!   [`HashSet`].
+//! - [`IndexedMap`] wraps a `MapIndex` or a `ProofMapIndex` and maintains secondary indexes
+//!   of its values, allowing to look up entries by the keys extracted from the values.
 //!
 //! [`Database`]: trait.Database.html
 //! [`RocksDB`]: struct.RocksDB.html
+//! [`LogDB`]: struct.LogDB.html
 //! [`MemoryDB`]: struct.MemoryDB.html
 //! [`Snapshot`]: trait.Snapshot.html
 //! [`Fork`]: struct.Fork.html
 //! [`Patch`]: struct.Patch.html
 //! [1]: trait.Database.html#tymethod.snapshot
 //! [2]: trait.Database.html#method.fork
+//! [3]: trait.Database.html#method.snapshot_at
+//! [`save_version`]: struct.Fork.html#method.save_version
 //! [`merge`]: trait.Database.html#tymethod.merge
 //! [`StorageKey`]: trait.StorageKey.html
+//! [`FixedSizeKey`]: trait.FixedSizeKey.html
+//! [`IndexFamily`]: struct.IndexFamily.html
 //! [`StorageValue`]: trait.StorageValue.html
 //! [`Entry`]: struct.Entry.html
 //! [`ListIndex`]: list_index/struct.ListIndex.html
@@ -98,6 +114,7 @@
 //! [`ProofMapIndex`]: proof_map_index/struct.ProofMapIndex.html
 //! [`KeySetIndex`]: key_set_index/struct.KeySetIndex.html
 //! [`ValueSetIndex`]: value_set_index/struct.ValueSetIndex.html
+//! [`IndexedMap`]: indexed_map/struct.IndexedMap.html
 //! [doc:storage]: https://exonum.com/doc/architecture/storage
 //! [`Option`]: https://doc.rust-lang.org/std/option/enum.Option.html
 //! [`Box`]: https://doc.rust-lang.org/std/boxed/struct.Box.html
@@ -106,36 +123,53 @@
 //! [`BTreeSet`]: https://doc.rust-lang.org/std/collections/struct.BTreeSet.html
 //! [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
 
-pub(crate) use self::indexes_metadata::StorageMetadata;
+pub(crate) use self::db::is_in_namespace;
+pub(crate) use self::indexes_metadata::{
+    indexes, service_data_version, set_service_data_version, table_names, StorageMetadata,
+};
 
 #[doc(no_inline)]
-pub use self::proof_map_index::{HashedKey, MapProof, ProofMapIndex};
+pub use self::proof_map_index::{HashedKey, MapProof, MapRangeProof, ProofMapIndex};
 pub use self::{
+    cache::{CacheStats, CachedDB},
     db::{
         Change, Changes, ChangesIterator, Database, Fork, Iter, Iterator, Patch, PatchIterator,
-        Snapshot,
+        SavepointId, Snapshot,
     },
-    entry::Entry, error::Error, hash::UniqueHash, key_set_index::KeySetIndex, keys::StorageKey,
-    list_index::ListIndex, map_index::MapIndex, memorydb::MemoryDB, options::DbOptions,
-    proof_list_index::{ListProof, ProofListIndex}, rocksdb::RocksDB,
-    sparse_list_index::SparseListIndex, value_set_index::ValueSetIndex, values::StorageValue,
+    entry::Entry, error::Error, hash::UniqueHash, index_family::IndexFamily,
+    indexed_map::IndexedMap, indexes_metadata::IndexType, key_set_index::KeySetIndex,
+    keys::{FixedSizeKey, StorageKey}, list_index::ListIndex, logdb::LogDB, map_index::MapIndex,
+    memorydb::MemoryDB,
+    options::{open_database, CompressionType, DatabaseBackend, DbOptions, WalSyncMode},
+    proof_list_index::{ListConsistencyProof, ListProof, ProofListIndex},
+    sparse_list_index::SparseListIndex, stats::{storage_stats, IndexStats, TableStats},
+    value_set_index::ValueSetIndex, values::StorageValue,
 };
+#[cfg(feature = "rocksdb")]
+pub use self::rocksdb::RocksDB;
 
 /// A specialized `Result` type for I/O operations with storage.
 pub type Result<T> = ::std::result::Result<T, Error>;
 
 mod base_index;
+mod cache;
 mod db;
 mod entry;
 mod error;
 mod hash;
+mod history;
 mod indexes_metadata;
 mod keys;
+mod logdb;
 mod memorydb;
 mod options;
+#[cfg(feature = "rocksdb")]
 mod rocksdb;
+mod stats;
 mod values;
 
+pub mod index_family;
+pub mod indexed_map;
 pub mod key_set_index;
 pub mod list_index;
 pub mod map_index;
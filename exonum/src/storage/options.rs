@@ -14,9 +14,16 @@
 
 //! Abstract settings for databases.
 
+use std::path::Path;
+
+#[cfg(feature = "rocksdb")]
+use super::RocksDB;
+use super::{CachedDB, Database, LogDB, Result};
+
 /// Options for the database.
 ///
-/// These parameters apply to the underlying database of Exonum, currently `RocksDB`.
+/// These parameters apply to the underlying database of Exonum, which is selected with
+/// the `backend` option. Unless stated otherwise, options apply only to `RocksDB`.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct DbOptions {
     /// Number of open files that can be used by the database.
@@ -38,6 +45,85 @@ pub struct DbOptions {
     ///
     /// Defaults to `true`.
     pub create_if_missing: bool,
+    /// Number of the latest blocks for which transaction bodies and precommits are kept
+    /// in the database.
+    ///
+    /// Data of older blocks is pruned when new blocks are committed; block headers,
+    /// transaction hashes and the current blockchain state are always kept. Note that
+    /// the historical state is also available only for the retained blocks.
+    ///
+    /// A pruned node cannot serve the pruned blocks to the peers synchronizing with
+    /// the network: block requests for the pruned heights are left without a response,
+    /// so such peers have to get the blocks from an archival node.
+    ///
+    /// Defaults to `None`, meaning that the node keeps all data (an archival node).
+    #[serde(default)]
+    pub keep_blocks: Option<u64>,
+    /// Number of the latest blocks for which the history of the storage state is kept.
+    /// The history allows to read the state of the storage as of a past block; it is
+    /// pruned when new blocks are committed.
+    ///
+    /// Defaults to `None`, meaning that the history is kept for all the blocks
+    /// retained according to `keep_blocks`.
+    #[serde(default)]
+    pub keep_history: Option<u64>,
+    /// Compression algorithm applied to the data blocks of the database files.
+    ///
+    /// Defaults to `Snappy`.
+    #[serde(default)]
+    pub compression_type: CompressionType,
+    /// Size of the block cache in bytes, which keeps uncompressed data blocks in memory.
+    /// A separate cache is created for each column family.
+    ///
+    /// Defaults to `None`, meaning that the default size of the database (8 MB) is used.
+    #[serde(default)]
+    pub block_cache_size: Option<usize>,
+    /// Size of a single memtable in bytes. Changes are accumulated in a memtable before
+    /// being written to disk; larger memtables reduce the number of flushes and compactions
+    /// under a write-heavy workload at the cost of memory usage and recovery time.
+    ///
+    /// Defaults to `None`, meaning that the default size of the database (64 MB) is used.
+    #[serde(default)]
+    pub write_buffer_size: Option<usize>,
+    /// Maximum number of memtables, both active and being flushed, held in memory.
+    /// If the limit is reached, writes are stalled until a memtable is flushed.
+    ///
+    /// Defaults to `None`, meaning that the default number of the database (2) is used.
+    #[serde(default)]
+    pub max_write_buffer_number: Option<i32>,
+    /// An option to indicate whether bloom filters should be created for the database
+    /// files. Bloom filters speed up reads of absent keys at the cost of additional
+    /// memory and disk space.
+    ///
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub bloom_filter: bool,
+    /// Synchronization mode of the write-ahead log used by `Database::merge`.
+    ///
+    /// Note that `Database::merge_sync` always waits until the changes are written to disk.
+    ///
+    /// Defaults to `Async`.
+    #[serde(default)]
+    pub wal_sync_mode: WalSyncMode,
+    /// Maximum number of concurrent background jobs, i.e., flushes and compactions.
+    ///
+    /// Defaults to `None`, meaning that the default number of the database (2) is used.
+    #[serde(default)]
+    pub max_background_jobs: Option<i32>,
+    /// Size of the read cache in bytes, which keeps the recently read committed entries
+    /// in memory. The cache applies to all the backends; see [`CachedDB`] for details.
+    ///
+    /// Defaults to `None`, meaning that the cache is disabled.
+    ///
+    /// [`CachedDB`]: struct.CachedDB.html
+    #[serde(default)]
+    pub cache_size: Option<usize>,
+    /// Database implementation used to store the data. Options `create_if_missing`,
+    /// `keep_blocks` and `keep_history` apply to all the backends.
+    ///
+    /// Defaults to `RocksDb` if the `rocksdb` feature is enabled and to `LogFile` otherwise.
+    #[serde(default)]
+    pub backend: DatabaseBackend,
 }
 
 impl Default for DbOptions {
@@ -45,6 +131,102 @@ impl Default for DbOptions {
         Self {
             max_open_files: None,
             create_if_missing: true,
+            keep_blocks: None,
+            keep_history: None,
+            compression_type: CompressionType::default(),
+            block_cache_size: None,
+            write_buffer_size: None,
+            max_write_buffer_number: None,
+            bloom_filter: false,
+            wal_sync_mode: WalSyncMode::default(),
+            max_background_jobs: None,
+            cache_size: None,
+            backend: DatabaseBackend::default(),
         }
     }
 }
+
+/// Compression algorithms of the database files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    /// No compression.
+    None,
+    /// Snappy compression.
+    Snappy,
+    /// Zlib compression.
+    Zlib,
+    /// Bzip2 compression.
+    Bz2,
+    /// LZ4 compression.
+    Lz4,
+    /// LZ4 compression with the high compression ratio.
+    Lz4hc,
+    /// Zstandard compression.
+    Zstd,
+}
+
+impl Default for CompressionType {
+    fn default() -> Self {
+        CompressionType::Snappy
+    }
+}
+
+/// Synchronization modes of the write-ahead log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalSyncMode {
+    /// Changes are written to the log without waiting for the operating system to flush
+    /// them to disk. Changes may be lost if the machine crashes, but not if only the
+    /// process crashes.
+    Async,
+    /// Each write waits until the log is flushed to disk.
+    Sync,
+    /// The log is not written. Changes that have not been flushed from memtables are
+    /// lost if the process crashes.
+    Disabled,
+}
+
+impl Default for WalSyncMode {
+    fn default() -> Self {
+        WalSyncMode::Async
+    }
+}
+
+/// Database implementations that can be selected in the node configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseBackend {
+    /// [`RocksDB`](struct.RocksDB.html) database. Requires the `rocksdb` feature.
+    #[cfg(feature = "rocksdb")]
+    RocksDb,
+    /// Append-only log file database, see [`LogDB`](struct.LogDB.html).
+    LogFile,
+}
+
+impl Default for DatabaseBackend {
+    #[cfg(feature = "rocksdb")]
+    fn default() -> Self {
+        DatabaseBackend::RocksDb
+    }
+
+    #[cfg(not(feature = "rocksdb"))]
+    fn default() -> Self {
+        DatabaseBackend::LogFile
+    }
+}
+
+/// Opens a database of the backend selected in the options at the given path.
+///
+/// If `cache_size` is specified in the options, the database is wrapped into a read cache.
+pub fn open_database<P: AsRef<Path>>(path: P, options: &DbOptions) -> Result<Box<dyn Database>> {
+    let db: Box<dyn Database> = match options.backend {
+        #[cfg(feature = "rocksdb")]
+        DatabaseBackend::RocksDb => Box::new(RocksDB::open(path, options)?),
+        DatabaseBackend::LogFile => Box::new(LogDB::open(path, options)?),
+    };
+    Ok(match options.cache_size {
+        Some(cache_size) => Box::new(CachedDB::new(db, cache_size)),
+        None => db,
+    })
+}
@@ -14,7 +14,7 @@
 
 //! An implementation of a Merkelized version of an array list (Merkle tree).
 
-pub use self::proof::{ListProof, ListProofError};
+pub use self::proof::{ListConsistencyProof, ListConsistencyProofError, ListProof, ListProofError};
 
 use std::{cell::Cell, marker::PhantomData};
 
@@ -47,11 +47,13 @@ pub struct ProofListIndex<T, V> {
 
 /// An iterator over the items of a `ProofListIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`ProofListIndex`]. See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`] or
+/// [`iter_rev_from`] method on [`ProofListIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.ProofListIndex.html#method.iter
 /// [`iter_from`]: struct.ProofListIndex.html#method.iter_from
+/// [`iter_rev`]: struct.ProofListIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.ProofListIndex.html#method.iter_rev_from
 /// [`ProofListIndex`]: struct.ProofListIndex.html
 #[derive(Debug)]
 pub struct ProofListIndexIter<'a, V> {
@@ -191,6 +193,19 @@ where
         }
     }
 
+    fn construct_consistency_proof(&self, key: ProofListKey, old_len: u64, hashes: &mut Vec<Hash>) {
+        let from = key.first_left_leaf_index();
+        let to = from + (1 << (key.height() - 1));
+        if to <= old_len || from >= old_len {
+            hashes.push(self.get_branch_unchecked(key));
+            return;
+        }
+        self.construct_consistency_proof(key.left(), old_len, hashes);
+        if self.has_branch(key.right()) {
+            self.construct_consistency_proof(key.right(), old_len, hashes);
+        }
+    }
+
     /// Returns the element at the indicated position or `None` if the indicated position
     /// is out of bounds.
     ///
@@ -397,6 +412,48 @@ where
         self.construct_proof(self.root_key(), from, to)
     }
 
+    /// Returns the proof that the list with `old_len` first elements of this list is its prefix.
+    ///
+    /// The proof can be verified by the Merkle root hashes of the list at both lengths
+    /// without access to the list elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `old_len` is greater than the length of the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3].iter().cloned());
+    /// let old_root = index.merkle_root();
+    /// index.extend([4, 5].iter().cloned());
+    ///
+    /// let proof = index.get_consistency_proof(3);
+    /// assert!(proof.validate(old_root, 3, index.merkle_root(), 5).is_ok());
+    /// ```
+    pub fn get_consistency_proof(&self, old_len: u64) -> ListConsistencyProof {
+        if old_len > self.len() {
+            panic!(
+                "Illegal old length: the len is {} but the old length is {}",
+                self.len(),
+                old_len
+            );
+        }
+
+        let mut hashes = Vec::new();
+        if !self.is_empty() {
+            self.construct_consistency_proof(self.root_key(), old_len, &mut hashes);
+        }
+        ListConsistencyProof::new(hashes)
+    }
+
     /// Returns an iterator over the list. The iterator element type is V.
     ///
     /// # Examples
@@ -441,6 +498,51 @@ where
             base_iter: self.base.iter_from(&0_u8, &ProofListKey::leaf(from)),
         }
     }
+
+    /// Returns an iterator over the list in reverse order. The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofListIndex<_, u8> = ProofListIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter_rev() {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn iter_rev(&self) -> ProofListIndexIter<V> {
+        ProofListIndexIter {
+            base_iter: self.base.iter_rev(&0_u8),
+        }
+    }
+
+    /// Returns an iterator over the list in reverse order starting from the specified position
+    /// (inclusive). The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofListIndex<_, u8> = ProofListIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter_rev_from(1) {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn iter_rev_from(&self, from: u64) -> ProofListIndexIter<V> {
+        ProofListIndexIter {
+            base_iter: self.base.iter_rev_from(&0_u8, &ProofListKey::leaf(from)),
+        }
+    }
 }
 
 impl<'a, V> ProofListIndex<&'a mut Fork, V>
@@ -547,9 +649,14 @@ where
                 index
             );
         }
-        let mut key = ProofListKey::new(1, index);
+        let key = ProofListKey::new(1, index);
         self.base.put(&key, value.hash());
         self.base.put(&ProofListKey::leaf(index), value);
+        self.update_branches(key);
+    }
+
+    // Recomputes hashes of all branches on the path from the given key to the root.
+    fn update_branches(&mut self, mut key: ProofListKey) {
         while key.height() < self.height() {
             let (left, right) = (key.as_left(), key.as_right());
             let hash = if self.has_branch(right) {
@@ -565,6 +672,89 @@ where
         }
     }
 
+    /// Removes the last element from the proof list and returns it, or returns `None`
+    /// if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    /// assert_eq!(None, index.pop());
+    ///
+    /// index.push(1);
+    /// assert_eq!(Some(1), index.pop());
+    /// assert!(index.is_empty());
+    /// ```
+    pub fn pop(&mut self) -> Option<V> {
+        match self.len() {
+            0 => None,
+            l => {
+                let v = self.get(l - 1);
+                self.truncate(l - 1);
+                v
+            }
+        }
+    }
+
+    /// Shortens the proof list, keeping the indicated number of first `len` elements
+    /// and dropping the rest.
+    ///
+    /// If `len` is greater than the current length of the proof list, this has no effect.
+    ///
+    /// Branches that cover only the dropped elements are removed, and only the hashes
+    /// on the path from the new last element to the root are recomputed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofListIndex::new(name, &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    /// assert_eq!(5, index.len());
+    ///
+    /// index.truncate(3);
+    /// assert_eq!(3, index.len());
+    /// assert_eq!(Some(3), index.last());
+    /// ```
+    pub fn truncate(&mut self, len: u64) {
+        let old_len = self.len();
+        if len >= old_len {
+            return;
+        }
+        let old_height = self.height();
+        self.set_len(len);
+        let height = self.height();
+
+        for index in len..old_len {
+            self.base.remove(&ProofListKey::leaf(index));
+        }
+        // Branches above the new root are removed entirely.
+        for branch_height in 1..=old_height {
+            let from = if branch_height > height {
+                0
+            } else {
+                branches_count(len, branch_height)
+            };
+            for index in from..branches_count(old_len, branch_height) {
+                self.base.remove(&ProofListKey::new(branch_height, index));
+            }
+        }
+
+        if len > 0 {
+            self.update_branches(ProofListKey::new(1, len - 1));
+        }
+    }
+
     /// Clears the proof list, removing all values.
     ///
     /// # Notes
@@ -619,6 +809,13 @@ where
     }
 }
 
+// Returns the number of branches at the given height in a list of the given length.
+fn branches_count(len: u64, height: u8) -> u64 {
+    debug_assert!(height > 0);
+    let shift = height - 1;
+    (len + (1 << shift) - 1) >> shift
+}
+
 /// Computes Merkle root hash for a given list of hashes.
 ///
 /// If `hashes` are empty then `Hash::zero()` value is returned.
@@ -89,6 +89,129 @@ impl<V: StorageValue> ListProof<V> {
     }
 }
 
+/// A proof that a list of a certain length is a prefix of the same list with a larger length.
+///
+/// The proof allows to check that the list with the Merkle root hash observed earlier was
+/// only appended to, i.e., its elements were neither changed nor removed. It consists of
+/// the hashes of the subtrees required to compute both Merkle root hashes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListConsistencyProof {
+    hashes: Vec<Hash>,
+}
+
+/// An error that is returned when the list consistency proof is invalid.
+#[derive(Debug)]
+pub enum ListConsistencyProofError {
+    /// The old length of the list is greater than the new one.
+    InvalidLength,
+    /// The proof is too short and does not correspond to the lengths of the lists.
+    MissingHash,
+    /// The proof is too long and does not correspond to the lengths of the lists.
+    ExtraHash,
+    /// The old hash of the proof is not equal to the trusted old root hash.
+    UnmatchedOldRootHash,
+    /// The new hash of the proof is not equal to the trusted new root hash.
+    UnmatchedNewRootHash,
+}
+
+impl ListConsistencyProof {
+    pub(crate) fn new(hashes: Vec<Hash>) -> Self {
+        Self { hashes }
+    }
+
+    /// Returns the hashes of the subtrees making up the proof.
+    pub fn hashes(&self) -> &[Hash] {
+        &self.hashes
+    }
+
+    // Returns the hashes of the subtree with the given key in the old and the new list.
+    // The old hash is `None` if the old list does not contain the subtree. Subtrees
+    // above the old root pass the old root hash through.
+    fn collect<'a, I>(
+        key: ProofListKey,
+        old_len: u64,
+        new_len: u64,
+        hashes: &mut I,
+    ) -> Result<(Option<Hash>, Hash), ListConsistencyProofError>
+    where
+        I: Iterator<Item = &'a Hash>,
+    {
+        let from = key.first_left_leaf_index();
+        let to = from + (1 << (key.height() - 1));
+        if to <= old_len || from >= old_len {
+            let hash = *hashes
+                .next()
+                .ok_or(ListConsistencyProofError::MissingHash)?;
+            let old_hash = if from < old_len { Some(hash) } else { None };
+            return Ok((old_hash, hash));
+        }
+
+        let middle = key.first_right_leaf_index();
+        let (left_old, left_new) = Self::collect(key.left(), old_len, new_len, hashes)?;
+        let left_old = left_old.expect("Left subtree of a partially old subtree is old");
+        let (right_old, right_new) = if middle < new_len {
+            let (right_old, right_new) = Self::collect(key.right(), old_len, new_len, hashes)?;
+            (right_old, Some(right_new))
+        } else {
+            (None, None)
+        };
+
+        let old_height = old_len.next_power_of_two().trailing_zeros() as u8 + 1;
+        let old_hash = if key.height() > old_height {
+            left_old
+        } else if let Some(right_old) = right_old {
+            hash_pair(&left_old, &right_old)
+        } else {
+            hash_one(&left_old)
+        };
+        let new_hash = match right_new {
+            Some(right_new) => hash_pair(&left_new, &right_new),
+            None => hash_one(&left_new),
+        };
+        Ok((Some(old_hash), new_hash))
+    }
+
+    /// Verifies the correctness of the proof by the trusted Merkle root hashes and the numbers
+    /// of elements of the list before and after appending new elements.
+    ///
+    /// If the proof is valid, `Ok(())` is returned. Otherwise, `Err` is returned.
+    pub fn validate(
+        &self,
+        old_root: Hash,
+        old_len: u64,
+        new_root: Hash,
+        new_len: u64,
+    ) -> Result<(), ListConsistencyProofError> {
+        if old_len > new_len {
+            return Err(ListConsistencyProofError::InvalidLength);
+        }
+
+        let (old_hash, new_hash) = if new_len == 0 {
+            if !self.hashes.is_empty() {
+                return Err(ListConsistencyProofError::ExtraHash);
+            }
+            (None, Hash::default())
+        } else {
+            let mut hashes = self.hashes.iter();
+            let height = new_len.next_power_of_two().trailing_zeros() as u8 + 1;
+            let (old_hash, new_hash) =
+                Self::collect(ProofListKey::new(height, 0), old_len, new_len, &mut hashes)?;
+            if hashes.next().is_some() {
+                return Err(ListConsistencyProofError::ExtraHash);
+            }
+            (old_hash, new_hash)
+        };
+
+        if old_hash.unwrap_or_default() != old_root {
+            return Err(ListConsistencyProofError::UnmatchedOldRootHash);
+        }
+        if new_hash != new_root {
+            return Err(ListConsistencyProofError::UnmatchedNewRootHash);
+        }
+        Ok(())
+    }
+}
+
 impl<V: Serialize> Serialize for ListProof<V> {
     fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
     where
@@ -15,12 +15,12 @@
 use rand::{thread_rng, Rng};
 
 use self::ListProof::*;
-use super::{hash_one, hash_pair, root_hash, ListProof, ProofListIndex};
+use super::{hash_one, hash_pair, root_hash, ListConsistencyProof, ListProof, ProofListIndex};
 use crypto::{hash, CryptoHash, Hash};
 use encoding::serialize::{
     json::reexport::{from_str, to_string}, reexport::Serialize,
 };
-use storage::Database;
+use storage::{Database, Fork, Snapshot};
 
 const IDX_NAME: &'static str = "idx_name";
 
@@ -106,6 +106,14 @@ fn iter(db: Box<dyn Database>) {
         list_index.iter_from(3).collect::<Vec<u8>>(),
         Vec::<u8>::new()
     );
+
+    assert_eq!(list_index.iter_rev().collect::<Vec<u8>>(), vec![3, 2, 1]);
+    assert_eq!(
+        list_index.iter_rev_from(5).collect::<Vec<u8>>(),
+        vec![3, 2, 1]
+    );
+    assert_eq!(list_index.iter_rev_from(1).collect::<Vec<u8>>(), vec![2, 1]);
+    assert_eq!(list_index.iter_rev_from(0).collect::<Vec<u8>>(), vec![1]);
 }
 
 fn list_index_proof(db: Box<dyn Database>) {
@@ -536,6 +544,68 @@ fn same_merkle_root(db1: Box<dyn Database>, db2: Box<dyn Database>) {
     assert_eq!(i1.merkle_root(), i2.merkle_root());
 }
 
+fn consistency_proofs(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    let num_values = 33;
+    let values = random_values(num_values);
+
+    let mut roots = vec![index.merkle_root()];
+    for new_len in 0..num_values as u64 + 1 {
+        if new_len > 0 {
+            index.push(values[new_len as usize - 1].clone());
+            roots.push(index.merkle_root());
+        }
+        let new_root = roots[new_len as usize];
+
+        for old_len in 0..new_len + 1 {
+            let old_root = roots[old_len as usize];
+            let proof = index.get_consistency_proof(old_len);
+            assert!(proof.validate(old_root, old_len, new_root, new_len).is_ok());
+
+            if old_len < new_len {
+                let wrong_root = roots[old_len as usize + 1];
+                assert!(
+                    proof
+                        .validate(wrong_root, old_len, new_root, new_len)
+                        .is_err()
+                );
+                assert!(
+                    proof
+                        .validate(old_root, old_len, old_root, new_len)
+                        .is_err()
+                );
+            }
+            if old_len > 0 {
+                assert!(proof.validate(old_root, old_len, old_root, old_len - 1).is_err());
+            }
+
+            let json_representation = to_string(&proof).unwrap();
+            let deserialized: ListConsistencyProof = from_str(&json_representation).unwrap();
+            assert_eq!(proof, deserialized);
+        }
+    }
+}
+
+fn consistency_proof_rewritten_history(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    index.extend(vec![vec![1], vec![2], vec![3]]);
+    let old_root = index.merkle_root();
+
+    index.set(1, vec![20]);
+    index.extend(vec![vec![4], vec![5]]);
+    let proof = index.get_consistency_proof(3);
+    assert!(proof.validate(old_root, 3, index.merkle_root(), 5).is_err());
+}
+
+fn consistency_proof_illegal_length(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    index.extend(vec![vec![1], vec![2]]);
+    index.get_consistency_proof(3);
+}
+
 #[derive(Serialize)]
 struct ProofInfo<'a, V: Serialize + 'a> {
     merkle_root: Hash,
@@ -545,6 +615,82 @@ struct ProofInfo<'a, V: Serialize + 'a> {
     range_end: u64,
 }
 
+fn pop_and_truncate(db: Box<dyn Database>) {
+    let mut fork = db.fork();
+    let mut index = ProofListIndex::new(IDX_NAME, &mut fork);
+    let num_values = 37;
+    let values = random_values(num_values);
+
+    let mut roots = vec![index.merkle_root()];
+    for value in &values {
+        index.push(value.clone());
+        roots.push(index.merkle_root());
+    }
+
+    for len in (0..num_values).rev() {
+        assert_eq!(index.pop(), Some(values[len].clone()));
+        assert_eq!(index.len(), len as u64);
+        assert_eq!(index.merkle_root(), roots[len]);
+        if len > 0 {
+            let proof = index.get_range_proof(0, len as u64);
+            let proved_values = proof.validate(roots[len], len as u64).unwrap();
+            assert_eq!(proved_values.len(), len);
+        }
+    }
+    assert_eq!(index.pop(), None);
+    assert!(index.is_empty());
+
+    index.extend(values.iter().cloned());
+    index.truncate(num_values as u64 + 1);
+    assert_eq!(index.merkle_root(), roots[num_values]);
+
+    for &len in &[33, 32, 17, 16, 5, 1, 0] {
+        index.truncate(len);
+        assert_eq!(index.len(), len);
+        assert_eq!(index.merkle_root(), roots[len as usize]);
+        if len > 0 {
+            let proof = index.get_proof(len - 1);
+            let proved_values = proof.validate(roots[len as usize], len).unwrap();
+            assert_eq!(proved_values, vec![(len - 1, &values[len as usize - 1])]);
+        }
+    }
+
+    // The list can grow again after truncation.
+    index.extend(values[..20].iter().cloned());
+    index.truncate(9);
+    index.extend(values[9..].iter().cloned());
+    assert_eq!(index.merkle_root(), roots[num_values]);
+}
+
+fn truncate_removes_branches(db: Box<dyn Database>) {
+    fn table_entries(fork: &Fork) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut entries = Vec::new();
+        let mut iter = fork.iter(IDX_NAME, &[]);
+        while let Some((k, v)) = iter.next() {
+            entries.push((k.to_vec(), v.to_vec()));
+        }
+        entries
+    }
+
+    let values = random_values(20);
+    for &(old_len, new_len) in &[(20, 13), (20, 16), (17, 16), (16, 15), (9, 8), (20, 1)] {
+        let mut truncated = db.fork();
+        {
+            let mut index = ProofListIndex::new(IDX_NAME, &mut truncated);
+            index.extend(values[..old_len].iter().cloned());
+            index.truncate(new_len as u64);
+        }
+
+        let mut expected = db.fork();
+        {
+            let mut index = ProofListIndex::new(IDX_NAME, &mut expected);
+            index.extend(values[..new_len].iter().cloned());
+        }
+
+        assert_eq!(table_entries(&truncated), table_entries(&expected));
+    }
+}
+
 mod memorydb_tests {
     use std::path::Path;
     use storage::{Database, MemoryDB};
@@ -637,6 +783,47 @@ mod memorydb_tests {
         super::proof_structure(db);
     }
 
+    #[test]
+    fn test_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proofs(db);
+    }
+
+    #[test]
+    fn test_consistency_proof_rewritten_history() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_rewritten_history(db);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_consistency_proof_illegal_length() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_illegal_length(db);
+    }
+
+    #[test]
+    fn test_pop_and_truncate() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::pop_and_truncate(db);
+    }
+
+    #[test]
+    fn test_truncate_removes_branches() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::truncate_removes_branches(db);
+    }
+
     #[test]
     fn test_simple_merkle_root() {
         let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
@@ -657,6 +844,7 @@ mod memorydb_tests {
     }
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
     use std::path::Path;
     use storage::{Database, DbOptions, RocksDB};
@@ -750,6 +938,47 @@ mod rocksdb_tests {
         super::proof_structure(db);
     }
 
+    #[test]
+    fn test_consistency_proofs() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proofs(db);
+    }
+
+    #[test]
+    fn test_consistency_proof_rewritten_history() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_rewritten_history(db);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_consistency_proof_illegal_length() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::consistency_proof_illegal_length(db);
+    }
+
+    #[test]
+    fn test_pop_and_truncate() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::pop_and_truncate(db);
+    }
+
+    #[test]
+    fn test_truncate_removes_branches() {
+        let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
+        let path = dir.path();
+        let db = create_database(path);
+        super::truncate_removes_branches(db);
+    }
+
     #[test]
     fn test_simple_merkle_root() {
         let dir = TempDir::new(super::gen_tempdir_name().as_str()).unwrap();
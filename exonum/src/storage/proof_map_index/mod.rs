@@ -16,18 +16,18 @@
 
 pub use self::{
     key::{HashedKey, ProofMapKey, ProofPath, KEY_SIZE as PROOF_MAP_KEY_SIZE},
-    proof::{CheckedMapProof, MapProof, MapProofError},
+    proof::{CheckedMapProof, CheckedMapRangeProof, MapProof, MapProofError, MapRangeProof},
 };
 
-use std::{fmt, marker::PhantomData};
+use std::{cmp, fmt, marker::PhantomData};
 
 use self::{
     key::{BitsRange, ChildKind, LEAF_KEY_PREFIX}, node::{BranchNode, Node},
-    proof::{create_multiproof, create_proof},
+    proof::{create_multiproof, create_proof, create_range_proof},
 };
 use super::{
-    base_index::{BaseIndex, BaseIndexIter}, indexes_metadata::IndexType, Fork, Snapshot,
-    StorageKey, StorageValue,
+    base_index::{BaseIndex, BaseIndexIter}, indexed_map::{PrimaryIndex, PrimaryIndexMut},
+    indexes_metadata::IndexType, Fork, Snapshot, StorageKey, StorageValue,
 };
 use crypto::{CryptoHash, Hash, HashStream};
 
@@ -59,11 +59,13 @@ pub struct ProofMapIndex<T, K, V> {
 
 /// An iterator over the entries of a `ProofMapIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`ProofMapIndex`]. See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`] or
+/// [`iter_rev_from`] method on [`ProofMapIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.ProofMapIndex.html#method.iter
 /// [`iter_from`]: struct.ProofMapIndex.html#method.iter_from
+/// [`iter_rev`]: struct.ProofMapIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.ProofMapIndex.html#method.iter_rev_from
 /// [`ProofMapIndex`]: struct.ProofMapIndex.html
 #[derive(Debug)]
 pub struct ProofMapIndexIter<'a, K, V> {
@@ -73,11 +75,13 @@ pub struct ProofMapIndexIter<'a, K, V> {
 
 /// An iterator over the keys of a `ProofMapIndex`.
 ///
-/// This struct is created by the [`keys`] or
-/// [`keys_from`] method on [`ProofMapIndex`]. See its documentation for details.
+/// This struct is created by the [`keys`], [`keys_from`], [`keys_rev`] or
+/// [`keys_rev_from`] method on [`ProofMapIndex`]. See its documentation for details.
 ///
 /// [`keys`]: struct.ProofMapIndex.html#method.keys
 /// [`keys_from`]: struct.ProofMapIndex.html#method.keys_from
+/// [`keys_rev`]: struct.ProofMapIndex.html#method.keys_rev
+/// [`keys_rev_from`]: struct.ProofMapIndex.html#method.keys_rev_from
 /// [`ProofMapIndex`]: struct.ProofMapIndex.html
 #[derive(Debug)]
 pub struct ProofMapIndexKeys<'a, K> {
@@ -87,11 +91,13 @@ pub struct ProofMapIndexKeys<'a, K> {
 
 /// An iterator over the values of a `ProofMapIndex`.
 ///
-/// This struct is created by the [`values`] or
-/// [`values_from`] method on [`ProofMapIndex`]. See its documentation for details.
+/// This struct is created by the [`values`], [`values_from`], [`values_rev`] or
+/// [`values_rev_from`] method on [`ProofMapIndex`]. See its documentation for details.
 ///
 /// [`values`]: struct.ProofMapIndex.html#method.values
 /// [`values_from`]: struct.ProofMapIndex.html#method.values_from
+/// [`values_rev`]: struct.ProofMapIndex.html#method.values_rev
+/// [`values_rev_from`]: struct.ProofMapIndex.html#method.values_rev_from
 /// [`ProofMapIndex`]: struct.ProofMapIndex.html
 #[derive(Debug)]
 pub struct ProofMapIndexValues<'a, V> {
@@ -331,6 +337,32 @@ where
         })
     }
 
+    /// Returns the proof of completeness for the entries with keys in the `[from, to)` range.
+    ///
+    /// Keys are compared by their [`ProofPath`]s, i.e., in the order of the leaves of
+    /// the Merkle Patricia tree, starting from the least significant bit of the first byte
+    /// of a key. See [`MapRangeProof`] for details.
+    ///
+    /// [`ProofPath`]: struct.ProofPath.html
+    /// [`MapRangeProof`]: struct.MapRangeProof.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, [u8; 32], u8> = ProofMapIndex::new("index", &snapshot);
+    ///
+    /// let proof = index.get_range_proof([0; 32], [128; 32]);
+    /// ```
+    pub fn get_range_proof(&self, from: K, to: K) -> MapRangeProof<K::Output, V> {
+        create_range_proof(&from, &to, self.get_root_node(), |path| {
+            self.get_node_unchecked(path)
+        })
+    }
+
     /// Returns an iterator over the entries of the map in ascending order. The iterator element
     /// type is `(K::Output, V)`.
     ///
@@ -481,6 +513,160 @@ where
             base_iter: self.base.iter_from(&LEAF_KEY_PREFIX, &ProofPath::new(from)),
         }
     }
+
+    /// Returns an iterator over the entries of the map in descending order. The iterator element
+    /// type is `(K::Output, V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter_rev() {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn iter_rev(&self) -> ProofMapIndexIter<K, V> {
+        ProofMapIndexIter {
+            base_iter: self.base.iter_rev(&LEAF_KEY_PREFIX),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map in descending order. The iterator element
+    /// type is `K::Output`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// for key in index.keys_rev() {
+    ///     println!("{:?}", key);
+    /// }
+    /// ```
+    pub fn keys_rev(&self) -> ProofMapIndexKeys<K> {
+        ProofMapIndexKeys {
+            base_iter: self.base.iter_rev(&LEAF_KEY_PREFIX),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the values of the map in descending order of keys. The iterator
+    /// element type is `V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// for val in index.values_rev() {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn values_rev(&self) -> ProofMapIndexValues<V> {
+        ProofMapIndexValues {
+            base_iter: self.base.iter_rev(&LEAF_KEY_PREFIX),
+        }
+    }
+
+    /// Returns an iterator over the entries of the map in descending order starting from the
+    /// specified key (inclusive). The iterator element type is `(K::Output, V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// let hash = Hash::default();
+    /// for val in index.iter_rev_from(&hash) {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn iter_rev_from(&self, from: &K) -> ProofMapIndexIter<K, V> {
+        ProofMapIndexIter {
+            base_iter: self.base
+                .iter_rev_from(&LEAF_KEY_PREFIX, &ProofPath::new(from)),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the keys of the map in descending order starting from the
+    /// specified key (inclusive). The iterator element type is `K::Output`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// let hash = Hash::default();
+    /// for key in index.keys_rev_from(&hash) {
+    ///     println!("{:?}", key);
+    /// }
+    /// ```
+    pub fn keys_rev_from(&self, from: &K) -> ProofMapIndexKeys<K> {
+        ProofMapIndexKeys {
+            base_iter: self.base
+                .iter_rev_from(&LEAF_KEY_PREFIX, &ProofPath::new(from)),
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the values of the map in descending order of keys starting from
+    /// the specified key (inclusive). The iterator element type is `V`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ProofMapIndex<_, Hash, u8> = ProofMapIndex::new(name, &snapshot);
+    ///
+    /// let hash = Hash::default();
+    /// for val in index.values_rev_from(&hash) {
+    ///     println!("{}", val);
+    /// }
+    /// ```
+    pub fn values_rev_from(&self, from: &K) -> ProofMapIndexValues<V> {
+        ProofMapIndexValues {
+            base_iter: self.base
+                .iter_rev_from(&LEAF_KEY_PREFIX, &ProofPath::new(from)),
+        }
+    }
 }
 
 impl<'a, K, V> ProofMapIndex<&'a mut Fork, K, V>
@@ -729,6 +915,210 @@ where
         }
     }
 
+    /// Inserts the key-value pairs into the proof map.
+    ///
+    /// Unlike a sequence of [`put`] calls, the hash of each affected branch node is
+    /// recomputed only once, which makes this method considerably faster for large batches.
+    /// If a key occurs several times, the last value is inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofMapIndex::new(name, &mut fork);
+    ///
+    /// let keys = vec![hash(&[1]), hash(&[2]), hash(&[3])];
+    /// index.put_all(keys.iter().map(|key| (*key, 1)));
+    /// assert!(keys.iter().all(|key| index.contains(key)));
+    /// ```
+    ///
+    /// [`put`]: #method.put
+    pub fn put_all<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let changes = iter.into_iter()
+            .map(|(key, value)| (ProofPath::new(&key), Some(value)))
+            .collect();
+        self.update_all(changes);
+    }
+
+    /// Removes the keys from the proof map.
+    ///
+    /// Unlike a sequence of [`remove`] calls, the hash of each affected branch node is
+    /// recomputed only once. Keys absent in the map are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ProofMapIndex};
+    /// use exonum::crypto::hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name = "name";
+    /// let mut fork = db.fork();
+    /// let mut index = ProofMapIndex::new(name, &mut fork);
+    ///
+    /// let keys = vec![hash(&[1]), hash(&[2]), hash(&[3])];
+    /// index.put_all(keys.iter().map(|key| (*key, 1)));
+    /// index.remove_all(keys[1..].iter().cloned());
+    /// assert!(index.contains(&keys[0]));
+    /// assert!(!index.contains(&keys[1]));
+    /// ```
+    ///
+    /// [`remove`]: #method.remove
+    pub fn remove_all<I>(&mut self, keys: I)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let changes = keys.into_iter()
+            .map(|key| (ProofPath::new(&key), None))
+            .collect();
+        self.update_all(changes);
+    }
+
+    // Applies the changes, where `None` means the removal of the key.
+    fn update_all(&mut self, mut changes: Vec<(ProofPath, Option<V>)>) {
+        // The sort is stable, so the last change of each key is kept after deduplication.
+        changes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let mut deduplicated: Vec<(ProofPath, Option<V>)> = Vec::with_capacity(changes.len());
+        for change in changes {
+            if deduplicated.last().map_or(false, |last| last.0 == change.0) {
+                *deduplicated.last_mut().unwrap() = change;
+            } else {
+                deduplicated.push(change);
+            }
+        }
+
+        let root = self.get_root_node().map(|(path, node)| {
+            let hash = match node {
+                Node::Leaf(value) => value.hash(),
+                Node::Branch(branch) => branch.hash(),
+            };
+            (path, hash)
+        });
+        self.update_subtree(root, &mut deduplicated);
+    }
+
+    // Applies the sorted changes to the subtree with the given root path and hash and
+    // returns the path and hash of the updated subtree root, or `None` if the subtree
+    // becomes empty. The hash of a leaf is the hash of its value. All paths start
+    // from the root of the tree.
+    fn update_subtree(
+        &mut self,
+        root: Option<(ProofPath, Hash)>,
+        changes: &mut [(ProofPath, Option<V>)],
+    ) -> Option<(ProofPath, Hash)> {
+        let (root_path, root_hash) = match root {
+            Some(root) => root,
+            None => return self.build_subtree(changes),
+        };
+        if changes.is_empty() {
+            return Some((root_path, root_hash));
+        }
+
+        // Changes are sorted, so they are all inside the subtree if the first and the last
+        // ones are.
+        let first = changes[0].0;
+        let last = changes[changes.len() - 1].0;
+        let prefix_len = cmp::min(
+            root_path.common_prefix_len(&first),
+            root_path.common_prefix_len(&last),
+        );
+        if prefix_len < root_path.len() {
+            // Some changes are outside the subtree, so the updated subtree root is
+            // a new branch at the common prefix of the subtree and the changes.
+            let common_len = cmp::min(prefix_len, first.common_prefix_len(&last));
+            let (left, right) = split_changes(changes, common_len);
+            let (left_root, right_root) = match root_path.bit(common_len) {
+                ChildKind::Left => (Some((root_path, root_hash)), None),
+                ChildKind::Right => (None, Some((root_path, root_hash))),
+            };
+            let left = self.update_subtree(left_root, left);
+            let right = self.update_subtree(right_root, right);
+            return self.join_subtrees(&root_path.prefix(common_len), left, right, false);
+        }
+
+        if root_path.is_leaf() {
+            // Changes are deduplicated, so there is the only change of this leaf.
+            return match changes[0].1.take() {
+                Some(value) => Some((root_path, self.insert_leaf(&root_path, value))),
+                None => {
+                    self.base.remove(&root_path);
+                    None
+                }
+            };
+        }
+
+        let branch: BranchNode = self.base.get(&root_path).unwrap();
+        let (left, right) = split_changes(changes, root_path.len());
+        let left_child = (
+            branch.child_path(ChildKind::Left),
+            *branch.child_hash(ChildKind::Left),
+        );
+        let right_child = (
+            branch.child_path(ChildKind::Right),
+            *branch.child_hash(ChildKind::Right),
+        );
+        let left = self.update_subtree(Some(left_child), left);
+        let right = self.update_subtree(Some(right_child), right);
+        self.join_subtrees(&root_path, left, right, true)
+    }
+
+    // Creates a subtree containing the values inserted by the sorted changes.
+    fn build_subtree(
+        &mut self,
+        changes: &mut [(ProofPath, Option<V>)],
+    ) -> Option<(ProofPath, Hash)> {
+        if changes.iter().all(|change| change.1.is_none()) {
+            return None;
+        }
+        if changes.len() == 1 {
+            let (path, value) = (changes[0].0, changes[0].1.take().unwrap());
+            return Some((path, self.insert_leaf(&path, value)));
+        }
+
+        let first = changes[0].0;
+        let common_len = first.common_prefix_len(&changes[changes.len() - 1].0);
+        let (left, right) = split_changes(changes, common_len);
+        let left = self.build_subtree(left);
+        let right = self.build_subtree(right);
+        self.join_subtrees(&first.prefix(common_len), left, right, false)
+    }
+
+    // Stores the branch with the given children at `path`. If a child is absent, the branch
+    // is replaced by the other child. `existing` indicates that a branch is already stored
+    // at `path`.
+    fn join_subtrees(
+        &mut self,
+        path: &ProofPath,
+        left: Option<(ProofPath, Hash)>,
+        right: Option<(ProofPath, Hash)>,
+        existing: bool,
+    ) -> Option<(ProofPath, Hash)> {
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                let mut branch = BranchNode::empty();
+                branch.set_child(ChildKind::Left, &left.0, &left.1);
+                branch.set_child(ChildKind::Right, &right.0, &right.1);
+                let hash = branch.hash();
+                self.base.put(path, branch);
+                Some((*path, hash))
+            }
+            (child, None) | (None, child) => {
+                if existing {
+                    self.base.remove(path);
+                }
+                child
+            }
+        }
+    }
+
     /// Clears the proof map, removing all entries.
     ///
     /// # Notes
@@ -760,6 +1150,19 @@ where
     }
 }
 
+// Splits the sorted changes into the ones with the bit at the given position
+// set to 0 and to 1.
+fn split_changes<V>(
+    changes: &mut [(ProofPath, Option<V>)],
+    bit: u16,
+) -> (&mut [(ProofPath, Option<V>)], &mut [(ProofPath, Option<V>)]) {
+    let position = changes
+        .iter()
+        .position(|change| change.0.bit(bit) == ChildKind::Right)
+        .unwrap_or_else(|| changes.len());
+    changes.split_at_mut(position)
+}
+
 impl<'a, T, K, V> ::std::iter::IntoIterator for &'a ProofMapIndex<T, K, V>
 where
     T: AsRef<dyn Snapshot>,
@@ -774,6 +1177,46 @@ where
     }
 }
 
+impl<T, K, V> PrimaryIndex for ProofMapIndex<T, K, V>
+where
+    T: AsRef<dyn Snapshot>,
+    K: ProofMapKey + StorageKey + Clone,
+    V: StorageValue,
+{
+    type Key = K;
+    type Value = V;
+
+    fn snapshot(&self) -> &(dyn Snapshot + 'static) {
+        self.base.snapshot()
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.get(key)
+    }
+}
+
+impl<'a, K, V> PrimaryIndexMut for ProofMapIndex<&'a mut Fork, K, V>
+where
+    K: ProofMapKey + StorageKey + Clone,
+    V: StorageValue,
+{
+    fn fork(&mut self) -> &mut Fork {
+        self.base.fork()
+    }
+
+    fn put(&mut self, key: &K, value: V) {
+        self.put(key, value)
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+}
+
 impl<'a, K, V> Iterator for ProofMapIndexIter<'a, K, V>
 where
     K: ProofMapKey,
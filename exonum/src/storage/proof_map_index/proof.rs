@@ -121,6 +121,15 @@ pub enum MapProofError {
     /// Entries in the proof are not ordered by increasing path.
     #[fail(display = "invalid path ordering")]
     InvalidOrdering(ProofPath, ProofPath),
+
+    /// An entry of the range proof lies outside the proved range.
+    #[fail(display = "entry outside the range in proof")]
+    EntryOutOfRange(ProofPath),
+
+    /// A hashed subtree of the range proof intersects the proved range, so the entries
+    /// of the range may be incomplete.
+    #[fail(display = "incomplete range in proof")]
+    IncompleteRange(ProofPath),
 }
 
 // Used instead of `(ProofPath, Hash)` only for the purpose of clearer (de)serialization.
@@ -130,6 +139,13 @@ struct MapProofEntry {
     hash: Hash,
 }
 
+// Used instead of `(K, V)` only for the purpose of clearer (de)serialization.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct MapRangeEntry<K, V> {
+    key: K,
+    value: V,
+}
+
 // Used instead of `(K, Option<V>)` only for the purpose of clearer (de)serialization.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
@@ -274,6 +290,72 @@ pub struct CheckedMapProof<K, V> {
     hash: Hash,
 }
 
+/// Proof of completeness for a range of a `ProofMapIndex`, i.e., all entries of the index
+/// whose keys lie in the range coupled with a *proof*, which jointly allow restoring
+/// the `merkle_root()` of the index.
+///
+/// The range is half-open, `[from, to)`, and keys are compared by their [`ProofPath`]s,
+/// i.e., in the order in which they are stored in the Merkle Patricia tree. Note that this
+/// order is different from the lexicographic order of the key bytes.
+///
+/// # Workflow
+///
+/// You can create `MapRangeProof`s with the [`get_range_proof()`] method of `ProofMapIndex`.
+/// Proofs can be verified with the help of [`check()`], which ensures that no entries
+/// from the range are omitted from the proof.
+///
+/// ```
+/// # use exonum::storage::{Database, MemoryDB, ProofMapIndex};
+/// let mut fork = { let db = MemoryDB::new(); db.fork() };
+/// let mut map = ProofMapIndex::new("index", &mut fork);
+/// map.put(&[1; 32], 100u32);
+/// map.put(&[2; 32], 200u32);
+/// map.put(&[3; 32], 300u32);
+///
+/// // Keys are compared starting from the least significant bit of the first byte,
+/// // so `[2; 32]` is less than `[1; 32]` and `[3; 32]`.
+/// let proof = map.get_range_proof([0; 32], [1; 32]);
+///
+/// let checked_proof = proof.check().unwrap();
+/// assert_eq!(checked_proof.entries(), vec![(&[2; 32], &200u32)]);
+/// assert_eq!(checked_proof.merkle_root(), map.merkle_root());
+/// ```
+///
+/// # JSON serialization
+///
+/// `MapRangeProof` is serialized to JSON as an object with the following fields:
+///
+/// - `from` and `to` are the boundaries of the range.
+/// - `entries` is an array of `{ "key": K, "value": V }` objects for all entries in the range,
+///   sorted by increasing [`ProofPath`] of the keys.
+/// - `proof` is an array of `{ "path": ProofPath, "hash": Hash }` objects for the subtrees
+///   lying outside the range, in the same format as in [`MapProof`].
+///
+/// [`get_range_proof()`]: struct.ProofMapIndex.html#method.get_range_proof
+/// [`check()`]: #method.check
+/// [`ProofPath`]: struct.ProofPath.html
+/// [`MapProof`]: struct.MapProof.html
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapRangeProof<K, V> {
+    from: K,
+    to: K,
+    entries: Vec<MapRangeEntry<K, V>>,
+    proof: Vec<MapProofEntry>,
+}
+
+/// Version of `MapRangeProof` obtained after verification.
+///
+/// See [`MapRangeProof`] for an example of usage.
+///
+/// [`MapRangeProof`]: struct.MapRangeProof.html#workflow
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckedMapRangeProof<K, V> {
+    from: K,
+    to: K,
+    entries: Vec<(K, V)>,
+    hash: Hash,
+}
+
 /// Computes the root hash of the Merkle Patricia tree backing the specified entries
 /// in the map view.
 ///
@@ -374,6 +456,41 @@ fn collect(entries: &[MapProofEntry]) -> Result<Hash, MapProofError> {
     }
 }
 
+/// Checks that entries in the proof are in increasing order and no path in the proof
+/// is a prefix of another one.
+fn check_proof_ordering(proof: &[MapProofEntry]) -> Result<(), MapProofError> {
+    use self::MapProofError::*;
+    use std::cmp::Ordering;
+
+    for w in proof.windows(2) {
+        let (prev_path, path) = (&w[0].path, &w[1].path);
+        match prev_path.partial_cmp(path) {
+            Some(Ordering::Less) => {
+                if path.starts_with(prev_path) {
+                    return Err(EmbeddedPaths {
+                        prefix: *prev_path,
+                        path: *path,
+                    });
+                }
+            }
+            Some(Ordering::Equal) => {
+                return Err(DuplicatePath(*path));
+            }
+            Some(Ordering::Greater) => {
+                return Err(InvalidOrdering(*prev_path, *path));
+            }
+            None => unreachable!("Incomparable keys in proof"),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the subtree with the given path lies entirely outside the `[from, to)` range
+/// of leaf paths.
+fn is_outside_range(path: &ProofPath, from: &ProofPath, to: &ProofPath) -> bool {
+    (path < from && !from.starts_with(path)) || path >= to
+}
+
 /// Builder for [`MapProof`]s.
 ///
 /// This struct rarely needs to be used explicitly (except for testing purposes). Instead,
@@ -469,29 +586,8 @@ where
 {
     fn precheck(&self) -> Result<(), MapProofError> {
         use self::MapProofError::*;
-        use std::cmp::Ordering;
 
-        // Check that entries in proof are in increasing order
-        for w in self.proof.windows(2) {
-            let (prev_path, path) = (&w[0].path, &w[1].path);
-            match prev_path.partial_cmp(path) {
-                Some(Ordering::Less) => {
-                    if path.starts_with(prev_path) {
-                        return Err(EmbeddedPaths {
-                            prefix: *prev_path,
-                            path: *path,
-                        });
-                    }
-                }
-                Some(Ordering::Equal) => {
-                    return Err(DuplicatePath(*path));
-                }
-                Some(Ordering::Greater) => {
-                    return Err(InvalidOrdering(*prev_path, *path));
-                }
-                None => unreachable!("Incomparable keys in proof"),
-            }
-        }
+        check_proof_ordering(&self.proof)?;
 
         // Check that no entry has a prefix among the paths in the proof entries.
         // In order to do this, it suffices to locate the closest smaller path in the proof entries
@@ -621,6 +717,120 @@ impl<K, V> CheckedMapProof<K, V> {
     }
 }
 
+impl<K, V> MapRangeProof<K, V> {
+    /// Provides access to the proof part of the view. Useful mainly for debug purposes.
+    pub fn proof_unchecked(&self) -> Vec<(ProofPath, Hash)> {
+        self.proof
+            .iter()
+            .cloned()
+            .map(|e| (e.path, e.hash))
+            .collect()
+    }
+}
+
+impl<K, V> MapRangeProof<K, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+{
+    /// Consumes this proof producing a `CheckedMapRangeProof` structure.
+    ///
+    /// Fails if the proof is malformed or if some entries from the range may be missing
+    /// from the proof.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum::storage::{Database, MemoryDB, ProofMapIndex};
+    /// let mut fork = { let db = MemoryDB::new(); db.fork() };
+    /// let mut map = ProofMapIndex::new("index", &mut fork);
+    /// map.put(&[1; 32], 100u32);
+    /// map.put(&[2; 32], 200u32);
+    ///
+    /// let proof = map.get_range_proof([0; 32], [255; 32]);
+    /// let checked_proof = proof.check().unwrap();
+    /// assert_eq!(checked_proof.entries(), vec![(&[2; 32], &200u32), (&[1; 32], &100u32)]);
+    /// assert_eq!(checked_proof.merkle_root(), map.merkle_root());
+    /// ```
+    pub fn check(self) -> Result<CheckedMapRangeProof<K, V>, MapProofError> {
+        use self::MapProofError::*;
+        use std::cmp::Ordering;
+
+        let MapRangeProof {
+            from,
+            to,
+            entries,
+            mut proof,
+        } = self;
+        let (from_path, to_path) = (ProofPath::new(&from), ProofPath::new(&to));
+
+        check_proof_ordering(&proof)?;
+        // Subtrees hashed in the proof must not contain entries from the range;
+        // otherwise, the prover could hide some of the entries.
+        if let Some(entry) = proof
+            .iter()
+            .find(|e| !is_outside_range(&e.path, &from_path, &to_path))
+        {
+            return Err(IncompleteRange(entry.path));
+        }
+
+        let mut last_path: Option<ProofPath> = None;
+        for entry in &entries {
+            let path = ProofPath::new(&entry.key);
+            if is_outside_range(&path, &from_path, &to_path) {
+                return Err(EntryOutOfRange(path));
+            }
+            if let Some(last_path) = last_path {
+                match last_path.partial_cmp(&path) {
+                    Some(Ordering::Less) => {}
+                    Some(Ordering::Equal) => return Err(DuplicatePath(path)),
+                    _ => return Err(InvalidOrdering(last_path, path)),
+                }
+            }
+            last_path = Some(path);
+
+            proof.push(MapProofEntry {
+                path,
+                hash: entry.value.hash(),
+            });
+        }
+        // Entries lie inside the range and proof entries lie outside it, so there
+        // can be no duplicate or embedded paths after sorting.
+        proof.sort_unstable_by(|x, y| {
+            x.path
+                .partial_cmp(&y.path)
+                .expect("Incomparable paths in proof")
+        });
+
+        collect(&proof).map(|hash| CheckedMapRangeProof {
+            from,
+            to,
+            entries: entries.into_iter().map(|e| (e.key, e.value)).collect(),
+            hash,
+        })
+    }
+}
+
+impl<K, V> CheckedMapRangeProof<K, V> {
+    /// Returns the boundaries of the proved range, `[from, to)`.
+    pub fn range(&self) -> (&K, &K) {
+        (&self.from, &self.to)
+    }
+
+    /// Retrieves references to all key-value pairs of the map lying in the range.
+    pub fn entries(&self) -> Vec<(&K, &V)> {
+        self.entries
+            .iter()
+            .map(|&(ref key, ref value)| (key, value))
+            .collect()
+    }
+
+    /// Returns a hash of the map that this proof is constructed for.
+    pub fn merkle_root(&self) -> Hash {
+        self.hash
+    }
+}
+
 /// Creates a proof for a single key.
 pub fn create_proof<K, V, F>(
     key: K,
@@ -906,3 +1116,95 @@ where
             .create(),
     }
 }
+
+/// Builder for [`MapRangeProof`]s, which traverses the subtrees intersecting the range.
+///
+/// [`MapRangeProof`]: struct.MapRangeProof.html
+struct MapRangeProofBuilder<K: ProofMapKey, V> {
+    from: ProofPath,
+    to: ProofPath,
+    entries: Vec<MapRangeEntry<K::Output, V>>,
+    proof: Vec<MapProofEntry>,
+}
+
+impl<K, V> MapRangeProofBuilder<K, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+{
+    fn add_node<F>(&mut self, path: ProofPath, hash: Hash, lookup: &F)
+    where
+        F: Fn(&ProofPath) -> Node<V>,
+    {
+        if is_outside_range(&path, &self.from, &self.to) {
+            self.proof.push(MapProofEntry { path, hash });
+        } else {
+            let node = lookup(&path);
+            self.add_inner_node(path, node, lookup);
+        }
+    }
+
+    fn add_inner_node<F>(&mut self, path: ProofPath, node: Node<V>, lookup: &F)
+    where
+        F: Fn(&ProofPath) -> Node<V>,
+    {
+        match node {
+            Node::Branch(branch) => self.add_children(&branch, lookup),
+            Node::Leaf(value) => self.entries.push(MapRangeEntry {
+                key: K::read_key(path.raw_key()),
+                value,
+            }),
+        }
+    }
+
+    fn add_children<F>(&mut self, branch: &BranchNode, lookup: &F)
+    where
+        F: Fn(&ProofPath) -> Node<V>,
+    {
+        for &kind in &[ChildKind::Left, ChildKind::Right] {
+            self.add_node(branch.child_path(kind), *branch.child_hash(kind), lookup);
+        }
+    }
+}
+
+/// Creates a proof for all entries with keys in the `[from, to)` range.
+pub fn create_range_proof<K, V, F>(
+    from: &K,
+    to: &K,
+    root_node: Option<(ProofPath, Node<V>)>,
+    lookup: F,
+) -> MapRangeProof<K::Output, V>
+where
+    K: ProofMapKey,
+    V: StorageValue,
+    F: Fn(&ProofPath) -> Node<V>,
+{
+    let (from, to) = (ProofPath::new(from), ProofPath::new(to));
+    let mut builder: MapRangeProofBuilder<K, V> = MapRangeProofBuilder {
+        from,
+        to,
+        entries: vec![],
+        proof: vec![],
+    };
+
+    if let Some((root_path, root_node)) = root_node {
+        match root_node {
+            Node::Leaf(ref value) if is_outside_range(&root_path, &from, &to) => {
+                builder.proof.push(MapProofEntry {
+                    path: root_path,
+                    hash: value.hash(),
+                });
+            }
+            // Children of the root branch are always put into the proof, even if the whole
+            // tree lies outside the range, since a single branch cannot be checked.
+            root_node => builder.add_inner_node(root_path, root_node, &lookup),
+        }
+    }
+
+    MapRangeProof {
+        from: K::read_key(from.raw_key()),
+        to: K::read_key(to.raw_key()),
+        entries: builder.entries,
+        proof: builder.proof,
+    }
+}
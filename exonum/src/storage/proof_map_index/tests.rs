@@ -19,12 +19,12 @@ use std::{cmp, collections::HashSet, fmt::Debug, hash::Hash as StdHash};
 
 use super::{
     key::{BitsRange, ChildKind, KEY_SIZE, LEAF_KEY_PREFIX}, node::BranchNode,
-    proof::MapProofBuilder, HashedKey, MapProof, MapProofError, ProofMapIndex, ProofMapKey,
-    ProofPath,
+    proof::MapProofBuilder, HashedKey, MapProof, MapProofError, MapRangeProof, ProofMapIndex,
+    ProofMapKey, ProofPath,
 };
 use crypto::{hash, CryptoHash, Hash, HashStream};
 use encoding::serialize::reexport::{DeserializeOwned, Serialize};
-use storage::{Database, Fork, StorageValue};
+use storage::{Database, Fork, Snapshot, StorageValue};
 
 const IDX_NAME: &'static str = "idx_name";
 
@@ -376,6 +376,48 @@ fn check_map_multiproof<K, V>(
 
 const MAX_CHECKED_ELEMENTS: usize = 1_024;
 
+fn check_map_range_proof<K, V>(
+    proof: MapRangeProof<K, V>,
+    from: K,
+    to: K,
+    table: &ProofMapIndex<&mut Fork, K, V>,
+) where
+    K: ProofMapKey<Output = K> + PartialEq + Debug + Serialize + DeserializeOwned,
+    V: StorageValue + PartialEq + Debug + Serialize + DeserializeOwned,
+{
+    let serialized_proof = serde_json::to_value(&proof).unwrap();
+    let deserialized_proof: MapRangeProof<K, V> = serde_json::from_value(serialized_proof).unwrap();
+
+    let (from_path, to_path) = (ProofPath::new(&from), ProofPath::new(&to));
+    let mut entries: Vec<(K, V)> = table
+        .iter()
+        .filter(|&(ref key, _)| {
+            let path = ProofPath::new(key);
+            from_path <= path && path < to_path
+        })
+        .collect();
+    entries.sort_by(|x, y| {
+        ProofPath::new(&x.0)
+            .partial_cmp(&ProofPath::new(&y.0))
+            .unwrap()
+    });
+
+    let proof = proof.check().unwrap();
+    assert_eq!(proof.range(), (&from, &to));
+    assert_eq!(
+        proof.entries(),
+        entries
+            .iter()
+            .map(|&(ref k, ref v)| (k, v))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(proof.merkle_root(), table.merkle_root());
+
+    let deserialized_proof = deserialized_proof.check().unwrap();
+    assert_eq!(deserialized_proof.entries(), proof.entries());
+    assert_eq!(deserialized_proof.merkle_root(), proof.merkle_root());
+}
+
 fn check_proofs_for_data<K, V>(db: &Box<dyn Database>, data: Vec<(K, V)>, nonexisting_keys: Vec<K>)
 where
     K: ProofMapKey + Copy + PartialEq + Debug + Serialize + DeserializeOwned,
@@ -517,6 +559,65 @@ fn test_invalid_map_proofs() {
     }
 }
 
+#[test]
+fn test_invalid_map_range_proofs() {
+    use self::MapProofError::*;
+
+    let (min_key, max_key) = ([0_u8; 32], [255_u8; 32]);
+    let (k1, k2) = ([1_u8; 32], [2_u8; 32]);
+    let (v1, v2) = (vec![1_u8], vec![2_u8]);
+
+    // The entry with `k1` lies in the range, but is hidden in the proof part.
+    let json = json!({
+        "from": min_key,
+        "to": max_key,
+        "entries": [ { "key": k2, "value": v2 } ],
+        "proof": [ { "path": ProofPath::new(&k1), "hash": hash(&v1) } ]
+    });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        IncompleteRange(..) => {}
+        e => panic!("expected incomplete range error, got {}", e),
+    }
+
+    // `k2 < k1` according to the ordering of proof paths, so `k1` is outside the range.
+    let json = json!({
+        "from": min_key,
+        "to": k1,
+        "entries": [ { "key": k2, "value": v2 }, { "key": k1, "value": v1 } ],
+        "proof": []
+    });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        EntryOutOfRange(..) => {}
+        e => panic!("expected entry out of range error, got {}", e),
+    }
+
+    let json = json!({
+        "from": min_key,
+        "to": max_key,
+        "entries": [ { "key": k1, "value": v1 }, { "key": k2, "value": v2 } ],
+        "proof": []
+    });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        InvalidOrdering(..) => {}
+        e => panic!("expected invalid ordering error, got {}", e),
+    }
+
+    let json = json!({
+        "from": min_key,
+        "to": max_key,
+        "entries": [ { "key": k1, "value": v1 }, { "key": k1, "value": v1 } ],
+        "proof": []
+    });
+    let proof: MapRangeProof<[u8; 32], Vec<u8>> = serde_json::from_value(json).unwrap();
+    match proof.check().unwrap_err() {
+        DuplicatePath(..) => {}
+        e => panic!("expected duplicate path error, got {}", e),
+    }
+}
+
 fn build_proof_in_empty_tree(db: Box<dyn Database>) {
     let mut storage = db.fork();
     let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
@@ -581,6 +682,63 @@ fn build_multiproof_in_single_node_tree(db: Box<dyn Database>) {
     check_map_multiproof(proof, keys, &table);
 }
 
+fn build_range_proof_in_empty_tree(db: Box<dyn Database>) {
+    let mut storage = db.fork();
+    let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
+
+    table.put(&[230; 32], vec![1]);
+    table.remove(&[230; 32]);
+
+    let proof = table.get_range_proof([0; 32], [255; 32]);
+    assert_eq!(proof.proof_unchecked(), vec![]);
+    check_map_range_proof(proof, [0; 32], [255; 32], &table);
+}
+
+fn build_range_proof_in_single_node_tree(db: Box<dyn Database>) {
+    let mut storage = db.fork();
+    let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
+
+    table.put(&[230; 32], vec![1]);
+    let proof = table.get_range_proof([0; 32], [255; 32]);
+    assert_eq!(proof.proof_unchecked(), vec![]);
+    check_map_range_proof(proof, [0; 32], [255; 32], &table);
+
+    // `[2; 32] < [230; 32]` according to the ordering of proof paths.
+    let proof = table.get_range_proof([0; 32], [2; 32]);
+    assert_eq!(
+        proof.proof_unchecked(),
+        vec![(ProofPath::new(&[230; 32]), hash(&vec![1]))]
+    );
+    check_map_range_proof(proof, [0; 32], [2; 32], &table);
+}
+
+fn fuzz_insert_build_range_proofs(db: Box<dyn Database>) {
+    let mut rng: XorShiftRng = rand::random();
+    let batch_sizes = (7..9).map(|x| 1 << x);
+
+    for batch_size in batch_sizes {
+        let data = generate_random_data_keys(batch_size, &mut rng);
+        let mut storage = db.fork();
+        let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
+        for &(ref key, ref value) in &data {
+            table.put(key, value.clone());
+        }
+
+        for _ in 0..50 {
+            // Range boundaries are both existing and non-existing keys.
+            let mut from = data[rng.gen_range(0, batch_size)].0;
+            let mut to = [0; KEY_SIZE];
+            rng.fill_bytes(&mut to);
+            if ProofPath::new(&to) < ProofPath::new(&from) {
+                ::std::mem::swap(&mut from, &mut to);
+            }
+
+            let proof = table.get_range_proof(from, to);
+            check_map_range_proof(proof, from, to, &table);
+        }
+    }
+}
+
 fn build_proof_in_complex_tree(db: Box<dyn Database>) {
     let mut storage = db.fork();
     let mut table = ProofMapIndex::new(IDX_NAME, &mut storage);
@@ -1134,6 +1292,79 @@ fn fuzz_insert_after_delete(db: Box<dyn Database>) {
     assert_eq!(index.merkle_root(), saved_hash);
 }
 
+// Checks that batch updates result in the same storage contents as single updates.
+fn batch_updates(db1: Box<dyn Database>, db2: Box<dyn Database>) {
+    fn contents(fork: &Fork) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let mut contents = Vec::new();
+        let mut iter = fork.iter(IDX_NAME, &[]);
+        while let Some((key, value)) = iter.next() {
+            contents.push((key.to_vec(), value.to_vec()));
+        }
+        contents
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut data = generate_random_data(200);
+    let mut storage1 = db1.fork();
+    let mut storage2 = db2.fork();
+
+    // Batches are applied both to an empty index and to an index with existing entries.
+    for chunk in data.chunks(50) {
+        {
+            let mut index1 = ProofMapIndex::new(IDX_NAME, &mut storage1);
+            for item in chunk {
+                index1.put(&item.0, item.1.clone());
+            }
+        }
+        let mut index2 = ProofMapIndex::new(IDX_NAME, &mut storage2);
+        index2.put_all(chunk.iter().cloned());
+        let index1: ProofMapIndex<_, [u8; 32], Vec<u8>> = ProofMapIndex::new(IDX_NAME, &storage1);
+        assert_eq!(index2.merkle_root(), index1.merkle_root());
+    }
+    assert_eq!(contents(&storage1), contents(&storage2));
+
+    // Updates of existing keys, duplicate keys and removals of absent keys.
+    rng.shuffle(&mut data);
+    let absent_keys = generate_random_data(10);
+    {
+        let mut index1 = ProofMapIndex::new(IDX_NAME, &mut storage1);
+        for item in &data[..20] {
+            index1.put(&item.0, vec![1]);
+        }
+        for item in &data[20..60] {
+            index1.remove(&item.0);
+        }
+        for item in &data[..10] {
+            index1.put(&item.0, vec![2]);
+        }
+    }
+    {
+        let mut index2 = ProofMapIndex::new(IDX_NAME, &mut storage2);
+        index2.put_all(data[..20].iter().map(|item| (item.0, vec![1])));
+        index2.remove_all(data[20..60].iter().chain(&absent_keys).map(|item| item.0));
+        index2.put_all(data[..10].iter().map(|item| (item.0, vec![3])));
+        index2.put_all(data[..10].iter().map(|item| (item.0, vec![2])));
+        index2.remove_all(Vec::new());
+        assert_eq!(index2.get(&data[0].0), Some(vec![2]));
+        assert_eq!(index2.get(&data[15].0), Some(vec![1]));
+        assert_eq!(index2.get(&data[30].0), None);
+    }
+    assert_eq!(contents(&storage1), contents(&storage2));
+
+    // Removal of all keys but one and then of all keys.
+    let mut index2 = ProofMapIndex::new(IDX_NAME, &mut storage2);
+    index2.remove_all(data[..60].iter().chain(&data[61..]).map(|item| item.0));
+    assert_eq!(index2.iter().count(), 1);
+    let leaf_root = HashStream::new()
+        .update(ProofPath::new(&data[60].0).as_bytes())
+        .update(data[60].1.hash().as_ref())
+        .hash();
+    assert_eq!(index2.merkle_root(), leaf_root);
+    index2.remove_all(data.iter().map(|item| item.0));
+    assert_eq!(index2.merkle_root(), Hash::zero());
+    assert_eq!(index2.iter().count(), 0);
+}
+
 fn iter(db: Box<dyn Database>) {
     let mut fork = db.fork();
     let mut map_index = ProofMapIndex::new(IDX_NAME, &mut fork);
@@ -1207,6 +1438,42 @@ fn iter(db: Box<dyn Database>) {
         map_index.values_from(&k4).collect::<Vec<u8>>(),
         Vec::<u8>::new()
     );
+
+    assert_eq!(
+        map_index.iter_rev().collect::<Vec<([u8; 32], u8)>>(),
+        vec![(k3, 3), (k2, 2), (k1, 1)]
+    );
+    assert_eq!(
+        map_index
+            .iter_rev_from(&k4)
+            .collect::<Vec<([u8; 32], u8)>>(),
+        vec![(k3, 3), (k2, 2), (k1, 1)]
+    );
+    assert_eq!(
+        map_index
+            .iter_rev_from(&k2)
+            .collect::<Vec<([u8; 32], u8)>>(),
+        vec![(k2, 2), (k1, 1)]
+    );
+    assert_eq!(
+        map_index
+            .iter_rev_from(&k0)
+            .collect::<Vec<([u8; 32], u8)>>(),
+        Vec::<([u8; 32], u8)>::new()
+    );
+    assert_eq!(
+        map_index.keys_rev().collect::<Vec<[u8; 32]>>(),
+        vec![k3, k2, k1]
+    );
+    assert_eq!(
+        map_index.keys_rev_from(&k2).collect::<Vec<[u8; 32]>>(),
+        vec![k2, k1]
+    );
+    assert_eq!(map_index.values_rev().collect::<Vec<u8>>(), vec![3, 2, 1]);
+    assert_eq!(
+        map_index.values_rev_from(&k2).collect::<Vec<u8>>(),
+        vec![2, 1]
+    );
 }
 
 fn tree_with_hashed_key(db: Box<dyn Database>) {
@@ -1323,6 +1590,12 @@ macro_rules! common_tests {
         }
         test_on_db!{test_build_proof_in_complex_tree, build_proof_in_complex_tree}
         test_on_db!{test_build_multiproof_simple, build_multiproof_simple}
+        test_on_db!{test_build_range_proof_in_empty_tree, build_range_proof_in_empty_tree}
+        test_on_db!{
+            test_build_range_proof_in_single_node_tree,
+            build_range_proof_in_single_node_tree
+        }
+        test_on_db!{test_fuzz_insert_build_range_proofs, fuzz_insert_build_range_proofs}
         test_on_db!{
             test_fuzz_insert_build_proofs_in_table_filled_with_hashes,
             fuzz_insert_build_proofs_in_table_filled_with_hashes
@@ -1332,6 +1605,7 @@ macro_rules! common_tests {
         test_on_db!{test_fuzz_delete_build_proofs, fuzz_delete_build_proofs}
         test_on_2dbs!{test_fuzz_delete, fuzz_delete}
         test_on_db!{test_fuzz_insert_after_delete, fuzz_insert_after_delete}
+        test_on_2dbs!{test_batch_updates, batch_updates}
         test_on_db!{test_iter, iter}
         test_on_db!{test_tree_with_hashed_key, tree_with_hashed_key}
     };
@@ -1349,6 +1623,7 @@ mod memorydb_tests {
     common_tests!{}
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
     use std::path::Path;
     use storage::{Database, DbOptions, RocksDB};
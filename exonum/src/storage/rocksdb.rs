@@ -18,11 +18,20 @@
 
 pub use rocksdb::{BlockBasedOptions as RocksBlockOptions, WriteOptions as RocksDBWriteOptions};
 
-use rocksdb::{self, utils::get_cf_names, DBIterator, Options as RocksDbOptions, WriteBatch};
+use rocksdb::{
+    self, checkpoint::Checkpoint, utils::get_cf_names, ColumnFamilyDescriptor, DBCompressionType,
+    DBIterator, Options as RocksDbOptions, WriteBatch,
+};
 
 use std::{error::Error, fmt, iter::Peekable, mem, path::Path, sync::Arc};
 
-use storage::{self, db::Change, Database, DbOptions, Iter, Iterator, Patch, Snapshot};
+use storage::{
+    self, db::Change, CompressionType, Database, DbOptions, Iter, Iterator, Patch, Snapshot,
+    TableStats, WalSyncMode,
+};
+
+// Number of bits per key in bloom filters, which gives about 1% of false positives.
+const BLOOM_FILTER_BITS_PER_KEY: i32 = 10;
 
 impl From<rocksdb::Error> for storage::Error {
     fn from(err: rocksdb::Error) -> Self {
@@ -38,6 +47,7 @@ impl From<rocksdb::Error> for storage::Error {
 /// use different databases.
 pub struct RocksDB {
     db: Arc<rocksdb::DB>,
+    options: DbOptions,
 }
 
 impl DbOptions {
@@ -45,8 +55,52 @@ impl DbOptions {
         let mut defaults = RocksDbOptions::default();
         defaults.create_if_missing(self.create_if_missing);
         defaults.set_max_open_files(self.max_open_files.unwrap_or(-1));
+        defaults.set_compression_type(self.compression_type.into());
+        if let Some(size) = self.write_buffer_size {
+            defaults.set_write_buffer_size(size);
+        }
+        if let Some(number) = self.max_write_buffer_number {
+            defaults.set_max_write_buffer_number(number);
+        }
+        if let Some(jobs) = self.max_background_jobs {
+            defaults.set_max_background_jobs(jobs);
+        }
+        if self.block_cache_size.is_some() || self.bloom_filter {
+            let mut block_opts = RocksBlockOptions::default();
+            if let Some(size) = self.block_cache_size {
+                block_opts.set_lru_cache(size);
+            }
+            if self.bloom_filter {
+                block_opts.set_bloom_filter(BLOOM_FILTER_BITS_PER_KEY, false);
+            }
+            defaults.set_block_based_table_factory(&block_opts);
+        }
         defaults
     }
+
+    fn write_options(&self) -> RocksDBWriteOptions {
+        let mut w_opts = RocksDBWriteOptions::default();
+        match self.wal_sync_mode {
+            WalSyncMode::Async => {}
+            WalSyncMode::Sync => w_opts.set_sync(true),
+            WalSyncMode::Disabled => w_opts.disable_wal(true),
+        }
+        w_opts
+    }
+}
+
+impl From<CompressionType> for DBCompressionType {
+    fn from(compression_type: CompressionType) -> Self {
+        match compression_type {
+            CompressionType::None => DBCompressionType::None,
+            CompressionType::Snappy => DBCompressionType::Snappy,
+            CompressionType::Zlib => DBCompressionType::Zlib,
+            CompressionType::Bz2 => DBCompressionType::Bz2,
+            CompressionType::Lz4 => DBCompressionType::Lz4,
+            CompressionType::Lz4hc => DBCompressionType::Lz4hc,
+            CompressionType::Zstd => DBCompressionType::Zstd,
+        }
+    }
 }
 
 /// A snapshot of a `RocksDB`.
@@ -68,16 +122,28 @@ impl RocksDB {
     /// If the database does not exist at the indicated path and the option
     /// `create_if_missing` is switched on in `DbOptions`, a new database will
     /// be created at the indicated path.
+    ///
+    /// Tuning options, such as the compression type or the block cache size, apply
+    /// to all column families of the database, including the existing ones and the ones
+    /// created later.
     pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> storage::Result<Self> {
         let db = {
             if let Ok(names) = get_cf_names(&path) {
-                let cf_names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
-                rocksdb::DB::open_cf(&options.to_rocksdb(), path, cf_names.as_ref())?
+                // Column families do not inherit the options of the database, so the options
+                // are passed for each of them.
+                let cf_descriptors = names
+                    .iter()
+                    .map(|name| ColumnFamilyDescriptor::new(name.as_str(), options.to_rocksdb()))
+                    .collect();
+                rocksdb::DB::open_cf_descriptors(&options.to_rocksdb(), path, cf_descriptors)?
             } else {
                 rocksdb::DB::open(&options.to_rocksdb(), path)?
             }
         };
-        Ok(Self { db: Arc::new(db) })
+        Ok(Self {
+            db: Arc::new(db),
+            options: *options,
+        })
     }
 
     fn do_merge(&self, patch: Patch, w_opts: &RocksDBWriteOptions) -> storage::Result<()> {
@@ -86,7 +152,7 @@ impl RocksDB {
             let cf = match self.db.cf_handle(&cf_name) {
                 Some(cf) => cf,
                 None => self.db
-                    .create_cf(&cf_name, &DbOptions::default().to_rocksdb())
+                    .create_cf(&cf_name, &self.options.to_rocksdb())
                     .unwrap(),
             };
             for (key, change) in changes {
@@ -109,15 +175,43 @@ impl Database for RocksDB {
     }
 
     fn merge(&self, patch: Patch) -> storage::Result<()> {
-        let w_opts = RocksDBWriteOptions::default();
+        let w_opts = self.options.write_options();
         self.do_merge(patch, &w_opts)
     }
 
     fn merge_sync(&self, patch: Patch) -> storage::Result<()> {
-        let mut w_opts = RocksDBWriteOptions::default();
+        let mut w_opts = self.options.write_options();
+        w_opts.disable_wal(false);
         w_opts.set_sync(true);
         self.do_merge(patch, &w_opts)
     }
+
+    fn create_checkpoint(&self, path: &Path) -> storage::Result<Box<dyn Snapshot>> {
+        Checkpoint::new(&self.db)?.create_checkpoint(path)?;
+        let checkpoint = Self::open(path, &self.options)?;
+        Ok(checkpoint.snapshot())
+    }
+
+    /// Returns estimates provided by `RocksDB` properties of the column family. The size
+    /// includes live data in SST files and the data in memtables.
+    fn table_stats(&self, name: &str) -> TableStats {
+        let cf = match self.db.cf_handle(name) {
+            Some(cf) => cf,
+            None => return TableStats::default(),
+        };
+        let property = |property: &str| {
+            self.db
+                .property_int_value_cf(cf, property)
+                .ok()
+                .and_then(|value| value)
+                .unwrap_or(0)
+        };
+        TableStats {
+            key_count: property("rocksdb.estimate-num-keys"),
+            size: property("rocksdb.estimate-live-data-size")
+                + property("rocksdb.cur-size-all-mem-tables"),
+        }
+    }
 }
 
 impl Snapshot for RocksDBSnapshot {
@@ -146,6 +240,29 @@ impl Snapshot for RocksDBSnapshot {
             value: None,
         })
     }
+
+    fn iter_rev<'a>(&'a self, name: &str, to: Option<&[u8]>) -> Iter<'a> {
+        use rocksdb::{Direction, IteratorMode};
+        let mode = match to {
+            Some(to) => IteratorMode::From(to, Direction::Reverse),
+            None => IteratorMode::End,
+        };
+        let mut iter = match self.db.cf_handle(name) {
+            Some(cf) => self.snapshot.iterator_cf(cf, mode).unwrap().peekable(),
+            None => self.snapshot.iterator(IteratorMode::End).peekable(),
+        };
+        // Reverse seek may be positioned at `to` itself, which must be excluded.
+        if let Some(to) = to {
+            while iter.peek().map_or(false, |&(ref key, _)| &key[..] >= to) {
+                iter.next();
+            }
+        }
+        Box::new(RocksDBIterator {
+            iter,
+            key: None,
+            value: None,
+        })
+    }
 }
 
 impl Iterator for RocksDBIterator {
@@ -93,10 +93,13 @@ pub struct SparseListIndex<T, V> {
 
 /// Returns an iterator over the items of a `SparseListIndex`.
 ///
-/// This struct is created by the [`iter`] method on [`SparseListIndex`].
-/// See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`] or [`iter_rev_from`]
+/// method on [`SparseListIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.SparseListIndex.html#method.iter
+/// [`iter_from`]: struct.SparseListIndex.html#method.iter_from
+/// [`iter_rev`]: struct.SparseListIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.SparseListIndex.html#method.iter_rev_from
 /// [`SparseListIndex`]: struct.SparseListIndex.html
 #[derive(Debug)]
 pub struct SparseListIndexIter<'a, V> {
@@ -105,10 +108,11 @@ pub struct SparseListIndexIter<'a, V> {
 
 /// Returns an iterator over the indices of a `SparseListIndex`.
 ///
-/// This struct is created by the [`indices`] method on [`SparseListIndex`].
+/// This struct is created by the [`indices`] or [`indices_rev`] method on [`SparseListIndex`].
 /// See its documentation for more.
 ///
 /// [`indices`]: struct.SparseListIndex.html#method.indices
+/// [`indices_rev`]: struct.SparseListIndex.html#method.indices_rev
 /// [`SparseListIndex`]: struct.SparseListIndex.html
 #[derive(Debug)]
 pub struct SparseListIndexKeys<'a> {
@@ -117,10 +121,11 @@ pub struct SparseListIndexKeys<'a> {
 
 /// Returns an iterator over the values of a `SparseListIndex`.
 ///
-/// This struct is created by the [`values`] method on [`SparseListIndex`].
+/// This struct is created by the [`values`] or [`values_rev`] method on [`SparseListIndex`].
 /// See its documentation for details.
 ///
 /// [`values`]: struct.SparseListIndex.html#method.values
+/// [`values_rev`]: struct.SparseListIndex.html#method.values_rev
 /// [`SparseListIndex`]: struct.SparseListIndex.html
 #[derive(Debug)]
 pub struct SparseListIndexValues<'a, V> {
@@ -392,6 +397,100 @@ where
             base_iter: self.base.iter_from(&(), &from),
         }
     }
+
+    /// Returns an iterator over the list in reverse order. The iterator element type is (u64, V).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, SparseListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = SparseListIndex::new("name", &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    /// index.remove(3);
+    ///
+    /// let items: Vec<_> = index.iter_rev().collect();
+    /// assert_eq!(items, vec![(4, 5), (2, 3), (1, 2), (0, 1)]);
+    /// ```
+    pub fn iter_rev(&self) -> SparseListIndexIter<V> {
+        SparseListIndexIter {
+            base_iter: self.base.iter_rev_range(&(), None::<&u64>, &0_u64),
+        }
+    }
+
+    /// Returns an iterator over the indices of the 'SparseListIndex' in reverse order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, SparseListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = SparseListIndex::new("name", &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    /// index.remove(3);
+    ///
+    /// let items: Vec<_> = index.indices_rev().collect();
+    /// assert_eq!(items, vec![4, 2, 1, 0]);
+    /// ```
+    pub fn indices_rev(&self) -> SparseListIndexKeys {
+        SparseListIndexKeys {
+            base_iter: self.base.iter_rev_range(&(), None::<&u64>, &0_u64),
+        }
+    }
+
+    /// Returns an iterator over the values of the 'SparseListIndex' in reverse order of indices.
+    /// The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, SparseListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = SparseListIndex::new("name", &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    /// index.remove(3);
+    ///
+    /// let items: Vec<_> = index.values_rev().collect();
+    /// assert_eq!(items, vec![5, 3, 2, 1]);
+    /// ```
+    pub fn values_rev(&self) -> SparseListIndexValues<V> {
+        SparseListIndexValues {
+            base_iter: self.base.iter_rev_range(&(), None::<&u64>, &0_u64),
+        }
+    }
+
+    /// Returns an iterator over the list in reverse order starting from the specified position
+    /// (inclusive). The iterator element type is (u64, V).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, SparseListIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let mut fork = db.fork();
+    /// let mut index = SparseListIndex::new("name", &mut fork);
+    ///
+    /// index.extend([1, 2, 3, 4, 5].iter().cloned());
+    /// index.remove(3);
+    ///
+    /// let items: Vec<_> = index.iter_rev_from(3).collect();
+    /// assert_eq!(items, vec![(2, 3), (1, 2), (0, 1)]);
+    /// ```
+    pub fn iter_rev_from(&self, from: u64) -> SparseListIndexIter<V> {
+        SparseListIndexIter {
+            base_iter: self.base.iter_rev_range(&(), Some(&from), &0_u64),
+        }
+    }
 }
 
 impl<'a, V> SparseListIndex<&'a mut Fork, V>
@@ -746,6 +845,23 @@ mod tests {
             vec![0_u64, 3, 4]
         );
         assert_eq!(list_index.values().collect::<Vec<u8>>(), vec![1_u8, 2, 3]);
+
+        assert_eq!(
+            list_index.iter_rev().collect::<Vec<(u64, u8)>>(),
+            vec![(4, 3), (3, 2), (0, 1)]
+        );
+        assert_eq!(
+            list_index.iter_rev_from(2).collect::<Vec<(u64, u8)>>(),
+            vec![(0, 1)]
+        );
+        assert_eq!(
+            list_index.indices_rev().collect::<Vec<u64>>(),
+            vec![4_u64, 3, 0]
+        );
+        assert_eq!(
+            list_index.values_rev().collect::<Vec<u8>>(),
+            vec![3_u8, 2, 1]
+        );
     }
 
     mod memorydb_tests {
@@ -774,6 +890,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rocksdb")]
     mod rocksdb_tests {
         use std::path::Path;
         use storage::{Database, DbOptions, RocksDB};
@@ -0,0 +1,175 @@
+// Copyright 2018 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Statistics of the storage usage by indices.
+
+use super::{indexes_metadata::{self, IndexType}, Database, Snapshot};
+
+/// The number of entries and the size of a single column family in the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableStats {
+    /// Number of keys in the column family.
+    pub key_count: u64,
+    /// Total size of keys and values in the column family in bytes.
+    pub size: u64,
+}
+
+impl TableStats {
+    /// Computes exact statistics of the column family with the given name by iterating
+    /// over its entries in the snapshot.
+    pub fn from_snapshot(snapshot: &dyn Snapshot, name: &str) -> Self {
+        let mut stats = Self::default();
+        let mut iter = snapshot.iter(name, &[]);
+        while let Some((key, value)) = iter.next() {
+            stats.add_entry(key, value);
+        }
+        stats
+    }
+
+    pub(crate) fn add_entry(&mut self, key: &[u8], value: &[u8]) {
+        self.key_count += 1;
+        self.size += (key.len() + value.len()) as u64;
+    }
+}
+
+/// Storage usage statistics of an index or an index family.
+///
+/// For an index family, the figures cover all members of the family.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexStats {
+    /// Name of the index.
+    pub name: String,
+    /// Type of the index.
+    pub index_type: IndexType,
+    /// `true` if the name refers to an index family.
+    pub is_family: bool,
+    /// Number of keys in the index.
+    pub key_count: u64,
+    /// Size of keys and values in the index in bytes.
+    pub size: u64,
+}
+
+/// Returns storage usage statistics of each index recorded in the indexes metadata,
+/// ordered by index names.
+///
+/// The accuracy of the figures depends on the database; see [`Database::table_stats`]
+/// for details. Indices that have never been changed are absent in the metadata and are
+/// not included in the result.
+///
+/// [`Database::table_stats`]: trait.Database.html#method.table_stats
+///
+/// # Examples
+///
+/// ```
+/// use exonum::storage::{storage_stats, Database, ListIndex, MemoryDB};
+///
+/// let db = MemoryDB::new();
+/// let mut fork = db.fork();
+/// ListIndex::new("list", &mut fork).extend(vec![1_u8, 2, 3]);
+/// db.merge(fork.into_patch()).unwrap();
+///
+/// let stats = storage_stats(&db);
+/// assert_eq!(stats.len(), 1);
+/// assert_eq!(stats[0].name, "list");
+/// // Three list items and the length of the list.
+/// assert_eq!(stats[0].key_count, 4);
+/// ```
+pub fn storage_stats(db: &dyn Database) -> Vec<IndexStats> {
+    let snapshot = db.snapshot();
+    indexes_metadata::indexes(&*snapshot)
+        .into_iter()
+        .map(|(name, index_type, is_family)| {
+            let table = db.table_stats(&name);
+            IndexStats {
+                name,
+                index_type,
+                is_family,
+                key_count: table.key_count,
+                size: table.size,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use storage::{Entry, MapIndex, MemoryDB, ProofListIndex};
+
+    fn fill_db(db: &dyn Database) {
+        let mut fork = db.fork();
+        Entry::new("entry", &mut fork).set(1_u64);
+        for id in 0..2_u8 {
+            MapIndex::new_in_family("family", &id, &mut fork).put(&1_u8, vec![0_u8; 10]);
+        }
+        {
+            let mut list = ProofListIndex::new("list", &mut fork);
+            list.push(1_u8);
+            list.clear();
+        }
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    #[test]
+    fn memorydb_stats() {
+        let db = MemoryDB::new();
+        fill_db(&db);
+
+        let stats = storage_stats(&db);
+        assert_eq!(
+            stats,
+            vec![
+                IndexStats {
+                    name: "entry".to_owned(),
+                    index_type: IndexType::Entry,
+                    is_family: false,
+                    key_count: 1,
+                    size: 8,
+                },
+                IndexStats {
+                    name: "family".to_owned(),
+                    index_type: IndexType::Map,
+                    is_family: true,
+                    key_count: 2,
+                    size: 2 * (2 + 10),
+                },
+                IndexStats {
+                    name: "list".to_owned(),
+                    index_type: IndexType::ProofList,
+                    is_family: false,
+                    key_count: 0,
+                    size: 0,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn rocksdb_stats() {
+        use storage::{DbOptions, RocksDB};
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("exonum_rocksdb_stats").unwrap();
+        let db = RocksDB::open(dir.path(), &DbOptions::default()).unwrap();
+        fill_db(&db);
+
+        let stats = storage_stats(&db);
+        let names: Vec<_> = stats.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["entry", "family", "list"]);
+        // RocksDB figures are estimates, so only check that written data is accounted for.
+        assert!(stats[0].key_count > 0 && stats[0].size > 0);
+        assert!(stats[1].key_count > 0 && stats[1].size > 0);
+    }
+}
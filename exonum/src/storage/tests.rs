@@ -113,6 +113,66 @@ fn fork_iter<T: Database>(db: T) {
     assert_iter(&fork, 0, &[(10, 10), (20, 20), (30, 30)]);
 }
 
+fn fork_iter_rev<T: Database>(db: T) {
+    let mut fork = db.fork();
+
+    fork.put(IDX_NAME, vec![10], vec![10]);
+    fork.put(IDX_NAME, vec![20], vec![20]);
+    fork.put(IDX_NAME, vec![30], vec![30]);
+    db.merge(fork.into_patch()).unwrap();
+
+    fn assert_iter_rev(fork: &Fork, to: Option<u8>, assumed: &[(u8, u8)]) {
+        let mut values = Vec::new();
+
+        let to = to.map(|to| vec![to]);
+        let mut iter = fork.iter_rev(IDX_NAME, to.as_ref().map(Vec::as_slice));
+        while let Some((k, v)) = iter.next() {
+            values.push((k[0], v[0]));
+        }
+        assert_eq!(values, assumed);
+    }
+
+    // Stored
+    let mut fork = db.fork();
+    assert_iter_rev(&fork, None, &[(30, 30), (20, 20), (10, 10)]);
+    assert_iter_rev(&fork, Some(35), &[(30, 30), (20, 20), (10, 10)]);
+    assert_iter_rev(&fork, Some(30), &[(20, 20), (10, 10)]);
+    assert_iter_rev(&fork, Some(21), &[(20, 20), (10, 10)]);
+    assert_iter_rev(&fork, Some(10), &[]);
+
+    // Inserted
+    fork.put(IDX_NAME, vec![5], vec![5]);
+    fork.put(IDX_NAME, vec![25], vec![25]);
+    fork.put(IDX_NAME, vec![35], vec![35]);
+    assert_iter_rev(
+        &fork,
+        None,
+        &[(35, 35), (30, 30), (25, 25), (20, 20), (10, 10), (5, 5)],
+    );
+    assert_iter_rev(&fork, Some(30), &[(25, 25), (20, 20), (10, 10), (5, 5)]);
+
+    // Replaced
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![10], vec![11]);
+    fork.put(IDX_NAME, vec![30], vec![31]);
+    assert_iter_rev(&fork, None, &[(30, 31), (20, 20), (10, 11)]);
+
+    // Deleted and MissDeleted
+    let mut fork = db.fork();
+    fork.remove(IDX_NAME, vec![20]);
+    fork.remove(IDX_NAME, vec![15]);
+    fork.remove(IDX_NAME, vec![35]);
+    assert_iter_rev(&fork, None, &[(30, 30), (10, 10)]);
+    fork.remove(IDX_NAME, vec![30]);
+    assert_iter_rev(&fork, Some(31), &[(10, 10)]);
+
+    // Other tables are not affected
+    let mut fork = db.fork();
+    fork.put("a", vec![1], vec![1]);
+    fork.put("z", vec![1], vec![1]);
+    assert_iter_rev(&fork, None, &[(30, 30), (20, 20), (10, 10)]);
+}
+
 fn changelog<T: Database>(db: T) {
     let mut fork = db.fork();
 
@@ -179,6 +239,135 @@ fn changelog<T: Database>(db: T) {
     assert_eq!(fork.get(IDX_NAME, &[4]), None);
 }
 
+fn savepoints<T: Database>(db: T) {
+    fn values(fork: &Fork) -> Vec<(u8, u8)> {
+        let mut values = Vec::new();
+        let mut iter = fork.iter(IDX_NAME, &[]);
+        while let Some((k, v)) = iter.next() {
+            values.push((k[0], v[0]));
+        }
+        values
+    }
+
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![1], vec![1]);
+    fork.put(IDX_NAME, vec![2], vec![2]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.checkpoint();
+    fork.put(IDX_NAME, vec![3], vec![3]);
+
+    let outer = fork.savepoint();
+    fork.put(IDX_NAME, vec![1], vec![10]);
+    let inner = fork.savepoint();
+    fork.remove(IDX_NAME, vec![2]);
+    fork.put(IDX_NAME, vec![4], vec![4]);
+    assert_eq!(values(&fork), &[(1, 10), (3, 3), (4, 4)]);
+
+    fork.rollback_to(inner);
+    assert_eq!(values(&fork), &[(1, 10), (2, 2), (3, 3)]);
+
+    // Released changes are rolled back with the outer savepoint.
+    let inner = fork.savepoint();
+    fork.put(IDX_NAME, vec![5], vec![5]);
+    fork.release(inner);
+    assert_eq!(values(&fork), &[(1, 10), (2, 2), (3, 3), (5, 5)]);
+    fork.rollback_to(outer);
+    assert_eq!(values(&fork), &[(1, 1), (2, 2), (3, 3)]);
+
+    // Rolling back to a savepoint removes the nested ones.
+    let outer = fork.savepoint();
+    let inner = fork.savepoint();
+    fork.remove_by_prefix(IDX_NAME, None);
+    assert_eq!(values(&fork), &[]);
+    fork.rollback_to(outer);
+    assert_eq!(values(&fork), &[(1, 1), (2, 2), (3, 3)]);
+    let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+        fork.release(inner);
+    }));
+    assert!(result.is_err());
+
+    let savepoint = fork.savepoint();
+    fork.put(IDX_NAME, vec![4], vec![4]);
+    fork.remove_by_prefix(IDX_NAME, Some(&vec![3]));
+    assert_eq!(values(&fork), &[(1, 1), (2, 2), (4, 4)]);
+    fork.rollback_to(savepoint);
+    assert_eq!(values(&fork), &[(1, 1), (2, 2), (3, 3)]);
+
+    // Committing the checkpoint keeps the changes made after the savepoints.
+    fork.savepoint();
+    fork.put(IDX_NAME, vec![4], vec![4]);
+    fork.commit();
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    assert_eq!(values(&fork), &[(1, 1), (2, 2), (3, 3), (4, 4)]);
+
+    // Rolling back the checkpoint discards all savepoints.
+    fork.checkpoint();
+    fork.savepoint();
+    fork.put(IDX_NAME, vec![5], vec![5]);
+    fork.rollback();
+    assert_eq!(values(&fork), &[(1, 1), (2, 2), (3, 3), (4, 4)]);
+    fork.checkpoint();
+    fork.commit();
+}
+
+fn history<T: Database>(db: T) {
+    assert!(db.snapshot_at(0).is_none());
+
+    for version in 0..4 {
+        let mut fork = db.fork();
+        fork.put(IDX_NAME, vec![version], vec![version]);
+        fork.put(IDX_NAME, vec![100], vec![version]);
+        if version == 2 {
+            fork.remove(IDX_NAME, vec![0]);
+        }
+        fork.save_version(u64::from(version));
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    // Changes made without saving a version are not tracked.
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![200], vec![200]);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot_at(0).unwrap();
+    assert_eq!(snapshot.get(IDX_NAME, &[0]), Some(vec![0]));
+    assert_eq!(snapshot.get(IDX_NAME, &[1]), None);
+    assert_eq!(snapshot.get(IDX_NAME, &[100]), Some(vec![0]));
+
+    let snapshot = db.snapshot_at(2).unwrap();
+    assert_eq!(snapshot.get(IDX_NAME, &[0]), None);
+    assert_eq!(snapshot.get(IDX_NAME, &[1]), Some(vec![1]));
+    assert_eq!(snapshot.get(IDX_NAME, &[2]), Some(vec![2]));
+    assert_eq!(snapshot.get(IDX_NAME, &[3]), None);
+    assert_eq!(snapshot.get(IDX_NAME, &[100]), Some(vec![2]));
+
+    let mut values = Vec::new();
+    let mut iter = snapshot.iter(IDX_NAME, &[]);
+    while let Some((k, v)) = iter.next() {
+        values.push((k[0], v[0]));
+    }
+    assert_eq!(values, &[(1, 1), (2, 2), (100, 2), (200, 200)]);
+
+    let snapshot = db.snapshot_at(3).unwrap();
+    assert_eq!(snapshot.get(IDX_NAME, &[3]), Some(vec![3]));
+    assert_eq!(snapshot.get(IDX_NAME, &[100]), Some(vec![3]));
+    assert!(db.snapshot_at(4).is_none());
+
+    // A gap in versions restarts the history.
+    let mut fork = db.fork();
+    fork.put(IDX_NAME, vec![100], vec![10]);
+    fork.save_version(10);
+    db.merge(fork.into_patch()).unwrap();
+
+    assert!(db.snapshot_at(3).is_none());
+    let snapshot = db.snapshot_at(10).unwrap();
+    assert_eq!(snapshot.get(IDX_NAME, &[100]), Some(vec![10]));
+}
+
 mod memorydb_tests {
     use super::super::MemoryDB;
 
@@ -191,15 +380,437 @@ mod memorydb_tests {
         super::fork_iter(memorydb_database());
     }
 
+    #[test]
+    fn test_memory_fork_iter_rev() {
+        super::fork_iter_rev(memorydb_database());
+    }
+
     #[test]
     fn test_memory_changelog() {
         super::changelog(memorydb_database());
     }
+
+    #[test]
+    fn test_memory_savepoints() {
+        super::savepoints(memorydb_database());
+    }
+
+    #[test]
+    fn test_memory_history() {
+        super::history(memorydb_database());
+    }
+}
+
+mod cached_tests {
+    use super::super::{CachedDB, Database, MemoryDB};
+
+    fn cached_database() -> CachedDB {
+        CachedDB::new(Box::new(MemoryDB::new()), 1 << 20)
+    }
+
+    #[test]
+    fn test_cached_fork_iter() {
+        super::fork_iter(cached_database());
+    }
+
+    #[test]
+    fn test_cached_fork_iter_rev() {
+        super::fork_iter_rev(cached_database());
+    }
+
+    #[test]
+    fn test_cached_changelog() {
+        super::changelog(cached_database());
+    }
+
+    #[test]
+    fn test_cached_savepoints() {
+        super::savepoints(cached_database());
+    }
+
+    #[test]
+    fn test_cached_history() {
+        super::history(cached_database());
+    }
+
+    #[test]
+    fn test_cache_stats_through_trait_object() {
+        let db: Box<dyn Database> = Box::new(cached_database());
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+        db.snapshot().get("table", &[1]);
+        assert_eq!(db.cache_stats().unwrap().misses, 1);
+
+        let db: Box<dyn Database> = Box::new(MemoryDB::new());
+        assert_eq!(db.cache_stats(), None);
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let db = cached_database();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let old_snapshot = db.snapshot();
+        assert_eq!(old_snapshot.get("table", &[1]), Some(vec![1]));
+        assert_eq!(old_snapshot.get("table", &[2]), None);
+        assert_eq!(old_snapshot.get("table", &[2]), None);
+
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![2]);
+        fork.put("table", vec![2], vec![2]);
+        db.merge(fork.into_patch()).unwrap();
+
+        // The old snapshot reads the state it has been created for.
+        assert_eq!(old_snapshot.get("table", &[1]), Some(vec![1]));
+        assert_eq!(old_snapshot.get("table", &[2]), None);
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get("table", &[1]), Some(vec![2]));
+        assert_eq!(snapshot.get("table", &[2]), Some(vec![2]));
+        assert!(snapshot.contains("table", &[2]));
+
+        let mut fork = db.fork();
+        fork.remove("table", vec![1]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        assert_eq!(db.snapshot().get("table", &[1]), None);
+        assert_eq!(db.snapshot().get("table", &[2]), Some(vec![2]));
+
+        let stats = db.cache_stats().unwrap();
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 5);
+        assert_eq!(stats.entries, 2);
+        assert!((stats.hit_rate() - 3.0 / 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let db = CachedDB::new(Box::new(MemoryDB::new()), 1000);
+        let mut fork = db.fork();
+        for i in 0..10_u8 {
+            fork.put("table", vec![i], vec![i; 100]);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        for i in 0..10_u8 {
+            assert_eq!(snapshot.get("table", &[i]), Some(vec![i; 100]));
+        }
+        let stats = db.cache_stats().unwrap();
+        assert!(stats.size <= 1000);
+        assert!(stats.entries < 10);
+
+        // The most recently read entries are kept in the cache.
+        assert_eq!(snapshot.get("table", &[9]), Some(vec![9; 100]));
+        assert_eq!(db.cache_stats().unwrap().hits, 1);
+        assert_eq!(snapshot.get("table", &[0]), Some(vec![0; 100]));
+        assert_eq!(db.cache_stats().unwrap().hits, 1);
+
+        // Entries larger than the cache are not cached.
+        let mut fork = db.fork();
+        fork.put("table", vec![100], vec![0; 2000]);
+        db.merge(fork.into_patch()).unwrap();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get("table", &[100]), Some(vec![0; 2000]));
+        assert!(db.cache_stats().unwrap().size <= 1000);
+    }
+}
+
+mod logdb_tests {
+    use super::super::{Database, DbOptions, LogDB};
+    use std::{
+        fs::{self, OpenOptions}, path::Path,
+    };
+    use tempdir::TempDir;
+
+    fn logdb_database(path: &Path) -> LogDB {
+        LogDB::open(path, &DbOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_logdb_fork_iter() {
+        let dir = TempDir::new("exonum_logdb_iter").unwrap();
+        super::fork_iter(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_fork_iter_rev() {
+        let dir = TempDir::new("exonum_logdb_iter_rev").unwrap();
+        super::fork_iter_rev(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_changelog() {
+        let dir = TempDir::new("exonum_logdb_changelog").unwrap();
+        super::changelog(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_savepoints() {
+        let dir = TempDir::new("exonum_logdb_savepoints").unwrap();
+        super::savepoints(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_history() {
+        let dir = TempDir::new("exonum_logdb_history").unwrap();
+        super::history(logdb_database(dir.path()));
+    }
+
+    #[test]
+    fn test_logdb_missing() {
+        let dir = TempDir::new("exonum_logdb_missing").unwrap();
+        let options = DbOptions {
+            create_if_missing: false,
+            ..DbOptions::default()
+        };
+        assert!(LogDB::open(dir.path().join("db"), &options).is_err());
+    }
+
+    #[test]
+    fn test_logdb_recovery() {
+        let dir = TempDir::new("exonum_logdb_recovery").unwrap();
+        let db = logdb_database(dir.path());
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1]);
+        fork.put("table", vec![2], vec![2]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![3]);
+        fork.remove("table", vec![2]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        drop(db);
+
+        let db = logdb_database(dir.path());
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get("table", &[1]), Some(vec![3]));
+        assert_eq!(snapshot.get("table", &[2]), None);
+        drop(db);
+
+        // The last record is written partially.
+        let file = OpenOptions::new()
+            .write(true)
+            .open(dir.path().join("data.log"))
+            .unwrap();
+        let len = file.metadata().unwrap().len();
+        file.set_len(len - 1).unwrap();
+        drop(file);
+
+        let db = logdb_database(dir.path());
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get("table", &[1]), Some(vec![1]));
+        assert_eq!(snapshot.get("table", &[2]), Some(vec![2]));
+
+        // New records are written after the discarded one.
+        let mut fork = db.fork();
+        fork.put("table", vec![3], vec![3]);
+        db.merge(fork.into_patch()).unwrap();
+        drop(db);
+        let db = logdb_database(dir.path());
+        assert_eq!(db.snapshot().get("table", &[3]), Some(vec![3]));
+        assert_eq!(db.table_stats("table").key_count, 3);
+    }
+
+    #[test]
+    fn test_logdb_corrupted_record() {
+        let dir = TempDir::new("exonum_logdb_corrupted").unwrap();
+        let log_path = dir.path().join("data.log");
+        let db = logdb_database(dir.path());
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1; 100]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        let first_record_end = log_path.metadata().unwrap().len();
+        let mut fork = db.fork();
+        fork.put("table", vec![2], vec![2; 100]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        drop(db);
+
+        // A corrupted record followed by other records is not discarded.
+        let bytes = fs::read(&log_path).unwrap();
+        let mut corrupted = bytes.clone();
+        corrupted[first_record_end as usize - 1] ^= 1;
+        fs::write(&log_path, &corrupted).unwrap();
+        assert!(LogDB::open(dir.path(), &DbOptions::default()).is_err());
+        assert_eq!(fs::read(&log_path).unwrap(), corrupted);
+
+        // A corrupted last record is discarded.
+        let mut corrupted = bytes.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        fs::write(&log_path, &corrupted).unwrap();
+        let db = logdb_database(dir.path());
+        assert_eq!(db.snapshot().get("table", &[1]), Some(vec![1; 100]));
+        assert_eq!(db.snapshot().get("table", &[2]), None);
+        assert_eq!(log_path.metadata().unwrap().len(), first_record_end);
+    }
+
+    #[test]
+    fn test_logdb_corrupted_record_length() {
+        let dir = TempDir::new("exonum_logdb_corrupted_length").unwrap();
+        let log_path = dir.path().join("data.log");
+        let db = logdb_database(dir.path());
+        let header_len = log_path.metadata().unwrap().len();
+        for i in 0..3 {
+            let mut fork = db.fork();
+            fork.put("table", vec![i], vec![i; 100]);
+            db.merge_sync(fork.into_patch()).unwrap();
+        }
+        drop(db);
+
+        // The length of the first record points beyond the end of the log.
+        let mut corrupted = fs::read(&log_path).unwrap();
+        corrupted[header_len as usize + 3] ^= 1;
+        fs::write(&log_path, &corrupted).unwrap();
+        assert!(LogDB::open(dir.path(), &DbOptions::default()).is_err());
+        assert_eq!(fs::read(&log_path).unwrap(), corrupted);
+    }
+
+    #[test]
+    fn test_logdb_snapshot_versions() {
+        let dir = TempDir::new("exonum_logdb_versions").unwrap();
+        let db = logdb_database(dir.path());
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1]);
+        fork.put("table", vec![2], vec![2]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![3]);
+        fork.remove("table", vec![2]);
+        fork.put("table", vec![4], vec![4]);
+        db.merge(fork.into_patch()).unwrap();
+        let second_snapshot = db.snapshot();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![5]);
+        db.merge(fork.into_patch()).unwrap();
+
+        assert_eq!(snapshot.get("table", &[1]), Some(vec![1]));
+        assert_eq!(snapshot.get("table", &[2]), Some(vec![2]));
+        assert!(!snapshot.contains("table", &[4]));
+        let mut iter = snapshot.iter("table", &[]);
+        assert_eq!(iter.next(), Some((&[1][..], &[1][..])));
+        assert_eq!(iter.next(), Some((&[2][..], &[2][..])));
+        assert_eq!(iter.next(), None);
+        let mut iter = second_snapshot.iter_rev("table", None);
+        assert_eq!(iter.next(), Some((&[4][..], &[4][..])));
+        assert_eq!(iter.next(), Some((&[1][..], &[3][..])));
+        assert_eq!(iter.next(), None);
+
+        // Previous versions are removed once the snapshots are dropped.
+        drop(snapshot);
+        drop(second_snapshot);
+        let mut fork = db.fork();
+        fork.put("table", vec![5], vec![5]);
+        db.merge(fork.into_patch()).unwrap();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get("table", &[1]), Some(vec![5]));
+        assert_eq!(snapshot.get("table", &[2]), None);
+        assert_eq!(db.table_stats("table").key_count, 3);
+    }
+
+    #[test]
+    fn test_logdb_iter_with_concurrent_merges() {
+        let dir = TempDir::new("exonum_logdb_iter_merges").unwrap();
+        let db = logdb_database(dir.path());
+        let mut fork = db.fork();
+        for i in 0..10_u8 {
+            fork.put("table", vec![i * 2], vec![i]);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let mut iter = snapshot.iter("table", &[4]);
+        assert_eq!(iter.peek(), Some((&[4][..], &[2][..])));
+        assert_eq!(iter.next(), Some((&[4][..], &[2][..])));
+
+        // Entries merged after the snapshot has been created are not visible.
+        let mut fork = db.fork();
+        fork.put("table", vec![5], vec![100]);
+        fork.put("table", vec![6], vec![100]);
+        fork.remove("table", vec![8]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let keys: Vec<u8> = (0..6).map(|_| iter.next().unwrap().0[0]).collect();
+        assert_eq!(keys, vec![6, 8, 10, 12, 14, 16]);
+        assert_eq!(iter.next(), Some((&[18][..], &[9][..])));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.peek(), None);
+
+        let mut iter = snapshot.iter_rev("table", Some(&[7]));
+        assert_eq!(iter.next(), Some((&[6][..], &[3][..])));
+        assert_eq!(iter.next(), Some((&[4][..], &[2][..])));
+
+        let snapshot = db.snapshot();
+        let mut iter = snapshot.iter("table", &[5]);
+        assert_eq!(iter.next(), Some((&[5][..], &[100][..])));
+        assert_eq!(iter.next(), Some((&[6][..], &[100][..])));
+        assert_eq!(iter.next(), Some((&[10][..], &[5][..])));
+        assert!(snapshot.iter("missing", &[]).next().is_none());
+    }
+
+    #[test]
+    fn test_logdb_compaction() {
+        let dir = TempDir::new("exonum_logdb_compaction").unwrap();
+        let log_len = || dir.path().join("data.log").metadata().unwrap().len();
+        let db = logdb_database(dir.path());
+        for i in 0..100_u8 {
+            let mut fork = db.fork();
+            fork.put("table", vec![i % 10], vec![i; 100]);
+            fork.put("other", vec![i], vec![i]);
+            if i % 2 == 0 {
+                fork.remove("other", vec![i]);
+            }
+            db.merge(fork.into_patch()).unwrap();
+        }
+        let old_snapshot = db.snapshot();
+        let len = log_len();
+
+        db.compact().unwrap();
+        assert!(log_len() < len / 5);
+        for snapshot in &[old_snapshot, db.snapshot()] {
+            assert_eq!(snapshot.get("table", &[1]), Some(vec![91; 100]));
+            assert_eq!(snapshot.get("other", &[1]), Some(vec![1]));
+            assert_eq!(snapshot.get("other", &[2]), None);
+            let mut iter = snapshot.iter("table", &[5]);
+            assert_eq!(iter.next(), Some((&[5][..], &[95; 100][..])));
+        }
+
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![1]);
+        db.merge(fork.into_patch()).unwrap();
+        drop(db);
+
+        let db = logdb_database(dir.path());
+        assert_eq!(db.snapshot().get("table", &[1]), Some(vec![1]));
+        assert_eq!(db.table_stats("table").key_count, 10);
+        assert_eq!(db.table_stats("other").key_count, 50);
+    }
+
+    #[test]
+    fn test_logdb_checkpoint() {
+        let dir = TempDir::new("exonum_logdb_checkpoint").unwrap();
+        let db = logdb_database(&dir.path().join("db"));
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![2]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let checkpoint = db.create_checkpoint(&dir.path().join("checkpoint"))
+            .unwrap();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![3]);
+        db.merge(fork.into_patch()).unwrap();
+
+        assert_eq!(checkpoint.get("table", &[1]), Some(vec![2]));
+        assert_eq!(db.snapshot().get("table", &[1]), Some(vec![3]));
+    }
 }
 
+#[cfg(feature = "rocksdb")]
 mod rocksdb_tests {
-    use super::super::{DbOptions, RocksDB};
-    use std::path::Path;
+    use super::super::{CompressionType, DbOptions, RocksDB, WalSyncMode};
+    use std::{fs, path::Path};
     use storage::{Database, ListIndex, Snapshot};
     use tempdir::TempDir;
 
@@ -208,6 +819,24 @@ mod rocksdb_tests {
         RocksDB::open(path, &options).unwrap()
     }
 
+    // Returns the sections of the latest options file of the database that describe
+    // the column family with the given name.
+    fn column_family_options(path: &Path, name: &str) -> String {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|file_name| file_name.starts_with("OPTIONS-"))
+            .collect();
+        files.sort();
+        let options = fs::read_to_string(path.join(files.last().unwrap())).unwrap();
+        let section_suffix = format!("\"{}\"]", name);
+        options
+            .split("\n[")
+            .filter(|section| section.contains(&section_suffix))
+            .collect::<Vec<_>>()
+            .join("\n[")
+    }
+
     #[test]
     fn test_rocksdb_fork_iter() {
         let dir = TempDir::new("exonum_rocksdb1").unwrap();
@@ -215,6 +844,13 @@ mod rocksdb_tests {
         super::fork_iter(rocksdb_database(path));
     }
 
+    #[test]
+    fn test_rocksdb_fork_iter_rev() {
+        let dir = TempDir::new("exonum_rocksdb_iter_rev").unwrap();
+        let path = dir.path();
+        super::fork_iter_rev(rocksdb_database(path));
+    }
+
     #[test]
     fn test_rocksdb_changelog() {
         let dir = TempDir::new("exonum_rocksdb2").unwrap();
@@ -222,6 +858,91 @@ mod rocksdb_tests {
         super::changelog(rocksdb_database(path));
     }
 
+    #[test]
+    fn test_rocksdb_savepoints() {
+        let dir = TempDir::new("exonum_rocksdb_savepoints").unwrap();
+        let path = dir.path();
+        super::savepoints(rocksdb_database(path));
+    }
+
+    #[test]
+    fn test_rocksdb_history() {
+        let dir = TempDir::new("exonum_rocksdb_history").unwrap();
+        let path = dir.path();
+        super::history(rocksdb_database(path));
+    }
+
+    #[test]
+    fn test_rocksdb_tuned_options() {
+        let dir = TempDir::new("exonum_rocksdb_tuned").unwrap();
+        let options = DbOptions {
+            compression_type: CompressionType::Lz4,
+            block_cache_size: Some(1 << 20),
+            write_buffer_size: Some(1 << 20),
+            max_write_buffer_number: Some(4),
+            bloom_filter: true,
+            wal_sync_mode: WalSyncMode::Disabled,
+            max_background_jobs: Some(4),
+            ..DbOptions::default()
+        };
+        let db = RocksDB::open(dir.path(), &options).unwrap();
+        super::changelog(db);
+
+        let db = RocksDB::open(dir.path(), &options).unwrap();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![2]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        drop(db);
+
+        let db = RocksDB::open(dir.path(), &options).unwrap();
+        assert_eq!(db.snapshot().get("table", &[1]), Some(vec![2]));
+    }
+
+    #[test]
+    fn test_rocksdb_options_of_existing_column_families() {
+        let dir = TempDir::new("exonum_rocksdb_reopen").unwrap();
+        let db = rocksdb_database(dir.path());
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![2]);
+        db.merge_sync(fork.into_patch()).unwrap();
+        drop(db);
+
+        let options = DbOptions {
+            compression_type: CompressionType::Lz4,
+            write_buffer_size: Some(1 << 20),
+            bloom_filter: true,
+            ..DbOptions::default()
+        };
+        let db = RocksDB::open(dir.path(), &options).unwrap();
+        let table_options = column_family_options(dir.path(), "table");
+        assert!(table_options.contains("compression=kLZ4Compression"));
+        assert!(table_options.contains("write_buffer_size=1048576"));
+        assert!(table_options.contains("filter_policy=rocksdb.BuiltinBloomFilter"));
+        assert_eq!(db.snapshot().get("table", &[1]), Some(vec![2]));
+    }
+
+    #[test]
+    fn test_rocksdb_checkpoint() {
+        let dir = TempDir::new("exonum_rocksdb_checkpoint").unwrap();
+        let db = rocksdb_database(&dir.path().join("db"));
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![2]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let checkpoint = db.create_checkpoint(&dir.path().join("checkpoint"))
+            .unwrap();
+        let mut fork = db.fork();
+        fork.put("table", vec![1], vec![3]);
+        db.merge(fork.into_patch()).unwrap();
+
+        assert_eq!(checkpoint.get("table", &[1]), Some(vec![2]));
+        assert_eq!(db.snapshot().get("table", &[1]), Some(vec![3]));
+        assert!(
+            db.create_checkpoint(&dir.path().join("checkpoint"))
+                .is_err()
+        );
+    }
+
     #[ignore]
     #[test]
     fn test_multiple_patch() {
@@ -40,11 +40,13 @@ pub struct ValueSetIndex<T, V> {
 
 /// Returns an iterator over the items of a `ValueSetIndex`.
 ///
-/// This struct is created by the [`iter`] or
-/// [`iter_from`] method on [`ValueSetIndex`]. See its documentation for details.
+/// This struct is created by the [`iter`], [`iter_from`], [`iter_rev`] or
+/// [`iter_rev_from`] method on [`ValueSetIndex`]. See its documentation for details.
 ///
 /// [`iter`]: struct.ValueSetIndex.html#method.iter
 /// [`iter_from`]: struct.ValueSetIndex.html#method.iter_from
+/// [`iter_rev`]: struct.ValueSetIndex.html#method.iter_rev
+/// [`iter_rev_from`]: struct.ValueSetIndex.html#method.iter_rev_from
 /// [`ValueSetIndex`]: struct.ValueSetIndex.html
 #[derive(Debug)]
 pub struct ValueSetIndexIter<'a, V> {
@@ -53,11 +55,13 @@ pub struct ValueSetIndexIter<'a, V> {
 
 /// Returns an iterator over the hashes of items of a `ValueSetIndex`.
 ///
-/// This struct is created by the [`hashes`] or
-/// [`hashes_from`] method on [`ValueSetIndex`]. See its documentation for details.
+/// This struct is created by the [`hashes`], [`hashes_from`], [`hashes_rev`] or
+/// [`hashes_rev_from`] method on [`ValueSetIndex`]. See its documentation for details.
 ///
 /// [`hashes`]: struct.ValueSetIndex.html#method.iter
 /// [`hashes_from`]: struct.ValueSetIndex.html#method.iter_from
+/// [`hashes_rev`]: struct.ValueSetIndex.html#method.hashes_rev
+/// [`hashes_rev_from`]: struct.ValueSetIndex.html#method.hashes_rev_from
 /// [`ValueSetIndex`]: struct.ValueSetIndex.html
 #[derive(Debug)]
 pub struct ValueSetIndexHashes<'a> {
@@ -266,6 +270,105 @@ where
             base_iter: self.base.iter_from(&(), from),
         }
     }
+
+    /// Returns an iterator visiting all elements in descending order of their hashes.
+    /// The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name  = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ValueSetIndex<_, u8> = ValueSetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.iter_rev() {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn iter_rev(&self) -> ValueSetIndexIter<V> {
+        ValueSetIndexIter {
+            base_iter: self.base.iter_rev(&()),
+        }
+    }
+
+    /// Returns an iterator visiting all elements in descending order of their hashes starting
+    /// from the specified hash of a value (inclusive). The iterator element type is V.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ValueSetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name  = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ValueSetIndex<_, u8> = ValueSetIndex::new(name, &snapshot);
+    ///
+    /// let hash = Hash::default();
+    ///
+    /// for val in index.iter_rev_from(&hash) {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn iter_rev_from(&self, from: &Hash) -> ValueSetIndexIter<V> {
+        ValueSetIndexIter {
+            base_iter: self.base.iter_rev_from(&(), from),
+        }
+    }
+
+    /// Returns an iterator visiting hashes of all elements in descending order. The iterator
+    /// element type is [Hash](../../crypto/struct.Hash.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ValueSetIndex};
+    ///
+    /// let db = MemoryDB::new();
+    /// let name  = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ValueSetIndex<_, u8> = ValueSetIndex::new(name, &snapshot);
+    ///
+    /// for val in index.hashes_rev() {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn hashes_rev(&self) -> ValueSetIndexHashes {
+        ValueSetIndexHashes {
+            base_iter: self.base.iter_rev(&()),
+        }
+    }
+
+    /// Returns an iterator visiting hashes of all elements in descending order starting from
+    /// the specified hash (inclusive). The iterator element type is
+    /// [Hash](../../crypto/struct.Hash.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum::storage::{MemoryDB, Database, ValueSetIndex};
+    /// use exonum::crypto::Hash;
+    ///
+    /// let db = MemoryDB::new();
+    /// let name  = "name";
+    /// let snapshot = db.snapshot();
+    /// let index: ValueSetIndex<_, u8> = ValueSetIndex::new(name, &snapshot);
+    ///
+    /// let hash = Hash::default();
+    ///
+    /// for val in index.hashes_rev_from(&hash) {
+    ///     println!("{:?}", val);
+    /// }
+    /// ```
+    pub fn hashes_rev_from(&self, from: &Hash) -> ValueSetIndexHashes {
+        ValueSetIndexHashes {
+            base_iter: self.base.iter_rev_from(&(), from),
+        }
+    }
 }
 
 impl<'a, V> ValueSetIndex<&'a mut Fork, V>
@@ -173,6 +173,71 @@ fn test_explorer_pool_transaction() {
     assert_eq!(tx_info.content().raw(), tx_alice.raw());
 }
 
+#[test]
+fn test_explorer_at_height() {
+    let mut blockchain = create_blockchain();
+
+    let (pk_alice, key_alice) = crypto::gen_keypair();
+    let tx_alice = CreateWallet::new(&pk_alice, "Alice", &key_alice);
+    create_block(&mut blockchain, vec![tx_alice.clone().into()]);
+    create_block(&mut blockchain, vec![]);
+
+    let explorer = BlockchainExplorer::at_height(&blockchain, Height(0)).unwrap();
+    assert_eq!(explorer.height(), Height(0));
+    assert!(explorer.block(Height(1)).is_none());
+    assert!(explorer.transaction(&tx_alice.hash()).is_none());
+
+    let explorer = BlockchainExplorer::at_height(&blockchain, Height(1)).unwrap();
+    assert_eq!(explorer.height(), Height(1));
+    let block = explorer.block(Height(1)).unwrap();
+    assert_eq!(block.len(), 1);
+    assert_eq!(block.precommits().len(), 1);
+    assert!(explorer.transaction(&tx_alice.hash()).unwrap().is_committed());
+
+    let latest = BlockchainExplorer::new(&blockchain);
+    let explorer = BlockchainExplorer::at_height(&blockchain, Height(2)).unwrap();
+    assert_eq!(
+        explorer.block(Height(2)).unwrap().header(),
+        latest.block(Height(2)).unwrap().header()
+    );
+    assert!(BlockchainExplorer::at_height(&blockchain, Height(3)).is_none());
+}
+
+#[test]
+fn test_explorer_pruned_blocks() {
+    let mut blockchain = create_blockchain();
+    blockchain.set_keep_blocks(Some(2));
+
+    let (pk_alice, key_alice) = crypto::gen_keypair();
+    let tx_alice = CreateWallet::new(&pk_alice, "Alice", &key_alice);
+    create_block(&mut blockchain, vec![tx_alice.clone().into()]);
+
+    {
+        let explorer = BlockchainExplorer::new(&blockchain);
+        assert!(!explorer.is_pruned(Height(0)));
+        assert!(explorer.transaction(&tx_alice.hash()).is_some());
+    }
+
+    create_block(&mut blockchain, vec![]);
+    create_block(&mut blockchain, vec![]);
+
+    let explorer = BlockchainExplorer::new(&blockchain);
+    assert!(explorer.is_pruned(Height(1)));
+    assert!(!explorer.is_pruned(Height(2)));
+    assert!(explorer.transaction(&tx_alice.hash()).is_none());
+    assert!(explorer.is_transaction_pruned(&tx_alice.hash()));
+    assert!(explorer.block_with_txs(Height(1)).is_none());
+
+    let block = explorer.block(Height(1)).unwrap();
+    assert_eq!(block.len(), 1);
+    assert_eq!(*block.transaction_hashes(), [tx_alice.hash()]);
+    assert!(block.precommits().is_empty());
+    assert!(block.transaction(0).is_none());
+
+    assert!(BlockchainExplorer::at_height(&blockchain, Height(1)).is_none());
+    assert!(BlockchainExplorer::at_height(&blockchain, Height(2)).is_some());
+}
+
 fn tx_generator() -> Box<Iterator<Item = Box<Transaction>>> {
     Box::new((0..).map(|i| {
         let (pk, key) = crypto::gen_keypair();
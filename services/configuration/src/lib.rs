@@ -120,6 +120,11 @@ impl blockchain::Service for Service {
         ConfigurationTransactions::tx_from_raw(raw).map(Into::into)
     }
 
+    fn storage_namespaces(&self) -> Vec<String> {
+        // Accepted configurations are committed to the core schema.
+        vec![SERVICE_NAME.to_owned(), "core".to_owned()]
+    }
+
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
         api::PublicApi::wire(builder);
         api::PrivateApi::wire(builder);
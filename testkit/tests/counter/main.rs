@@ -634,6 +634,76 @@ fn test_explorer_transaction_info() {
     );
 }
 
+#[test]
+fn test_explorer_multisig() {
+    use exonum::api::node::private::multisig::{MultisigQuery, MultisigSubmission};
+    use exonum::crypto::CryptoHash;
+    use exonum::messages::{MultisigStatement, PartialSignature, SignatureSet, ThresholdPolicy};
+
+    let (_testkit, api) = init_testkit();
+    let keys: Vec<_> = (0..3).map(|_| crypto::gen_keypair()).collect();
+    let policy = ThresholdPolicy::new(2, keys.iter().map(|&(pk, _)| pk).collect());
+    let (submitter, _) = crypto::gen_keypair();
+    let payload_hash = crypto::hash(b"payload");
+    let statement =
+        MultisigStatement::for_message::<TxIncrement>(&submitter, &policy, 1, &payload_hash);
+    let sign = |i: usize| PartialSignature::sign(&statement, &keys[i].0, &keys[i].1);
+
+    let error = api.private(ApiKind::Explorer)
+        .query(&MultisigQuery::new(statement.hash()))
+        .get::<SignatureSet>("v1/multisig")
+        .unwrap_err();
+    assert_matches!(error, ApiError::NotFound(_));
+
+    // The signatures are collected only by the private API.
+    let error = api.public(ApiKind::Explorer)
+        .query(&MultisigQuery::new(statement.hash()))
+        .get::<SignatureSet>("v1/multisig")
+        .unwrap_err();
+    assert_matches!(error, ApiError::NotFound(_));
+
+    let submission = MultisigSubmission {
+        statement: statement.clone(),
+        policy: policy.clone(),
+        signatures: vec![sign(0)],
+    };
+    let set: SignatureSet = api.private(ApiKind::Explorer)
+        .query(&submission)
+        .post("v1/multisig")
+        .unwrap();
+    assert_eq!(set.signatures(), &[sign(0)][..]);
+    assert!(!set.is_complete());
+
+    // Signatures of another statement are rejected.
+    let other_statement =
+        MultisigStatement::for_message::<TxIncrement>(&submitter, &policy, 2, &payload_hash);
+    let other_signature = PartialSignature::sign(&other_statement, &keys[1].0, &keys[1].1);
+    let forged = MultisigSubmission {
+        signatures: vec![other_signature],
+        ..submission.clone()
+    };
+    let error = api.private(ApiKind::Explorer)
+        .query(&forged)
+        .post::<SignatureSet>("v1/multisig")
+        .unwrap_err();
+    assert_matches!(error, ApiError::BadRequest(_));
+
+    let submission = MultisigSubmission {
+        signatures: vec![sign(1)],
+        ..submission
+    };
+    let _: SignatureSet = api.private(ApiKind::Explorer)
+        .query(&submission)
+        .post("v1/multisig")
+        .unwrap();
+    let set: SignatureSet = api.private(ApiKind::Explorer)
+        .query(&MultisigQuery::new(statement.hash()))
+        .get("v1/multisig")
+        .unwrap();
+    assert!(set.is_complete());
+    assert!(policy.verify(&statement, set.signatures()));
+}
+
 #[test]
 fn test_explorer_transaction_statuses() {
     use exonum::blockchain::TransactionResult;